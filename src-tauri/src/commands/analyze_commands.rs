@@ -0,0 +1,484 @@
+//! 分析命令
+
+use chrono::DateTime;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::commands::file_commands::AppState;
+use crate::parser::callstack::{detect_callstacks, CallStack};
+use crate::parser::highlight::{normalize_path, DEFAULT_HIGHLIGHTER};
+use crate::parser::patterns::HIGHLIGHT_PATH;
+use crate::parser::{
+    Encoding, LogEntry, LogLevel, LogParser, MessageGroup, ParserConfig, TimeBucket,
+};
+use crate::search::{DefaultTokenizer, Tokenizer};
+use crate::streaming::buffered;
+use crate::streaming::encoding::decode_lines_lossy;
+
+/// 全文件扫描的输入: 当前文件的路径、编码和解析配置
+///
+/// 只在取出时短暂持有 AppState 的锁，扫描期间不锁定行读取器，不阻塞 load_chunk 和滚动
+fn scan_target(state: &Mutex<AppState>) -> Result<(PathBuf, Encoding, ParserConfig), String> {
+    let state = state.lock().map_err(|e| e.to_string())?;
+    let (file_path, index) = state.file_and_index()?;
+    Ok((file_path, index.encoding, state.parser_config.clone()))
+}
+
+/// 用独立的文件句柄从头逐行解析 (不经过行读取器和块缓存)
+fn file_entries<'a>(
+    path: &Path,
+    encoding: Encoding,
+    parser_config: &'a ParserConfig,
+) -> std::io::Result<impl Iterator<Item = std::io::Result<LogEntry>> + 'a> {
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(encoding.bom_len() as u64))?;
+
+    Ok(decode_lines_lossy(buffered(file), encoding)
+        .enumerate()
+        .map(move |(i, line)| {
+            let (line, invalid_utf8) = line?;
+            let mut entry = LogParser::parse_line_with(parser_config, i as u64 + 1, &line);
+            entry.has_invalid_utf8 = invalid_utf8;
+            Ok(entry)
+        }))
+}
+
+/// 按归一化消息聚合日志 (数字、路径、UUID 及词内编号视为相同)，返回出现次数最多的 top_n 组
+///
+/// level_filter 为空时统计所有级别；续行不计入。流式扫描整个文件 (不锁定行读取器)，在后台线程执行
+#[tauri::command(async)]
+pub fn group_messages(
+    level_filter: Vec<LogLevel>,
+    top_n: usize,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<MessageGroup>, String> {
+    let (file_path, encoding, parser_config) = scan_target(&state)?;
+    let entries = file_entries(&file_path, encoding, &parser_config).map_err(|e| e.to_string())?;
+    group_entries(entries, &level_filter, top_n).map_err(|e| e.to_string())
+}
+
+/// 找出文件中的崩溃调用栈 (Fatal error / Assertion failed 及其后的栈帧)
+///
+/// 流式扫描整个文件 (不锁定行读取器)，在后台线程执行
+#[tauri::command(async)]
+pub fn get_callstacks(state: tauri::State<'_, Mutex<AppState>>) -> Result<Vec<CallStack>, String> {
+    let (file_path, encoding, parser_config) = scan_target(&state)?;
+    let entries = file_entries(&file_path, encoding, &parser_config).map_err(|e| e.to_string())?;
+    detect_callstacks(entries).map_err(|e| e.to_string())
+}
+
+/// 列出文件中所有 ensure 失败行 (非致命，与 get_callstacks 的致命错误分开分诊)
+///
+/// 流式扫描整个文件 (不锁定行读取器)，在后台线程执行
+#[tauri::command(async)]
+pub fn list_ensures(state: tauri::State<'_, Mutex<AppState>>) -> Result<Vec<LogEntry>, String> {
+    let (file_path, encoding, parser_config) = scan_target(&state)?;
+    let entries = file_entries(&file_path, encoding, &parser_config).map_err(|e| e.to_string())?;
+    entries
+        .filter(|entry| entry.as_ref().map_or(true, |entry| entry.is_ensure))
+        .collect::<std::io::Result<_>>()
+        .map_err(|e| e.to_string())
+}
+
+/// UE 资源文件扩展名 (小写)
+const ASSET_EXTENSIONS: &[&str] = &[
+    "uasset", "umap", "uexp", "ubulk", "uptnl", "pak", "utoc", "ucas",
+];
+
+/// 提取文件中出现的所有路径，返回 (路径, 出现的行数)，按行数降序
+///
+/// dedupe 为 true 时按归一化路径合并 (`C:\A\..\B` 与 `C:/B` 视为同一路径)；
+/// asset_only 为 true 时只保留资源文件 (.uasset / .umap 等)。
+/// 流式扫描整个文件 (不锁定行读取器)，在后台线程执行
+#[tauri::command(async)]
+pub fn extract_paths(
+    dedupe: bool,
+    asset_only: bool,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<(String, u64)>, String> {
+    let (file_path, encoding, parser_config) = scan_target(&state)?;
+    let entries = file_entries(&file_path, encoding, &parser_config).map_err(|e| e.to_string())?;
+    collect_paths(entries, dedupe, asset_only).map_err(|e| e.to_string())
+}
+
+/// 统计路径，同一行中重复出现的路径只计一次；次数相同时按路径字典序
+fn collect_paths(
+    entries: impl Iterator<Item = std::io::Result<LogEntry>>,
+    dedupe: bool,
+    asset_only: bool,
+) -> std::io::Result<Vec<(String, u64)>> {
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    let mut seen = HashSet::new();
+
+    for entry in entries {
+        let entry = entry?;
+        seen.clear();
+        for m in HIGHLIGHT_PATH.find_iter(&entry.raw) {
+            let path = m.as_str();
+            if asset_only && !is_asset_path(path) {
+                continue;
+            }
+            let key = if dedupe {
+                normalize_path(path)
+            } else {
+                path.to_string()
+            };
+            if seen.insert(key.clone()) {
+                *counts.entry(key).or_insert(0) += 1;
+            }
+        }
+    }
+
+    let mut paths: Vec<(String, u64)> = counts.into_iter().collect();
+    paths.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    Ok(paths)
+}
+
+/// 是否为资源文件路径 (扩展名不区分大小写)
+fn is_asset_path(path: &str) -> bool {
+    path.rsplit_once('.')
+        .map(|(_, ext)| {
+            !ext.contains(['\\', '/'])
+                && ASSET_EXTENSIONS
+                    .iter()
+                    .any(|known| ext.eq_ignore_ascii_case(known))
+        })
+        .unwrap_or(false)
+}
+
+/// 聚合条目，按次数降序 (次数相同时先出现的在前) 取前 top_n 组
+fn group_entries(
+    entries: impl Iterator<Item = std::io::Result<LogEntry>>,
+    levels: &[LogLevel],
+    top_n: usize,
+) -> std::io::Result<Vec<MessageGroup>> {
+    let mut groups: HashMap<String, MessageGroup> = HashMap::new();
+
+    for entry in entries {
+        let entry = entry?;
+        if entry.is_continuation || (!levels.is_empty() && !levels.contains(&entry.level)) {
+            continue;
+        }
+
+        let message = entry.message.as_deref().unwrap_or(&entry.raw);
+        groups
+            .entry(normalize_message(message))
+            .and_modify(|group| group.count += 1)
+            .or_insert_with_key(|normalized| MessageGroup {
+                normalized_message: normalized.clone(),
+                count: 1,
+                first_line: entry.line_number,
+                sample: entry.raw.to_string(),
+            });
+    }
+
+    let mut groups: Vec<MessageGroup> = groups.into_values().collect();
+    groups.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| a.first_line.cmp(&b.first_line))
+    });
+    groups.truncate(top_n);
+    Ok(groups)
+}
+
+/// 归一化消息: 高亮器替换数字、路径和 UUID 后，再把词元内部的数字
+/// (如 BP_Enemy_C_2、30s 中不在单词边界上的数字) 替换为 `<number>`
+fn normalize_message(message: &str) -> String {
+    let masked = DEFAULT_HIGHLIGHTER.mask(message);
+    let mut normalized = String::with_capacity(masked.len());
+    let mut last = 0;
+
+    for token in DefaultTokenizer.tokenize(&masked) {
+        if !token.text.bytes().any(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        normalized.push_str(&masked[last..token.start]);
+        let mut in_number = false;
+        for c in token.text.chars() {
+            match (c.is_ascii_digit(), in_number) {
+                (true, false) => normalized.push_str("<number>"),
+                (true, true) => {}
+                (false, _) => normalized.push(c),
+            }
+            in_number = c.is_ascii_digit();
+        }
+        last = token.end;
+    }
+    normalized.push_str(&masked[last..]);
+    normalized
+}
+
+/// 按固定时间窗口统计各类别的条目数 (按时间先后排列)
+///
+/// 窗口按 bucket_seconds 对齐；没有时间戳的行计入上一个带时间戳的行所在的窗口，
+/// 第一个时间戳之前的行计入第一个窗口。文件中没有可解析的时间戳时返回空列表
+#[tauri::command(async)]
+pub fn category_timeline(
+    bucket_seconds: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<TimeBucket>, String> {
+    if bucket_seconds == 0 {
+        return Err("bucket_seconds must be greater than 0".to_string());
+    }
+
+    let (file_path, encoding, parser_config) = scan_target(&state)?;
+    let entries = file_entries(&file_path, encoding, &parser_config).map_err(|e| e.to_string())?;
+    timeline_of(entries, bucket_seconds).map_err(|e| e.to_string())
+}
+
+/// 统计时间线，续行和没有类别的行不计入
+fn timeline_of(
+    entries: impl Iterator<Item = std::io::Result<LogEntry>>,
+    bucket_seconds: u64,
+) -> std::io::Result<Vec<TimeBucket>> {
+    let bucket_millis = (bucket_seconds as i64).saturating_mul(1000);
+    let mut buckets: BTreeMap<i64, HashMap<String, u64>> = BTreeMap::new();
+    // 第一个时间戳之前的计数
+    let mut pending: HashMap<String, u64> = HashMap::new();
+    let mut current: Option<i64> = None;
+
+    for entry in entries {
+        let entry = entry?;
+        if let Some(millis) = entry
+            .timestamp
+            .as_deref()
+            .and_then(LogParser::timestamp_millis)
+        {
+            let start = millis - millis.rem_euclid(bucket_millis);
+            if current.is_none() {
+                buckets.insert(start, std::mem::take(&mut pending));
+            }
+            current = Some(start);
+        }
+
+        let Some(category) = entry.category.filter(|_| !entry.is_continuation) else {
+            continue;
+        };
+        let counts = match current {
+            Some(start) => buckets.entry(start).or_default(),
+            None => &mut pending,
+        };
+        *counts.entry(category.to_string()).or_insert(0) += 1;
+    }
+
+    Ok(buckets
+        .into_iter()
+        .map(|(start, counts)| TimeBucket {
+            start_timestamp: format_timestamp(start),
+            counts,
+        })
+        .collect())
+}
+
+/// Unix 毫秒 -> UE 时间戳文本
+fn format_timestamp(millis: i64) -> String {
+    DateTime::from_timestamp_millis(millis)
+        .map(|dt| dt.format("%Y.%m.%d-%H.%M.%S:%3f").to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::{index_file, LineReader};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_scan_without_reader_lock() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogCore: Critical: Out of memory 1")?;
+        writeln!(temp_file, "LogCore: Critical: Out of memory 2")?;
+        writeln!(temp_file, "LogInit: Display: Ready")?;
+        temp_file.flush()?;
+
+        let index = index_file(temp_file.path())?;
+        let reader = std::sync::Arc::new(Mutex::new(LineReader::from_index(
+            temp_file.path(),
+            index.clone(),
+        )?));
+        let state = Mutex::new(AppState {
+            current_file: Some(temp_file.path().to_path_buf()),
+            current_index: Some(std::sync::Arc::new(index)),
+            line_reader: Some(reader.clone()),
+            ..Default::default()
+        });
+        state
+            .lock()
+            .unwrap()
+            .parser_config
+            .level_aliases
+            .insert("Critical".to_string(), LogLevel::Error);
+
+        // 行读取器被占用 (如正在加载块) 时扫描不受影响
+        let _busy = reader.lock().unwrap();
+        let (file_path, encoding, parser_config) = scan_target(&state).unwrap();
+        let entries = file_entries(&file_path, encoding, &parser_config)?;
+        let groups = group_entries(entries, &[LogLevel::Error], 10)?;
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].count, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_group_messages() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Starting")?;
+        for i in 0..5 {
+            writeln!(
+                temp_file,
+                "LogStreaming: Warning: Loading asset {}",
+                i * 111
+            )?;
+        }
+        writeln!(temp_file, "  continuation 1")?;
+        writeln!(temp_file, r"LogNet: Error: Missing C:\Game\A.uasset")?;
+        writeln!(temp_file, r"LogNet: Error: Missing C:\Game\B.uasset")?;
+        writeln!(temp_file, "LogNet: Error: Timeout")?;
+        writeln!(
+            temp_file,
+            "LogActor: Error: Destroyed BP_Enemy_C_2 after 30s"
+        )?;
+        writeln!(
+            temp_file,
+            "LogActor: Error: Destroyed BP_Enemy_C_17 after 5s"
+        )?;
+        temp_file.flush()?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let levels = [LogLevel::Warning, LogLevel::Error];
+        let groups = group_entries(reader.entries()?, &levels, 10)?;
+        let summary: Vec<(&str, u64, u64)> = groups
+            .iter()
+            .map(|g| (g.normalized_message.as_str(), g.count, g.first_line))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("Loading asset <number>", 5, 2),
+                ("Missing <path>", 2, 8),
+                ("Destroyed BP_Enemy_C_<number> after <number>s", 2, 11),
+                ("Timeout", 1, 10),
+            ]
+        );
+        assert_eq!(groups[0].sample, "LogStreaming: Warning: Loading asset 0");
+
+        let top = group_entries(reader.entries()?, &[], 1)?;
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].count, 5);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extract_paths() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(
+            temp_file,
+            r"LogStreaming: Warning: Failed to load C:\Game\Content\Hero.uasset"
+        )?;
+        writeln!(
+            temp_file,
+            r"LogStreaming: Warning: Retry C:\Game\Content\Hero.uasset from C:\Game\Content\Hero.uasset"
+        )?;
+        writeln!(
+            temp_file,
+            r"LogStreaming: Display: Loaded C:\Game\Maps\..\Content\Hero.UASSET"
+        )?;
+        writeln!(temp_file, r"LogInit: Display: Log dir C:\Game\Saved\Logs")?;
+        writeln!(temp_file, r"LogLoad: Display: Map C:\Game\Maps\Entry.umap")?;
+        writeln!(temp_file, "LogTemp: Display: No paths here")?;
+        temp_file.flush()?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let paths = collect_paths(reader.entries()?, false, false)?;
+        assert_eq!(
+            paths,
+            vec![
+                (r"C:\Game\Content\Hero.uasset".to_string(), 2),
+                (r"C:\Game\Maps\..\Content\Hero.UASSET".to_string(), 1),
+                (r"C:\Game\Maps\Entry.umap".to_string(), 1),
+                (r"C:\Game\Saved\Logs".to_string(), 1),
+            ]
+        );
+
+        let deduped = collect_paths(reader.entries()?, true, true)?;
+        assert_eq!(
+            deduped,
+            vec![
+                ("C:/Game/Content/Hero.uasset".to_string(), 2),
+                ("C:/Game/Content/Hero.UASSET".to_string(), 1),
+                ("C:/Game/Maps/Entry.umap".to_string(), 1),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_category_timeline() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "Log file open, 02/14/26 11:33:35")?;
+        writeln!(temp_file, "LogInit: Display: Before any timestamp")?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.33.56:070][  0]LogInit: Display: Start"
+        )?;
+        writeln!(temp_file, "LogNet: Display: No timestamp")?;
+        writeln!(temp_file, "  continuation")?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.34.10:000][  1]LogNet: Warning: Later"
+        )?;
+        // 会话重启后时间回退，仍按时间先后排列
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.20.05:000][  0]LogInit: Display: Restart"
+        )?;
+        temp_file.flush()?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let buckets = timeline_of(reader.entries()?, 60)?;
+        let starts: Vec<&str> = buckets.iter().map(|b| b.start_timestamp.as_str()).collect();
+        assert_eq!(
+            starts,
+            vec![
+                "2026.02.14-03.20.00:000",
+                "2026.02.14-03.33.00:000",
+                "2026.02.14-03.34.00:000"
+            ]
+        );
+        assert_eq!(
+            buckets[0].counts,
+            HashMap::from([("LogInit".to_string(), 1)])
+        );
+        // 文件头 (LogFile) 和第一个时间戳之前的行计入第一个窗口
+        assert_eq!(buckets[1].counts["LogInit"], 2);
+        assert_eq!(buckets[1].counts["LogFile"], 1);
+        assert_eq!(buckets[1].counts["LogNet"], 1);
+        assert_eq!(
+            buckets[2].counts,
+            HashMap::from([("LogNet".to_string(), 1)])
+        );
+
+        let mut untimed = NamedTempFile::new()?;
+        writeln!(untimed, "LogInit: Display: Line 1")?;
+        untimed.flush()?;
+        let index = index_file(untimed.path())?;
+        let mut reader = LineReader::from_index(untimed.path(), index)?;
+        assert!(timeline_of(reader.entries()?, 60)?.is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+//! 书签命令
+//!
+//! 书签按文件路径保存在 AppState 中，每次修改后写入 <logpath>.bookmarks.json
+
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::commands::file_commands::AppState;
+use crate::parser::Bookmark;
+
+/// 读取文件的书签 sidecar，不存在或无法读取时返回空列表
+pub fn load_bookmarks(log_path: &Path) -> Vec<Bookmark> {
+    let sidecar = Bookmark::sidecar_path(log_path);
+    match Bookmark::load_all(&sidecar) {
+        Ok(bookmarks) => bookmarks,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+        Err(e) => {
+            log::warn!("Failed to read bookmarks {}: {}", sidecar.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+/// 为当前文件添加书签 (行号已有书签时更新备注)
+#[tauri::command]
+pub fn add_bookmark(
+    line_number: u64,
+    note: String,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    add_to_state(&mut state, line_number, note)
+}
+
+/// 删除当前文件某行的书签，返回是否存在该书签
+#[tauri::command]
+pub fn remove_bookmark(
+    line_number: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<bool, String> {
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    remove_from_state(&mut state, line_number)
+}
+
+/// 列出当前文件的书签 (按行号升序)
+#[tauri::command]
+pub fn list_bookmarks(state: tauri::State<'_, Mutex<AppState>>) -> Result<Vec<Bookmark>, String> {
+    let state = state.lock().map_err(|e| e.to_string())?;
+    let file_path = state.current_file.as_ref().ok_or("No file opened")?;
+    Ok(state.bookmarks.get(file_path).cloned().unwrap_or_default())
+}
+
+fn add_to_state(state: &mut AppState, line_number: u64, note: String) -> Result<(), String> {
+    let (file_path, index) = state.file_and_index()?;
+    if line_number == 0 || line_number > index.total_lines {
+        return Err(format!(
+            "Line {} is out of range (1-{})",
+            line_number, index.total_lines
+        ));
+    }
+
+    let bookmarks = state.bookmarks.entry(file_path.clone()).or_default();
+    match bookmarks.binary_search_by_key(&line_number, |b| b.line_number) {
+        Ok(pos) => bookmarks[pos].note = note,
+        Err(pos) => bookmarks.insert(pos, Bookmark { line_number, note }),
+    }
+
+    save_bookmarks(&file_path, bookmarks)
+}
+
+fn remove_from_state(state: &mut AppState, line_number: u64) -> Result<bool, String> {
+    let file_path = state.current_file.clone().ok_or("No file opened")?;
+    let Some(bookmarks) = state.bookmarks.get_mut(&file_path) else {
+        return Ok(false);
+    };
+
+    let Ok(pos) = bookmarks.binary_search_by_key(&line_number, |b| b.line_number) else {
+        return Ok(false);
+    };
+    bookmarks.remove(pos);
+
+    save_bookmarks(&file_path, bookmarks)?;
+    Ok(true)
+}
+
+fn save_bookmarks(log_path: &Path, bookmarks: &[Bookmark]) -> Result<(), String> {
+    Bookmark::save_all(Bookmark::sidecar_path(log_path), bookmarks)
+        .map_err(|e| format!("Failed to save bookmarks: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::index_file;
+    use std::io::Write;
+    use std::sync::Arc;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_bookmarks_persisted() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=10 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+        temp_file.flush()?;
+        let path = temp_file.path().to_path_buf();
+        let sidecar = Bookmark::sidecar_path(&path);
+
+        let mut state = AppState {
+            current_file: Some(path.clone()),
+            current_index: Some(Arc::new(index_file(&path)?)),
+            ..Default::default()
+        };
+
+        add_to_state(&mut state, 7, "crash here".to_string()).unwrap();
+        add_to_state(&mut state, 3, "first".to_string()).unwrap();
+        add_to_state(&mut state, 7, "updated".to_string()).unwrap();
+        assert!(add_to_state(&mut state, 0, String::new()).is_err());
+        assert!(add_to_state(&mut state, 11, String::new()).is_err());
+
+        let saved = load_bookmarks(&path);
+        assert_eq!(saved, state.bookmarks[&path]);
+        let lines: Vec<(u64, &str)> = saved
+            .iter()
+            .map(|b| (b.line_number, b.note.as_str()))
+            .collect();
+        assert_eq!(lines, vec![(3, "first"), (7, "updated")]);
+
+        assert!(remove_from_state(&mut state, 3).unwrap());
+        assert!(!remove_from_state(&mut state, 3).unwrap());
+        assert_eq!(load_bookmarks(&path).len(), 1);
+
+        std::fs::remove_file(sidecar)?;
+        assert!(load_bookmarks(&path).is_empty());
+
+        Ok(())
+    }
+}
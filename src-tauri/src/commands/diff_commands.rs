@@ -0,0 +1,22 @@
+//! 比较命令
+
+use crate::commands::file_commands::resolve_log_path;
+use crate::diff;
+use crate::parser::DiffResult;
+
+/// 逐行比较两个日志文件 (与当前打开的文件无关)
+///
+/// ignore_timestamps 为 true 时忽略行首的 `[时间戳][帧号]`，只比较消息部分。
+/// 在后台线程执行
+#[tauri::command(async)]
+pub fn diff_files(
+    path_a: String,
+    path_b: String,
+    ignore_timestamps: bool,
+) -> Result<DiffResult, String> {
+    let path_a = resolve_log_path(&path_a)?;
+    let path_b = resolve_log_path(&path_b)?;
+
+    diff::diff_files(&path_a, &path_b, ignore_timestamps)
+        .map_err(|e| format!("Failed to diff files: {}", e))
+}
@@ -0,0 +1,383 @@
+//! 导出命令
+
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::commands::file_commands::AppState;
+use crate::parser::{ExportFormat, FileIndex, FilterOptions, LogEntry, LogParser, ParserConfig};
+use crate::streaming::encoding::{decode_line, newline_bytes, newline_spans};
+use crate::streaming::{map_file, LineReader};
+
+/// 导出目标无法写入时返回的错误码
+pub const EXPORT_WRITE_ERROR: &str = "EXPORT_WRITE_ERROR";
+
+/// CSV 导出的列名
+const CSV_HEADER: &[u8] = b"line_number,timestamp,frame,category,level,message\n";
+
+/// 将行范围内 (可选过滤后) 的原始行导出到文件，返回写入的行数
+///
+/// 原样复制源文件的字节 (保持编码、BOM 和换行符)，不在内存中缓冲整个输出；
+/// 目标无法写入时返回以 EXPORT_WRITE_ERROR 开头的错误。不锁定行读取器，在后台线程执行
+#[tauri::command(async)]
+pub fn export_range(
+    start_line: u64,
+    end_line: u64,
+    filter: Option<FilterOptions>,
+    dest_path: String,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<u64, String> {
+    let (file_path, index, parser_config) = {
+        let state = state.lock().map_err(|e| e.to_string())?;
+        let (file_path, index) = state.file_and_index()?;
+        (file_path, index, state.parser_config.clone())
+    };
+
+    export_lines(
+        &file_path,
+        &index,
+        &parser_config,
+        start_line,
+        end_line,
+        filter.as_ref(),
+        &dest_path,
+    )
+}
+
+/// 将行范围内解析后的条目导出为 CSV 或 NDJSON，返回写入的条目数
+///
+/// CSV 列为 line_number,timestamp,frame,category,level,message；
+/// NDJSON 每行一个 LogEntry 的 JSON 对象；无法解析的行在 CSV 的 message 列写出原始内容。
+/// 在后台线程执行
+#[tauri::command(async)]
+pub fn export_structured(
+    start_line: u64,
+    end_line: u64,
+    format: ExportFormat,
+    dest_path: String,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<u64, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    export_entries(&mut reader, start_line, end_line, format, &dest_path)
+}
+
+/// 写出 [start_line, end_line] 中通过过滤的原始行
+///
+/// 每行直接复制映射中 [行首, 下一行行首) 的字节；源文件有 BOM 时先写出 BOM，
+/// 最后一行没有换行时补上。只有需要过滤时才解码并解析行
+fn export_lines(
+    file_path: &Path,
+    index: &FileIndex,
+    parser_config: &ParserConfig,
+    start_line: u64,
+    end_line: u64,
+    filter: Option<&FilterOptions>,
+    dest_path: &str,
+) -> Result<u64, String> {
+    let write_error = |e: std::io::Error| format!("{}: {}: {}", EXPORT_WRITE_ERROR, dest_path, e);
+
+    let mut writer = BufWriter::new(File::create(dest_path).map_err(write_error)?);
+    let mmap = map_file(file_path).map_err(|e| e.to_string())?;
+    let data = mmap.as_deref().unwrap_or_default();
+    let encoding = index.encoding;
+    let bom_len = encoding.bom_len().min(data.len());
+    writer.write_all(&data[..bom_len]).map_err(write_error)?;
+
+    let start_line = start_line.max(1);
+    let end_line = end_line.min(index.total_lines);
+    let (offset, lines_before) = index.seek_position(start_line);
+    let from = usize::try_from(offset).map_or(data.len(), |offset| offset.min(data.len()));
+    let from = from.max(bom_len);
+
+    let mut newlines = newline_spans(data, from, data.len(), encoding);
+    let mut pos = from;
+    let mut line_number = lines_before;
+    let mut written = 0;
+
+    while pos < data.len() && line_number < end_line {
+        // 最后一行没有换行时，换行位置与下一行行首都是文件末尾
+        let (newline, next) = newlines.next().unwrap_or((data.len(), data.len()));
+        let line_start = std::mem::replace(&mut pos, next);
+        line_number += 1;
+        if line_number < start_line {
+            continue;
+        }
+
+        if let Some(filter) = filter {
+            let bytes = &data[line_start..newline];
+            let decoded =
+                decode_line(bytes, encoding).unwrap_or_else(|| String::from_utf8_lossy(bytes));
+            let line = decoded.strip_suffix('\r').unwrap_or(&decoded);
+            let entry = LogParser::parse_line_with(parser_config, line_number, line);
+            if !filter.matches(&entry) {
+                continue;
+            }
+        }
+
+        writer
+            .write_all(&data[line_start..next])
+            .map_err(write_error)?;
+        if newline == next {
+            writer
+                .write_all(newline_bytes(encoding))
+                .map_err(write_error)?;
+        }
+        written += 1;
+    }
+
+    writer.flush().map_err(write_error)?;
+    Ok(written)
+}
+
+/// 按格式写出 [start_line, end_line] 中的全部条目
+fn export_entries(
+    reader: &mut LineReader,
+    start_line: u64,
+    end_line: u64,
+    format: ExportFormat,
+    dest_path: &str,
+) -> Result<u64, String> {
+    match format {
+        ExportFormat::Csv => write_entries(
+            reader,
+            start_line,
+            end_line,
+            dest_path,
+            CSV_HEADER,
+            |writer, entry| {
+                write_csv_row(writer, entry)?;
+                Ok(true)
+            },
+        ),
+        ExportFormat::Ndjson => write_entries(
+            reader,
+            start_line,
+            end_line,
+            dest_path,
+            b"",
+            |writer, entry| {
+                serde_json::to_writer(&mut *writer, entry)?;
+                writer.write_all(b"\n")?;
+                Ok(true)
+            },
+        ),
+    }
+}
+
+/// 逐条读取并写出，write_entry 返回该条目是否被写出
+///
+/// 不在内存中缓冲整个输出；写入失败时返回以 EXPORT_WRITE_ERROR 开头的错误
+fn write_entries<F>(
+    reader: &mut LineReader,
+    start_line: u64,
+    end_line: u64,
+    dest_path: &str,
+    header: &[u8],
+    mut write_entry: F,
+) -> Result<u64, String>
+where
+    F: FnMut(&mut BufWriter<File>, &LogEntry) -> std::io::Result<bool>,
+{
+    let write_error = |e: std::io::Error| format!("{}: {}: {}", EXPORT_WRITE_ERROR, dest_path, e);
+
+    let mut writer = BufWriter::new(File::create(dest_path).map_err(write_error)?);
+    writer.write_all(header).map_err(write_error)?;
+    let mut written = 0;
+
+    for entry in reader.entries_from(start_line).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        if entry.line_number > end_line {
+            break;
+        }
+
+        if write_entry(&mut writer, &entry).map_err(write_error)? {
+            written += 1;
+        }
+    }
+
+    writer.flush().map_err(write_error)?;
+    Ok(written)
+}
+
+/// 写出一行 CSV
+fn write_csv_row<W: Write>(writer: &mut W, entry: &LogEntry) -> std::io::Result<()> {
+    let frame = entry.frame.map(|f| f.to_string()).unwrap_or_default();
+
+    writeln!(
+        writer,
+        "{},{},{},{},{},{}",
+        entry.line_number,
+        csv_field(entry.timestamp.as_deref().unwrap_or("")),
+        frame,
+        csv_field(entry.category.as_deref().unwrap_or("")),
+        entry.level.display_name().to_lowercase(),
+        // 无法解析的行没有 message，导出原始内容
+        csv_field(entry.message.as_deref().unwrap_or(&entry.raw)),
+    )
+}
+
+/// 含逗号、引号或换行的字段加引号，内部引号加倍
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::LogLevel;
+    use crate::streaming::index_file;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_export_filtered_range() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=1500 {
+            let level = if i % 10 == 0 { "Error" } else { "Display" };
+            writeln!(temp_file, "LogNet: {}: Packet {}", level, i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let config = ParserConfig::default();
+        let export = |start: u64, end: u64, filter: Option<&FilterOptions>, dest: &str| {
+            export_lines(temp_file.path(), &index, &config, start, end, filter, dest)
+        };
+        let dest = tempfile::tempdir().unwrap();
+        let dest_path = dest.path().join("errors.log");
+        let dest_str = dest_path.to_str().unwrap();
+
+        let filter = FilterOptions {
+            levels: vec![LogLevel::Error],
+            ..Default::default()
+        };
+        let written = export(995, 1030, Some(&filter), dest_str).unwrap();
+        assert_eq!(written, 4);
+        assert_eq!(
+            std::fs::read_to_string(&dest_path).unwrap(),
+            "LogNet: Error: Packet 1000\nLogNet: Error: Packet 1010\n\
+             LogNet: Error: Packet 1020\nLogNet: Error: Packet 1030\n"
+        );
+
+        // 不过滤时导出范围内的全部行
+        let written = export(1, 3, None, dest_str).unwrap();
+        assert_eq!(written, 3);
+
+        let missing_dir = dest.path().join("missing").join("out.log");
+        let err = export(1, 3, None, missing_dir.to_str().unwrap()).unwrap_err();
+        assert!(err.starts_with(EXPORT_WRITE_ERROR));
+    }
+
+    #[test]
+    fn test_export_copies_source_bytes() {
+        let config = ParserConfig::default();
+        let filter = FilterOptions {
+            levels: vec![LogLevel::Error],
+            ..Default::default()
+        };
+        let dest = tempfile::tempdir().unwrap();
+        let dest_path = dest.path().join("out.log");
+        let dest_str = dest_path.to_str().unwrap();
+
+        // CRLF、无效 UTF-8 和 BOM 原样保留，最后一行补上换行
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file
+            .write_all(
+                b"\xEF\xBB\xBFLogNet: Error: bad \xFF byte\r\n\
+                  LogNet: Display: ok\r\n\
+                  LogNet: Error: last",
+            )
+            .unwrap();
+        let index = index_file(temp_file.path()).unwrap();
+
+        let written = export_lines(
+            temp_file.path(),
+            &index,
+            &config,
+            1,
+            3,
+            Some(&filter),
+            dest_str,
+        )
+        .unwrap();
+        assert_eq!(written, 2);
+        assert_eq!(
+            std::fs::read(&dest_path).unwrap(),
+            b"\xEF\xBB\xBFLogNet: Error: bad \xFF byte\r\nLogNet: Error: last\n"
+        );
+
+        // UTF-16 按码元切分，输出保持源编码
+        let text = "LogNet: Display: ok\nLogNet: Error: Ошибка\n";
+        let mut utf16 = vec![0xFF, 0xFE];
+        utf16.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        let mut temp_file = NamedTempFile::new().unwrap();
+        temp_file.write_all(&utf16).unwrap();
+        let index = index_file(temp_file.path()).unwrap();
+
+        let written = export_lines(
+            temp_file.path(),
+            &index,
+            &config,
+            1,
+            2,
+            Some(&filter),
+            dest_str,
+        )
+        .unwrap();
+        assert_eq!(written, 1);
+        let mut expected = vec![0xFF, 0xFE];
+        expected.extend(
+            "LogNet: Error: Ошибка\n"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        assert_eq!(std::fs::read(&dest_path).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_export_structured() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.33.56:070][  7]LogNet: Warning: Peer \"A\", retry 2"
+        )
+        .unwrap();
+        writeln!(temp_file, "LogInit: Display: Ready").unwrap();
+        writeln!(temp_file, "plain text, not a log line").unwrap();
+
+        let index = index_file(temp_file.path()).unwrap();
+        let mut reader = LineReader::from_index(temp_file.path(), index).unwrap();
+        let dest = tempfile::tempdir().unwrap();
+
+        let csv_path = dest.path().join("out.csv");
+        let csv_str = csv_path.to_str().unwrap();
+        let written = export_entries(&mut reader, 1, 3, ExportFormat::Csv, csv_str).unwrap();
+        assert_eq!(written, 3);
+        assert_eq!(
+            std::fs::read_to_string(&csv_path).unwrap(),
+            "line_number,timestamp,frame,category,level,message\n\
+             1,2026.02.14-03.33.56:070,7,LogNet,warning,\"Peer \"\"A\"\", retry 2\"\n\
+             2,,,LogInit,display,Ready\n\
+             3,,,,unknown,\"plain text, not a log line\"\n"
+        );
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+
+        let json_path = dest.path().join("out.ndjson");
+        let json_str = json_path.to_str().unwrap();
+        export_entries(&mut reader, 1, 2, ExportFormat::Ndjson, json_str).unwrap();
+        let entries: Vec<LogEntry> = std::fs::read_to_string(&json_path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].message.as_deref(), Some("Peer \"A\", retry 2"));
+        assert_eq!(entries[1].level, LogLevel::Display);
+    }
+}
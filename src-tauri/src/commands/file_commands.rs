@@ -1,92 +1,1227 @@
 //! 文件操作命令
 
-use std::path::PathBuf;
-use std::sync::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
 
-use crate::parser::{FileIndex, LogChunk, OpenFileResult};
-use crate::streaming::{index_file, LineReader};
+use crate::commands::bookmark_commands::load_bookmarks;
+use crate::parser::highlight::Highlighter;
+use crate::parser::{
+    Bookmark, CategoryLines, CustomPattern, EntryField, FileIndex, IndexStats, LogChunk,
+    LogFileInfo, OpenFileResult, OpenMergedResult, ParseGroupMap, ParseStats, ParserConfig,
+    PreviewMode, ProjectedChunk, SessionBoundary, UnparsedSample,
+};
+use crate::streaming::{
+    load_or_index_file, load_or_index_file_with_progress, log_file_info, FileIndexer, LineReader,
+    MergedReader, FILE_TRUNCATED,
+};
 
 /// 全局状态
+///
+/// 锁的使用约定:
+/// - AppState 的锁只用于读取或替换字段，不在持有期间读取文件；
+///   索引和行读取器放在 Arc 中，克隆出来后即可释放
+/// - 读取文件时只持有行读取器自己的锁，get_file_index 等元数据查询不会被阻塞
+/// - 需要同时持有时先锁 AppState 再锁行读取器，持有行读取器的锁时不得再锁 AppState
+#[derive(Default)]
 pub struct AppState {
     pub current_file: Option<PathBuf>,
-    pub current_index: Option<FileIndex>,
-    pub line_reader: Option<LineReader>,
+    pub current_index: Option<Arc<FileIndex>>,
+    /// get_category_lines 按需构建的类别行号及其所属的索引 (该索引被替换后失效)
+    pub category_lines: Option<(Weak<FileIndex>, Arc<CategoryLines>)>,
+    pub line_reader: Option<Arc<Mutex<LineReader>>>,
+    pub parser_config: ParserConfig,
+    /// 跟踪线程的停止标志 (None 表示未在跟踪)
+    pub tail_stop: Option<Arc<AtomicBool>>,
+    /// 自定义高亮模式 (None 表示使用默认模式)
+    pub highlighter: Option<Highlighter>,
+    /// 正在进行的 search_logs 的取消标志 (None 表示没有搜索)
+    pub search_cancel: Option<Arc<AtomicBool>>,
+    /// 各文件的书签 (按行号升序)
+    pub bookmarks: HashMap<PathBuf, Vec<Bookmark>>,
+    /// 多文件合并视图 (与当前文件相互独立，None 表示未打开)
+    pub merged_reader: Option<Arc<Mutex<MergedReader>>>,
+    /// 目录监视器 (None 表示未在监视，drop 即停止监视，见 watch_directory)
+    pub dir_watcher: Option<notify::RecommendedWatcher>,
 }
 
-impl Default for AppState {
-    fn default() -> Self {
-        Self {
-            current_file: None,
-            current_index: None,
-            line_reader: None,
-        }
+impl AppState {
+    /// 当前文件的行读取器 (克隆出 Arc 后应先释放 AppState 的锁再锁定读取器)
+    pub fn reader(&self) -> Result<Arc<Mutex<LineReader>>, String> {
+        self.line_reader
+            .clone()
+            .ok_or_else(|| "No file opened".to_string())
+    }
+
+    /// 当前文件的路径和索引
+    pub fn file_and_index(&self) -> Result<(PathBuf, Arc<FileIndex>), String> {
+        let file_path = self.current_file.clone().ok_or("No file opened")?;
+        let index = self
+            .current_index
+            .clone()
+            .ok_or("No file index available")?;
+        Ok((file_path, index))
     }
+
+    /// 为 index 构建的类别行号 (尚未构建或已为其他索引构建时为 None)
+    pub fn category_lines_of(&self, index: &Arc<FileIndex>) -> Option<Arc<CategoryLines>> {
+        let (built_for, lines) = self.category_lines.as_ref()?;
+        Weak::ptr_eq(built_for, &Arc::downgrade(index)).then(|| lines.clone())
+    }
+}
+
+/// 文件增长事件名
+pub const FILE_GREW_EVENT: &str = "file-grew";
+
+/// 索引进度事件名
+pub const INDEX_PROGRESS_EVENT: &str = "index-progress";
+
+/// 后台打开完成事件名
+pub const INDEX_DONE_EVENT: &str = "index-done";
+
+/// 后台打开失败事件名
+pub const INDEX_ERROR_EVENT: &str = "index-error";
+
+/// 打开文件时预览的行数
+const PREVIEW_LINES: u64 = 100;
+
+/// discover_logs 列出的日志文件扩展名 (小写)
+const LOG_EXTENSIONS: &[&str] = &["log", "txt"];
+
+/// 跟踪轮询间隔下限 (毫秒)
+const MIN_TAIL_POLL_MS: u64 = 50;
+
+/// 文件增长事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileGrewEvent {
+    /// 新的总行数
+    pub total_lines: u64,
+    /// 新追加的行
+    pub chunk: LogChunk,
+}
+
+/// 索引进度事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexProgressEvent {
+    /// 已扫描的字节数
+    pub bytes_done: u64,
+    /// 文件总字节数
+    pub bytes_total: u64,
+}
+
+/// Windows 传统路径长度上限，超过时需保留 \\?\ 前缀才能打开
+const MAX_PATH: usize = 260;
+
+/// 解析并规范化前端传入的日志路径
+///
+/// 支持 \\?\C:\... 长路径与 \\server\share\... UNC 路径。
+/// 返回的规范路径会尽量去掉 \\?\ 前缀以便显示，超长路径保留前缀
+pub fn resolve_log_path(path: &str) -> Result<PathBuf, String> {
+    let canonical = std::fs::canonicalize(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => format!("File not found: {}", path),
+        _ => format!("Invalid path {}: {}", path, e),
+    })?;
+
+    if !canonical.is_file() {
+        return Err(format!("Not a file: {}", path));
+    }
+
+    let simplified = canonical
+        .to_str()
+        .and_then(strip_verbatim_prefix)
+        .map(PathBuf::from);
+
+    Ok(simplified.unwrap_or(canonical))
+}
+
+/// 去掉 \\?\ 扩展长度前缀 (路径足够短时)
+///
+/// `\\?\C:\dir\a.log` -> `C:\dir\a.log`，`\\?\UNC\server\share\a.log` -> `\\server\share\a.log`
+fn strip_verbatim_prefix(path: &str) -> Option<String> {
+    let simplified = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", rest)
+    } else {
+        let rest = path.strip_prefix(r"\\?\")?;
+        let bytes = rest.as_bytes();
+        // 只处理盘符路径 (X:\...)
+        if bytes.len() < 3 || !bytes[0].is_ascii_alphabetic() || &bytes[1..3] != b":\\" {
+            return None;
+        }
+        rest.to_string()
+    };
+
+    (simplified.len() < MAX_PATH).then_some(simplified)
 }
 
 /// 打开日志文件
+///
+/// preview_mode 决定预览文件开头还是末尾的 PREVIEW_LINES 行，默认为开头
 #[tauri::command]
 pub fn open_log_file(
     path: String,
+    preview_mode: Option<PreviewMode>,
     state: tauri::State<'_, Mutex<AppState>>,
 ) -> Result<OpenFileResult, String> {
-    let file_path = PathBuf::from(&path);
+    let file_path = resolve_log_path(&path)?;
 
-    if !file_path.exists() {
-        return Err(format!("File not found: {}", path));
-    }
+    // 构建索引 (优先复用持久化的索引)
+    let (index, _) =
+        load_or_index_file(&file_path).map_err(|e| format!("Failed to index file: {}", e))?;
+
+    open_indexed(file_path, index, preview_mode.unwrap_or_default(), &state)
+}
+
+/// 在后台线程打开日志文件，用于大文件
+///
+/// 索引期间约每 5% 发送一次 `index-progress` 事件，完成后发送携带 OpenFileResult 的
+/// `index-done` 事件，失败时发送携带错误信息的 `index-error` 事件。
+/// 路径无效时直接返回错误
+#[tauri::command]
+pub fn open_log_file_with_progress(
+    app: AppHandle,
+    path: String,
+    preview_mode: Option<PreviewMode>,
+) -> Result<(), String> {
+    let file_path = resolve_log_path(&path)?;
 
-    // 构建索引
-    let index = index_file(&file_path).map_err(|e| format!("Failed to index file: {}", e))?;
+    std::thread::spawn(move || {
+        let on_progress = |bytes_done, bytes_total| {
+            let event = IndexProgressEvent {
+                bytes_done,
+                bytes_total,
+            };
+            if let Err(e) = app.emit(INDEX_PROGRESS_EVENT, event) {
+                log::warn!("Failed to emit {}: {}", INDEX_PROGRESS_EVENT, e);
+            }
+        };
 
+        let opened = load_or_index_file_with_progress(&file_path, &on_progress)
+            .map_err(|e| format!("Failed to index file: {}", e))
+            .and_then(|(index, _)| {
+                open_indexed(
+                    file_path,
+                    index,
+                    preview_mode.unwrap_or_default(),
+                    &app.state::<Mutex<AppState>>(),
+                )
+            });
+
+        let emitted = match opened {
+            Ok(result) => app.emit(INDEX_DONE_EVENT, result),
+            Err(e) => app.emit(INDEX_ERROR_EVENT, e),
+        };
+        if let Err(e) = emitted {
+            log::warn!("Failed to emit index result: {}", e);
+        }
+    });
+
+    Ok(())
+}
+
+/// 为已建好索引的文件创建行读取器、读取预览，并设为当前文件
+fn open_indexed(
+    file_path: PathBuf,
+    index: FileIndex,
+    preview_mode: PreviewMode,
+    state: &Mutex<AppState>,
+) -> Result<OpenFileResult, String> {
     // 创建行读取器
     let mut reader = LineReader::from_index(&file_path, index.clone())
         .map_err(|e| format!("Failed to create reader: {}", e))?;
 
+    // 沿用当前的解析器配置
+    {
+        let state = state.lock().map_err(|e| e.to_string())?;
+        reader.set_parser_config(state.parser_config.clone());
+    }
+
     // 读取预览
-    let preview = reader
-        .read_preview(100)
-        .map_err(|e| format!("Failed to read preview: {}", e))?;
+    let preview = match preview_mode {
+        PreviewMode::Head => reader.read_preview(PREVIEW_LINES),
+        PreviewMode::Tail => reader.read_tail(PREVIEW_LINES).map(|chunk| chunk.entries),
+    }
+    .map_err(|e| format!("Failed to read preview: {}", e))?;
+
+    // 读取已保存的书签
+    let bookmarks = load_bookmarks(&file_path);
 
     // 更新状态
     let mut state = state.lock().map_err(|e| e.to_string())?;
+    state.bookmarks.insert(file_path.clone(), bookmarks);
     state.current_file = Some(file_path);
-    state.current_index = Some(index.clone());
-    state.line_reader = Some(reader);
+    state.current_index = Some(Arc::new(index.clone()));
+    state.category_lines = None;
+    state.line_reader = Some(Arc::new(Mutex::new(reader)));
 
-    Ok(OpenFileResult { index, preview })
+    Ok(OpenFileResult {
+        encoding: index.encoding,
+        index,
+        preview,
+    })
 }
 
-/// 加载日志块
+/// 按时间顺序合并打开多个日志文件 (如 Saved/Logs 与其 Backup/ 中拆分的日志)
+///
+/// 各文件优先复用持久化的索引；合并需要完整扫描每个文件一次，在后台线程执行。
+/// 合并视图通过 load_merged_chunk 读取，不影响当前打开的文件
+#[tauri::command(async)]
+pub fn open_merged_files(
+    paths: Vec<String>,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<OpenMergedResult, String> {
+    if paths.is_empty() {
+        return Err("No files to merge".to_string());
+    }
+
+    let mut files = Vec::with_capacity(paths.len());
+    for path in &paths {
+        let file_path = resolve_log_path(path)?;
+        let (index, _) = load_or_index_file(&file_path)
+            .map_err(|e| format!("Failed to index {}: {}", path, e))?;
+        files.push((index, file_path));
+    }
+
+    let config = state
+        .lock()
+        .map_err(|e| e.to_string())?
+        .parser_config
+        .clone();
+    let mut reader =
+        MergedReader::new(files, &config).map_err(|e| format!("Failed to merge files: {}", e))?;
+    let preview = reader
+        .read_range(1, PREVIEW_LINES)
+        .map_err(|e| format!("Failed to read preview: {}", e))?
+        .entries;
+
+    let result = OpenMergedResult {
+        total_lines: reader.total_lines(),
+        sources: reader.source_paths(),
+        preview,
+    };
+    state.lock().map_err(|e| e.to_string())?.merged_reader = Some(Arc::new(Mutex::new(reader)));
+
+    Ok(result)
+}
+
+/// 加载合并视图的日志块 (行号为合并视图中的行号，条目带来源文件)
 #[tauri::command]
-pub fn load_chunk(
+pub fn load_merged_chunk(
     start_line: u64,
     end_line: u64,
     state: tauri::State<'_, Mutex<AppState>>,
 ) -> Result<LogChunk, String> {
+    let reader = state
+        .lock()
+        .map_err(|e| e.to_string())?
+        .merged_reader
+        .clone()
+        .ok_or("No merged files opened")?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .read_range(start_line, end_line)
+        .map_err(|e| e.to_string())
+}
+
+/// 在后台预先构建并持久化索引 (不影响当前打开的文件)
+///
+/// 用于最近文件列表预热，之后打开该文件时可直接复用索引
+#[tauri::command]
+pub fn precompute_index(path: String) -> Result<(), String> {
+    let file_path = resolve_log_path(&path)?;
+
+    std::thread::spawn(move || {
+        if let Err(e) = load_or_index_file(&file_path) {
+            log::warn!("Failed to precompute index for {}: {}", path, e);
+        }
+    });
+
+    Ok(())
+}
+
+/// 为指定文件构建一次索引并返回耗时和吞吐量，用于排查慢速机器
+///
+/// 不读取也不写入持久化索引，不影响当前打开的文件
+#[tauri::command(async)]
+pub fn index_benchmark(path: String) -> Result<IndexStats, String> {
+    let file_path = resolve_log_path(&path)?;
+    let indexer = FileIndexer::open(&file_path).map_err(|e| e.to_string())?;
+    let (_, stats) = indexer.build_index_timed();
+    Ok(stats)
+}
+
+/// 列出目录中的日志文件 (.log / .txt)，按修改时间降序，用于最近日志选择
+///
+/// 行数按文件开头估算，不建立索引。recursive 时包含子目录 (不跟随目录的符号链接)；
+/// 无法读取的文件或子目录记录警告后跳过，目录本身无法读取时返回错误
+#[tauri::command(async)]
+pub fn discover_logs(dir: String, recursive: bool) -> Result<Vec<LogFileInfo>, String> {
+    let mut logs = Vec::new();
+    collect_logs(Path::new(&dir), recursive, &mut logs)
+        .map_err(|e| format!("Failed to read directory {}: {}", dir, e))?;
+
+    logs.sort_by(|a, b| {
+        b.modified
+            .cmp(&a.modified)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    Ok(logs)
+}
+
+/// 收集 dir 中的日志文件，只有 dir 本身无法读取时返回错误
+fn collect_logs(dir: &Path, recursive: bool, logs: &mut Vec<LogFileInfo>) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                log::warn!("Failed to read entry in {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+        let path = entry.path();
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(e) => {
+                log::warn!("Failed to stat {}: {}", path.display(), e);
+                continue;
+            }
+        };
+
+        if file_type.is_dir() {
+            if recursive {
+                if let Err(e) = collect_logs(&path, recursive, logs) {
+                    log::warn!("Failed to read directory {}: {}", path.display(), e);
+                }
+            }
+            continue;
+        }
+
+        let is_log = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                LOG_EXTENSIONS
+                    .iter()
+                    .any(|known| ext.eq_ignore_ascii_case(known))
+            });
+        if !is_log {
+            continue;
+        }
+
+        match log_file_info(&path) {
+            Ok(info) => logs.push(info),
+            Err(e) => log::warn!("Failed to read {}: {}", path.display(), e),
+        }
+    }
+    Ok(())
+}
+
+/// 重新检查当前文件，增量索引新追加的行，返回新增的行数
+///
+/// 文件被截断或轮转时返回以 FILE_TRUNCATED 开头的错误，前端应重新打开文件
+#[tauri::command]
+pub fn refresh_file(state: tauri::State<'_, Mutex<AppState>>) -> Result<u64, String> {
+    refresh_current_file(&state)
+}
+
+/// 增量索引当前文件并同步到行读取器，返回新增的行数
+///
+/// 建立索引期间不持有 AppState 的锁；期间文件被关闭、重新打开或已被其他调用刷新时放弃本次结果。
+/// 已构建的类别行号随索引一起增量更新
+fn refresh_current_file(state: &Mutex<AppState>) -> Result<u64, String> {
+    let (file_path, index, category_lines) = {
+        let state = state.lock().map_err(|e| e.to_string())?;
+        let (file_path, index) = state.file_and_index()?;
+        let category_lines = state.category_lines_of(&index);
+        (file_path, index, category_lines)
+    };
+
+    // 进行中的搜索持有旧索引的 Arc，不受影响
+    let mut updated = FileIndex::clone(&index);
+    if let Some(category_lines) = category_lines {
+        CategoryLines::clone(&category_lines).put_into(&mut updated);
+    }
+    let indexer = FileIndexer::open(&file_path).map_err(|e| e.to_string())?;
+    let new_lines = indexer
+        .update_index(&mut updated)
+        .map_err(|e| e.to_string())?;
+    let category_lines = CategoryLines::take_from(&mut updated).map(Arc::new);
+
+    let reader = {
+        let mut state = state.lock().map_err(|e| e.to_string())?;
+        let unchanged = state.current_file.as_ref() == Some(&file_path)
+            && state
+                .current_index
+                .as_ref()
+                .is_some_and(|current| Arc::ptr_eq(current, &index));
+        if !unchanged {
+            return Ok(0);
+        }
+        let current = Arc::new(updated.clone());
+        state.category_lines = category_lines.map(|lines| (Arc::downgrade(&current), lines));
+        state.current_index = Some(current);
+        state.line_reader.clone()
+    };
+
+    if new_lines > 0 {
+        if let Some(reader) = reader {
+            let mut reader = reader.lock().map_err(|e| e.to_string())?;
+            reader.replace_index(updated);
+        }
+    }
+
+    Ok(new_lines)
+}
+
+/// 检查当前文件长度，增长时增量索引并返回新追加的行
+///
+/// 只短暂持有 AppState 的锁，读取器忙时只阻塞跟踪线程自身
+fn poll_file_growth(state: &Mutex<AppState>) -> Result<Option<FileGrewEvent>, String> {
+    let Ok((file_path, index)) = state.lock().map_err(|e| e.to_string())?.file_and_index() else {
+        return Ok(None);
+    };
+    let old_total = index.total_lines;
+
+    let len = std::fs::metadata(&file_path)
+        .map_err(|e| e.to_string())?
+        .len();
+    if len == index.file_size {
+        return Ok(None);
+    }
+
+    let new_lines = refresh_current_file(state)?;
+    if new_lines == 0 {
+        return Ok(None);
+    }
+
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+    let total_lines = reader.index().total_lines;
+    let chunk = reader
+        .read_range(old_total + 1, total_lines)
+        .map_err(|e| e.to_string())?;
+
+    Ok(Some(FileGrewEvent { total_lines, chunk }))
+}
+
+/// 开始跟踪当前文件 (每 poll_ms 检查一次)，文件增长时发送 `file-grew` 事件
+///
+/// 已在跟踪时不会重复启动线程
+#[tauri::command]
+pub fn start_tailing(
+    app: AppHandle,
+    poll_ms: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
     let mut state = state.lock().map_err(|e| e.to_string())?;
+    if state.tail_stop.is_some() {
+        return Ok(());
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    state.tail_stop = Some(stop.clone());
+    let interval = Duration::from_millis(poll_ms.max(MIN_TAIL_POLL_MS));
 
-    let reader = state.line_reader.as_mut().ok_or("No file opened")?;
+    std::thread::spawn(move || {
+        while !stop.load(Ordering::Relaxed) {
+            std::thread::sleep(interval);
+            if stop.load(Ordering::Relaxed) {
+                break;
+            }
+
+            let polled = poll_file_growth(&app.state::<Mutex<AppState>>());
+
+            match polled {
+                Ok(Some(event)) => {
+                    if let Err(e) = app.emit(FILE_GREW_EVENT, event) {
+                        log::warn!("Failed to emit {}: {}", FILE_GREW_EVENT, e);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) if e.starts_with(FILE_TRUNCATED) => {
+                    log::warn!("Stopped tailing: {}", e);
+                    break;
+                }
+                Err(e) => log::warn!("Failed to refresh tailed file: {}", e),
+            }
+        }
+
+        // 线程自行退出 (如文件被截断) 时清除标志，之后可以重新开始跟踪
+        let state = app.state::<Mutex<AppState>>();
+        if let Ok(mut state) = state.lock() {
+            if state
+                .tail_stop
+                .as_ref()
+                .is_some_and(|current| Arc::ptr_eq(current, &stop))
+            {
+                state.tail_stop = None;
+            }
+        };
+    });
+
+    Ok(())
+}
+
+/// 停止跟踪当前文件
+#[tauri::command]
+pub fn stop_tailing(state: tauri::State<'_, Mutex<AppState>>) -> Result<(), String> {
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    if let Some(stop) = state.tail_stop.take() {
+        stop.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// 加载日志块
+#[tauri::command]
+pub fn load_chunk(
+    start_line: u64,
+    end_line: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<LogChunk, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
 
     reader
         .read_range(start_line, end_line)
         .map_err(|e| e.to_string())
 }
 
-/// 获取当前文件索引
+/// 加载覆盖字节范围 [start_byte, end_byte) 的日志行 (对接报告字节偏移的外部工具)
+#[tauri::command]
+pub fn load_byte_range(
+    start_byte: u64,
+    end_byte: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<LogChunk, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .read_byte_range(start_byte, end_byte)
+        .map_err(|e| e.to_string())
+}
+
+/// 某行起始的字节偏移 (行号越界时为 None)，用于在外部工具中定位原始文件
+#[tauri::command]
+pub fn line_to_byte_offset(
+    line_number: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Option<u64>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .line_to_byte_offset(line_number)
+        .map_err(|e| e.to_string())
+}
+
+/// 字节偏移所在的行号
+#[tauri::command]
+pub fn byte_offset_to_line(
+    offset: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<u64, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .byte_offset_to_line(offset)
+        .map_err(|e| e.to_string())
+}
+
+/// 加载日志块，相邻的相同行折叠为一条 (repeat_count 为重复次数)
+#[tauri::command]
+pub fn load_chunk_deduped(
+    start_line: u64,
+    end_line: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<LogChunk, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .read_range_deduped(start_line, end_line)
+        .map_err(|e| e.to_string())
+}
+
+/// 读取末尾 count 行，打开大文件时可代替开头的预览先显示最新内容
+#[tauri::command]
+pub fn load_tail(count: u64, state: tauri::State<'_, Mutex<AppState>>) -> Result<LogChunk, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader.read_tail(count).map_err(|e| e.to_string())
+}
+
+/// 读取某行前后各 radius 行 (用于上下文预览，在文件边界处截断)
+#[tauri::command]
+pub fn peek_around(
+    line_number: u64,
+    radius: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<LogChunk, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .peek_around(line_number, radius)
+        .map_err(|e| e.to_string())
+}
+
+/// 加载日志块 (续行合并到所属日志行)
+#[tauri::command]
+pub fn load_chunk_merged(
+    start_line: u64,
+    end_line: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<LogChunk, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .read_range_merged(start_line, end_line)
+        .map_err(|e| e.to_string())
+}
+
+/// 加载日志块 (列式，只返回请求的字段)
+#[tauri::command]
+pub fn load_chunk_projected(
+    start_line: u64,
+    end_line: u64,
+    fields: Vec<EntryField>,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<ProjectedChunk, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    let chunk = reader
+        .read_range(start_line, end_line)
+        .map_err(|e| e.to_string())?;
+
+    Ok(chunk.project(&fields))
+}
+
+/// 获取当前文件索引 (不会被进行中的文件读取阻塞)
 #[tauri::command]
 pub fn get_file_index(
     state: tauri::State<'_, Mutex<AppState>>,
-) -> Result<Option<FileIndex>, String> {
+) -> Result<Option<Arc<FileIndex>>, String> {
     let state = state.lock().map_err(|e| e.to_string())?;
     Ok(state.current_index.clone())
 }
 
-/// 关闭当前文件
+/// 关闭当前文件 (同时停止跟踪和目录监视)
 #[tauri::command]
 pub fn close_file(state: tauri::State<'_, Mutex<AppState>>) -> Result<(), String> {
     let mut state = state.lock().map_err(|e| e.to_string())?;
+    if let Some(stop) = state.tail_stop.take() {
+        stop.store(true, Ordering::Relaxed);
+    }
     state.current_file = None;
     state.current_index = None;
+    state.category_lines = None;
     state.line_reader = None;
+    state.dir_watcher = None;
     Ok(())
 }
+
+/// 设置解析器配置
+///
+/// 已加载的块不会自动刷新，前端需对可见范围调用 `reparse_chunk`
+#[tauri::command]
+pub fn set_parser_config(
+    config: ParserConfig,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    // 自定义格式不经过前端传递，沿用已设置的
+    let config = ParserConfig {
+        custom_pattern: state.parser_config.custom_pattern.clone(),
+        ..config
+    };
+    apply_parser_config(&mut state, config)
+}
+
+/// 设置自定义行格式，配置后每行优先尝试该格式
+///
+/// group_map 中的捕获组必须都在正则中存在；传入空字符串清除自定义格式。
+/// 与 set_parser_config 一样，已加载的块需调用 `reparse_chunk` 刷新
+#[tauri::command]
+pub fn set_custom_pattern(
+    regex: String,
+    group_map: ParseGroupMap,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let custom_pattern = if regex.is_empty() {
+        None
+    } else {
+        Some(CustomPattern::new(&regex, group_map)?)
+    };
+
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    let config = ParserConfig {
+        custom_pattern,
+        ..state.parser_config.clone()
+    };
+    apply_parser_config(&mut state, config)
+}
+
+/// 更新解析器配置，并同步到当前文件的行读取器
+fn apply_parser_config(state: &mut AppState, config: ParserConfig) -> Result<(), String> {
+    if let Some(reader) = &state.line_reader {
+        let mut reader = reader.lock().map_err(|e| e.to_string())?;
+        reader.set_parser_config(config.clone());
+    }
+    state.parser_config = config;
+    Ok(())
+}
+
+/// 按当前解析器配置重新解析日志块
+#[tauri::command]
+pub fn reparse_chunk(
+    start_line: u64,
+    end_line: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<LogChunk, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .reparse_range(start_line, end_line)
+        .map_err(|e| e.to_string())
+}
+
+/// 采样未能解析的行，帮助编写自定义解析规则
+#[tauri::command(async)]
+pub fn scan_unparsed(
+    limit: usize,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<UnparsedSample>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader.scan_unparsed(limit).map_err(|e| e.to_string())
+}
+
+/// 统计各解析分支命中的行数，用于发现尚未支持的日志格式
+#[tauri::command(async)]
+pub fn parse_stats(state: tauri::State<'_, Mutex<AppState>>) -> Result<ParseStats, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader.parse_stats().map_err(|e| e.to_string())
+}
+
+/// 获取第一个带时间戳行的时间 (Unix 毫秒)，用于与外部数据对齐
+#[tauri::command]
+pub fn get_log_start_time(state: tauri::State<'_, Mutex<AppState>>) -> Result<Option<i64>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader.first_timestamp_millis().map_err(|e| e.to_string())
+}
+
+/// 获取最后一个带时间戳行的时间 (Unix 毫秒)
+#[tauri::command]
+pub fn get_log_end_time(state: tauri::State<'_, Mutex<AppState>>) -> Result<Option<i64>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader.last_timestamp_millis().map_err(|e| e.to_string())
+}
+
+/// 获取日志的时间跨度 (第一个与最后一个时间戳)，没有时间戳时返回 None
+#[tauri::command]
+pub fn get_time_range(
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Option<(String, String)>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader.time_range().map_err(|e| e.to_string())
+}
+
+/// 跳转到指定时间: 返回第一个时间戳不早于 timestamp 的行号
+#[tauri::command]
+pub fn goto_timestamp(
+    timestamp: String,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Option<u64>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .find_line_by_timestamp(&timestamp)
+        .map_err(|e| e.to_string())
+}
+
+/// 获取帧号范围 (最小, 最大)，没有帧号时返回 None
+#[tauri::command(async)]
+pub fn get_frame_range(
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Option<(u64, u64)>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader.frame_range().map_err(|e| e.to_string())
+}
+
+/// 跳转到指定帧: 返回第一个帧号不小于 frame 的行号 (帧号重置时取最早的一处)
+#[tauri::command(async)]
+pub fn goto_frame(
+    frame: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Option<u64>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader.find_line_by_frame(frame).map_err(|e| e.to_string())
+}
+
+/// 检测时间戳回退的会话边界 (返回新会话起始行号)
+#[tauri::command(async)]
+pub fn detect_session_resets(state: tauri::State<'_, Mutex<AppState>>) -> Result<Vec<u64>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader.detect_time_resets().map_err(|e| e.to_string())
+}
+
+/// 检测会话边界 (文件头或帧号重置)，用于会话下拉列表
+#[tauri::command(async)]
+pub fn get_sessions(
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<SessionBoundary>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader.detect_sessions().map_err(|e| e.to_string())
+}
+
+/// 跳转到下一个与指定行同类别的日志行
+#[tauri::command(async)]
+pub fn next_same_category(
+    line_number: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Option<u64>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .next_same_category(line_number)
+        .map_err(|e| e.to_string())
+}
+
+/// 跳转到上一个与指定行同类别的日志行
+#[tauri::command(async)]
+pub fn prev_same_category(
+    line_number: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Option<u64>, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    reader
+        .prev_same_category(line_number)
+        .map_err(|e| e.to_string())
+}
+
+/// 设置顺序读取的缓冲区大小 (字节)，返回实际生效的值
+#[tauri::command]
+pub fn set_io_buffer_size(bytes: usize) -> usize {
+    crate::streaming::set_io_buffer_size(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_verbatim_prefix() {
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\C:\Project\Saved\Logs\Game.log").as_deref(),
+            Some(r"C:\Project\Saved\Logs\Game.log")
+        );
+        assert_eq!(
+            strip_verbatim_prefix(r"\\?\UNC\buildserver\logs\Game.log").as_deref(),
+            Some(r"\\buildserver\logs\Game.log")
+        );
+        assert_eq!(strip_verbatim_prefix(r"C:\Game.log"), None);
+        assert_eq!(strip_verbatim_prefix(r"\\?\Volume{1234}\Game.log"), None);
+
+        let long = format!(r"\\?\C:\{}\Game.log", "a".repeat(MAX_PATH));
+        assert_eq!(strip_verbatim_prefix(&long), None);
+    }
+
+    #[test]
+    fn test_open_with_tail_preview() -> std::io::Result<()> {
+        use std::io::Write;
+
+        let mut temp_file = tempfile::NamedTempFile::new()?;
+        for i in 1..=1000 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+        temp_file.flush()?;
+
+        let path = temp_file.path().to_path_buf();
+        let state = Mutex::new(AppState::default());
+
+        let index = crate::streaming::index_file(&path)?;
+        let result = open_indexed(path.clone(), index.clone(), PreviewMode::Tail, &state).unwrap();
+        let lines: Vec<u64> = result.preview.iter().map(|e| e.line_number).collect();
+        assert_eq!(lines, (901..=1000).collect::<Vec<_>>());
+
+        let result = open_indexed(path, index, PreviewMode::Head, &state).unwrap();
+        assert_eq!(result.preview.first().map(|e| e.line_number), Some(1));
+        assert_eq!(result.preview.len(), 100);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_poll_file_growth() -> std::io::Result<()> {
+        use std::io::Write;
+
+        let mut temp_file = tempfile::NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Line 1")?;
+        writeln!(temp_file, "LogInit: Display: Line 2")?;
+        temp_file.flush()?;
+
+        let index = crate::streaming::index_file(temp_file.path())?;
+        let state = Mutex::new(AppState {
+            current_file: Some(temp_file.path().to_path_buf()),
+            line_reader: Some(Arc::new(Mutex::new(LineReader::from_index(
+                temp_file.path(),
+                index.clone(),
+            )?))),
+            current_index: Some(Arc::new(index)),
+            ..Default::default()
+        });
+
+        assert!(poll_file_growth(&state).unwrap().is_none());
+
+        writeln!(temp_file, "LogNet: Warning: Line 3")?;
+        writeln!(temp_file, "LogNet: Error: Line 4")?;
+        temp_file.flush()?;
+
+        let event = poll_file_growth(&state).unwrap().expect("file grew");
+        assert_eq!(event.total_lines, 4);
+        let lines: Vec<u64> = event.chunk.entries.iter().map(|e| e.line_number).collect();
+        assert_eq!(lines, vec![3, 4]);
+        let total_lines = |state: &Mutex<AppState>| {
+            state
+                .lock()
+                .unwrap()
+                .current_index
+                .as_ref()
+                .map(|index| index.total_lines)
+        };
+        assert_eq!(total_lines(&state), Some(4));
+
+        assert!(poll_file_growth(&state).unwrap().is_none());
+
+        // 读取器被长时间占用时，增量索引照常完成且不占用状态锁
+        writeln!(temp_file, "LogNet: Error: Line 5")?;
+        temp_file.flush()?;
+        let reader = state.lock().unwrap().reader().unwrap();
+        let busy = reader.lock().unwrap();
+        let event = std::thread::scope(|scope| {
+            let poll = scope.spawn(|| poll_file_growth(&state));
+
+            let deadline = std::time::Instant::now() + Duration::from_secs(5);
+            while total_lines(&state) != Some(5) {
+                assert!(
+                    std::time::Instant::now() < deadline,
+                    "index not updated while the reader was busy"
+                );
+                std::thread::sleep(Duration::from_millis(5));
+            }
+
+            drop(busy);
+            poll.join().unwrap()
+        });
+        let event = event.unwrap().expect("file grew");
+        assert_eq!(event.total_lines, 5);
+        assert_eq!(event.chunk.entries.len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_refresh_updates_category_lines() -> std::io::Result<()> {
+        use std::io::Write;
+
+        let mut temp_file = tempfile::NamedTempFile::new()?;
+        writeln!(temp_file, "LogNet: Display: Line 1")?;
+        writeln!(temp_file, "LogInit: Display: Line 2")?;
+        temp_file.flush()?;
+
+        let mut built = FileIndexer::open(temp_file.path())?.build_index_with_category_lines(10);
+        let category_lines = Arc::new(CategoryLines::take_from(&mut built).unwrap());
+        let index = Arc::new(built);
+        let state = Mutex::new(AppState {
+            current_file: Some(temp_file.path().to_path_buf()),
+            category_lines: Some((Arc::downgrade(&index), category_lines)),
+            current_index: Some(index),
+            ..Default::default()
+        });
+
+        writeln!(temp_file, "LogNet: Warning: Line 3")?;
+        temp_file.flush()?;
+        assert_eq!(refresh_current_file(&state).unwrap(), 1);
+
+        // 类别行号随索引增量更新，并对应新的索引
+        let state = state.lock().unwrap();
+        let current = state.current_index.clone().unwrap();
+        assert!(current.category_lines.is_none());
+        let lines = state.category_lines_of(&current).expect("carried over");
+        assert_eq!(lines.complete("LogNet"), Some(&[1, 3][..]));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_readable_while_reader_busy() -> std::io::Result<()> {
+        use std::io::Write;
+        use std::sync::mpsc;
+        use std::time::Duration;
+
+        let mut temp_file = tempfile::NamedTempFile::new()?;
+        for i in 1..=10 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+        temp_file.flush()?;
+
+        let index = crate::streaming::index_file(temp_file.path())?;
+        let state = Mutex::new(AppState {
+            current_file: Some(temp_file.path().to_path_buf()),
+            line_reader: Some(Arc::new(Mutex::new(LineReader::from_index(
+                temp_file.path(),
+                index.clone(),
+            )?))),
+            current_index: Some(Arc::new(index)),
+            ..Default::default()
+        });
+
+        // 模拟长时间读取: 持有读取器锁 (按约定先短暂获取状态锁再释放)
+        let reader = state.lock().unwrap().reader().unwrap();
+        let busy = reader.lock().unwrap();
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                // 与 get_file_index 相同的读取
+                let index = state.lock().unwrap().current_index.clone();
+                tx.send(index.map(|index| index.total_lines)).unwrap();
+            });
+
+            let total_lines = rx
+                .recv_timeout(Duration::from_secs(5))
+                .expect("index query blocked by the reader lock");
+            assert_eq!(total_lines, Some(10));
+        });
+
+        drop(busy);
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover_logs() -> std::io::Result<()> {
+        use std::fs::File;
+        use std::io::Write;
+        use std::time::SystemTime;
+
+        let dir = tempfile::tempdir()?;
+        let backup = dir.path().join("Backup");
+        std::fs::create_dir(&backup)?;
+
+        let write = |path: PathBuf, content: &str, age_secs: u64| -> std::io::Result<PathBuf> {
+            let file = File::create(&path)?;
+            (&file).write_all(content.as_bytes())?;
+            file.set_modified(SystemTime::now() - Duration::from_secs(age_secs))?;
+            Ok(path)
+        };
+        let game = write(dir.path().join("Game.log"), "LogInit: Display: A\n", 10)?;
+        let notes = write(dir.path().join("Notes.TXT"), "a\nb\nc", 20)?;
+        write(dir.path().join("Hero.uasset"), "binary", 0)?;
+        let old = write(backup.join("Game-backup.log"), "LogInit: Display: B\n", 30)?;
+
+        let paths = |logs: &[LogFileInfo]| -> Vec<String> {
+            logs.iter().map(|info| info.path.clone()).collect()
+        };
+        let dir_str = dir.path().to_string_lossy().to_string();
+
+        let logs = discover_logs(dir_str.clone(), false).unwrap();
+        assert_eq!(
+            paths(&logs),
+            vec![
+                game.to_string_lossy().to_string(),
+                notes.to_string_lossy().to_string(),
+            ]
+        );
+        assert_eq!(logs[1].line_count_estimate, 3);
+        assert_eq!(logs[0].size, 20);
+
+        let logs = discover_logs(dir_str, true).unwrap();
+        assert_eq!(logs.len(), 3);
+        assert_eq!(logs[2].path, old.to_string_lossy());
+
+        assert!(discover_logs(
+            dir.path().join("missing").to_string_lossy().to_string(),
+            true
+        )
+        .is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_missing_file() {
+        let err = resolve_log_path("/definitely/not/here/Game.log").unwrap_err();
+        assert!(err.starts_with("File not found"));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_resolve_long_path() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let mut deep = dir.path().to_path_buf();
+        while deep.as_os_str().len() < MAX_PATH + 20 {
+            deep.push("a_fairly_long_directory_name");
+        }
+        let verbatim_dir = PathBuf::from(format!(r"\\?\{}", deep.display()));
+        std::fs::create_dir_all(&verbatim_dir)?;
+        let log_path = verbatim_dir.join("Game.log");
+        std::fs::write(&log_path, "LogInit: Display: Line 1\n")?;
+
+        let resolved = resolve_log_path(log_path.to_str().unwrap()).unwrap();
+        assert!(resolved.to_str().unwrap().starts_with(r"\\?\"));
+        assert!(std::fs::File::open(&resolved).is_ok());
+
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_resolve_unc_path() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log_path = dir.path().join("Game.log");
+        std::fs::write(&log_path, "LogInit: Display: Line 1\n")?;
+
+        // 通过本机管理共享访问同一文件: \\localhost\C$\...
+        let local = log_path.to_str().unwrap();
+        let drive = &local[..1];
+        let unc = format!(r"\\localhost\{}$\{}", drive, &local[3..]);
+
+        let resolved = resolve_log_path(&unc).unwrap();
+        assert!(resolved.to_str().unwrap().starts_with(r"\\"));
+        assert!(std::fs::File::open(&resolved).is_ok());
+
+        Ok(())
+    }
+}
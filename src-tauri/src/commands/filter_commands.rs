@@ -1,9 +1,15 @@
 //! Filter commands
 
-use std::sync::Mutex;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 
 use crate::commands::file_commands::AppState;
-use crate::parser::{FileIndex, LogLevel};
+use crate::parser::{
+    CategoryLines, CategorySample, FileIndex, FilterOptions, LevelHistogram, LogChunk, LogEntry,
+    LogLevel, LogParser, ParserConfig,
+};
+use crate::streaming::encoding::decode_lines_lossy;
+use crate::streaming::{buffered, sample_categories, skip_bom, FileIndexer};
 
 /// Result for filtered lines query
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -14,31 +20,88 @@ pub struct FilteredLinesResult {
     pub total_count: u64,
 }
 
+/// Result for a filtered range read
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FilteredChunk {
+    /// First line of the scanned range
+    pub start_line: u64,
+    /// Last line of the scanned range
+    pub end_line: u64,
+    /// Entries that passed the filter
+    pub entries: Vec<LogEntry>,
+    /// Number of lines scanned in the range
+    pub total_scanned: u64,
+}
+
+/// Read a line range and keep only entries that pass `options`
+///
+/// Empty include lists mean "all"; `exclude_categories` always applies.
+#[tauri::command]
+pub fn filter_logs(
+    options: FilterOptions,
+    start_line: u64,
+    end_line: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<FilteredChunk, String> {
+    let reader = state.lock().map_err(|e| e.to_string())?.reader()?;
+    let mut reader = reader.lock().map_err(|e| e.to_string())?;
+
+    let chunk = reader
+        .read_range(start_line, end_line)
+        .map_err(|e| e.to_string())?;
+
+    Ok(filter_chunk(chunk, &options))
+}
+
+/// Apply the filter to an already loaded chunk
+fn filter_chunk(chunk: LogChunk, options: &FilterOptions) -> FilteredChunk {
+    let total_scanned = chunk.entries.len() as u64;
+
+    FilteredChunk {
+        start_line: chunk.start_line,
+        end_line: chunk.end_line,
+        entries: chunk
+            .entries
+            .into_iter()
+            .filter(|entry| options.matches(entry))
+            .collect(),
+        total_scanned,
+    }
+}
+
 /// Get line numbers that match the given filter criteria
+///
+/// `min_level` keeps only lines at least that severe; it combines with `levels`.
 #[tauri::command]
 pub fn get_filtered_lines(
     levels: Vec<String>,
     categories: Vec<String>,
+    min_level: Option<String>,
     state: tauri::State<'_, Mutex<AppState>>,
 ) -> Result<FilteredLinesResult, String> {
-    let state = state.lock().map_err(|e| e.to_string())?;
-
-    let file_path = state.current_file.as_ref().ok_or("No file opened")?;
-
-    let index = state
-        .current_index
-        .as_ref()
-        .ok_or("No file index available")?;
+    let (file_path, index, parser_config) = {
+        let state = state.lock().map_err(|e| e.to_string())?;
+        let (file_path, index) = state.file_and_index()?;
+        (file_path, index, state.parser_config.clone())
+    };
 
     // Convert string levels to LogLevel
     let level_set: Vec<LogLevel> = levels.iter().map(|l| LogLevel::from_str(l)).collect();
+    let min_level = min_level.as_deref().map(LogLevel::from_str);
 
     // Build category set for faster lookup
     let category_set: std::collections::HashSet<String> = categories.iter().cloned().collect();
 
     // Scan file for matching lines
-    let line_numbers = scan_file_for_filter(file_path, index, &level_set, &category_set)
-        .map_err(|e| format!("Failed to scan file: {}", e))?;
+    let line_numbers = scan_file_for_filter(
+        &file_path,
+        &index,
+        &parser_config,
+        &level_set,
+        min_level,
+        &category_set,
+    )
+    .map_err(|e| format!("Failed to scan file: {}", e))?;
 
     let total_count = line_numbers.len() as u64;
 
@@ -49,21 +112,26 @@ pub fn get_filtered_lines(
 }
 
 /// Scan file and find lines matching the filter
+///
+/// Lines are parsed with `parser_config`, the same way the reader parses them
+/// for display, so custom patterns and level aliases filter as they show.
 fn scan_file_for_filter(
     file_path: &std::path::Path,
-    index: &FileIndex,
+    _index: &FileIndex,
+    parser_config: &ParserConfig,
     levels: &[LogLevel],
+    min_level: Option<LogLevel>,
     categories: &std::collections::HashSet<String>,
 ) -> std::io::Result<Vec<u64>> {
     use std::fs::File;
-    use std::io::{BufRead, BufReader};
 
     let file = File::open(file_path)?;
-    let reader = BufReader::new(file);
+    let mut reader = buffered(file);
+    let encoding = skip_bom(&mut reader)?;
     let mut matching_lines = Vec::new();
 
     // Determine filter modes
-    let filter_by_level = !levels.is_empty();
+    let filter_by_level = !levels.is_empty() || min_level.is_some();
     let filter_by_category = !categories.is_empty();
 
     // If no filters, return empty (meaning show all)
@@ -71,19 +139,20 @@ fn scan_file_for_filter(
         return Ok(Vec::new());
     }
 
-    for (line_num, line_result) in reader.lines().enumerate() {
+    // Invalid bytes are replaced like the reader does, so one bad byte can't fail the scan
+    for (line_num, line_result) in decode_lines_lossy(reader, encoding).enumerate() {
         let line_number = (line_num + 1) as u64;
-        let line = line_result?;
+        let (line, _) = line_result?;
 
-        // Extract level and category from the line
-        // If level cannot be extracted, treat it as Unknown
-        let line_level = crate::parser::LogParser::extract_level(&line)
-            .unwrap_or(LogLevel::Unknown);
-        let line_category = crate::parser::LogParser::extract_category(&line);
+        // Lines without a level (continuations, raw lines) parse as Unknown
+        let entry = LogParser::parse_line_with(parser_config, line_number, &line);
+        let line_level = entry.level;
+        let line_category = entry.category;
 
         // Check if line matches filter
         let level_match = if filter_by_level {
-            levels.contains(&line_level)
+            (levels.is_empty() || levels.contains(&line_level))
+                && !matches!(min_level, Some(min) if line_level < min)
         } else {
             true
         };
@@ -91,8 +160,7 @@ fn scan_file_for_filter(
         let category_match = if filter_by_category {
             line_category
                 .as_ref()
-                .map(|c| categories.contains(c))
-                .unwrap_or(false)
+                .is_some_and(|c| categories.contains(&**c))
         } else {
             true
         };
@@ -109,3 +177,393 @@ fn scan_file_for_filter(
 
     Ok(matching_lines)
 }
+
+/// Get the most common log level in each of `regions` equal line ranges
+///
+/// Used to color the scrollbar heatmap. Lines without a recognizable level
+/// (continuations, raw lines) don't vote; a region with no leveled lines is
+/// reported as `Unknown`. Ties resolve to the more severe level. At most one
+/// region per line is returned. Runs on a background thread.
+#[tauri::command(async)]
+pub fn region_levels(
+    regions: usize,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<LogLevel>, String> {
+    let (file_path, index, parser_config) = {
+        let state = state.lock().map_err(|e| e.to_string())?;
+        let (file_path, index) = state.file_and_index()?;
+        (file_path, index, state.parser_config.clone())
+    };
+
+    scan_region_levels(&file_path, &parser_config, index.total_lines, regions)
+        .map_err(|e| format!("Failed to scan file: {}", e))
+}
+
+/// Tally levels per region and pick the dominant one for each
+///
+/// Lines are decoded leniently and parsed with `parser_config`, as the reader does.
+fn scan_region_levels(
+    file_path: &std::path::Path,
+    parser_config: &ParserConfig,
+    total_lines: u64,
+    regions: usize,
+) -> std::io::Result<Vec<LogLevel>> {
+    use std::fs::File;
+
+    if regions == 0 || total_lines == 0 {
+        return Ok(Vec::new());
+    }
+    // Never allocate more regions than there are lines, whatever the caller asks for
+    let regions = regions.min(usize::try_from(total_lines).unwrap_or(usize::MAX));
+
+    let file = File::open(file_path)?;
+    let mut reader = buffered(file);
+    let encoding = skip_bom(&mut reader)?;
+    let mut tallies: Vec<HashMap<LogLevel, u64>> = vec![HashMap::new(); regions];
+
+    for (line_num, line_result) in decode_lines_lossy(reader, encoding).enumerate() {
+        let (line, _) = line_result?;
+
+        let level = LogParser::parse_line_with(parser_config, line_num as u64 + 1, &line).level;
+        if level == LogLevel::Unknown {
+            continue;
+        }
+
+        // Map the line into its region proportionally (widened so the product can't overflow)
+        let region = (line_num as u128 * regions as u128 / total_lines as u128) as usize;
+        *tallies[region.min(regions - 1)].entry(level).or_insert(0) += 1;
+    }
+
+    Ok(tallies
+        .into_iter()
+        .map(|tally| {
+            tally
+                .into_iter()
+                .max_by_key(|(level, count)| (*count, level.severity()))
+                .map(|(level, _)| level)
+                .unwrap_or(LogLevel::Unknown)
+        })
+        .collect())
+}
+
+/// Most line numbers recorded per category for `get_category_lines`
+const CATEGORY_LINES_CAP: usize = 100_000;
+
+/// Get the sorted line numbers where `category` appears (for the minimap)
+///
+/// The per-category line index is built on first use and kept in `AppState`
+/// next to (not inside) the current file index, so the index itself is never
+/// replaced. Each category is capped at `CATEGORY_LINES_CAP` lines.
+#[tauri::command(async)]
+pub fn get_category_lines(
+    category: String,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<u64>, String> {
+    let category_lines = current_category_lines(&state)?;
+    Ok(category_lines
+        .lines
+        .get(&category)
+        .cloned()
+        .unwrap_or_default())
+}
+
+/// The category line index for the current file, building it on first use
+fn current_category_lines(state: &Mutex<AppState>) -> Result<Arc<CategoryLines>, String> {
+    let (file_path, index, cached) = {
+        let state = state.lock().map_err(|e| e.to_string())?;
+        let (file_path, index) = state.file_and_index()?;
+        let cached = state.category_lines_of(&index);
+        (file_path, index, cached)
+    };
+    if let Some(category_lines) = cached {
+        return Ok(category_lines);
+    }
+
+    // Build without holding the state lock
+    let mut built = FileIndexer::open(&file_path)
+        .map_err(|e| format!("Failed to index file: {}", e))?
+        .build_index_with_category_lines(CATEGORY_LINES_CAP);
+    let category_lines = Arc::new(CategoryLines::take_from(&mut built).unwrap_or_default());
+
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    // Skip caching if the file was reopened or refreshed meanwhile
+    let unchanged = state
+        .current_index
+        .as_ref()
+        .is_some_and(|current| Arc::ptr_eq(current, &index));
+    if unchanged {
+        state.category_lines = Some((Arc::downgrade(&index), category_lines.clone()));
+    }
+
+    Ok(category_lines)
+}
+
+/// List categories from the current index, most frequent first
+///
+/// Ties are broken by name. Returns an empty list while no file is open so the
+/// sidebar can render during load.
+#[tauri::command]
+pub fn get_categories_sorted(
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<(String, u64)>, String> {
+    let state = state.lock().map_err(|e| e.to_string())?;
+
+    Ok(state
+        .current_index
+        .as_ref()
+        .map(|index| sort_categories(&index.categories))
+        .unwrap_or_default())
+}
+
+/// Level counts of the current index as a struct with one field per level
+///
+/// Missing levels are zero; returns an all-zero histogram while no file is open.
+#[tauri::command]
+pub fn get_level_histogram(
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<LevelHistogram, String> {
+    let state = state.lock().map_err(|e| e.to_string())?;
+
+    Ok(state
+        .current_index
+        .as_ref()
+        .map(|index| LevelHistogram::from_counts(&index.level_counts))
+        .unwrap_or_default())
+}
+
+/// Sort category counts by count descending, then name ascending
+fn sort_categories(categories: &HashMap<String, u64>) -> Vec<(String, u64)> {
+    let mut sorted: Vec<(String, u64)> = categories
+        .iter()
+        .map(|(name, count)| (name.clone(), *count))
+        .collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    sorted
+}
+
+/// Lines without a new category after which sampling stops
+const CATEGORY_SAMPLE_STABLE_LINES: u64 = 5000;
+
+/// Discover categories from the head of the file without a full scan
+///
+/// Scans at most `max_lines` lines and stops early once no new category has
+/// appeared for a while. `complete` is false when the file wasn't read to the
+/// end, so the filter UI can hint that more categories may exist.
+#[tauri::command]
+pub fn discover_categories_sampled(
+    max_lines: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<CategorySample, String> {
+    let file_path = {
+        let state = state.lock().map_err(|e| e.to_string())?;
+        state.current_file.clone().ok_or("No file opened")?
+    };
+
+    sample_categories(&file_path, max_lines, CATEGORY_SAMPLE_STABLE_LINES)
+        .map_err(|e| format!("Failed to scan file: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_category_lines_kept_outside_index() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=300 {
+            let category = if i % 100 == 0 { "LogRare" } else { "LogInit" };
+            writeln!(temp_file, "{}: Display: Line {}", category, i)?;
+        }
+
+        let index = Arc::new(crate::streaming::index_file(temp_file.path())?);
+        let state = Mutex::new(AppState {
+            current_file: Some(temp_file.path().to_path_buf()),
+            current_index: Some(index.clone()),
+            ..Default::default()
+        });
+
+        let built = current_category_lines(&state).unwrap();
+        assert_eq!(built.complete("LogRare"), Some(&[100, 200, 300][..]));
+        assert_eq!(built.complete("LogMissing"), Some(&[][..]));
+
+        // The current index is left untouched, so refreshes still see the same Arc
+        let state = state.into_inner().unwrap();
+        assert!(Arc::ptr_eq(state.current_index.as_ref().unwrap(), &index));
+        assert!(index.category_lines.is_none());
+
+        // Later calls reuse the cached lines
+        let state = Mutex::new(state);
+        assert!(Arc::ptr_eq(
+            &current_category_lines(&state).unwrap(),
+            &built
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_chunk_counts() {
+        let lines = [
+            "LogNet: Error: Connection lost",
+            "LogInit: Display: Ready",
+            "LogNet: Display: Retrying",
+            "LogSpam: Error: Noise",
+        ];
+        let chunk = LogChunk {
+            start_line: 1,
+            end_line: 4,
+            entries: lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| crate::parser::LogParser::parse_line(i as u64 + 1, line))
+                .collect(),
+            read_errors: vec![],
+        };
+        let options = FilterOptions {
+            levels: vec![LogLevel::Error],
+            exclude_categories: vec!["LogSpam".to_string()],
+            ..Default::default()
+        };
+
+        let filtered = filter_chunk(chunk, &options);
+        assert_eq!(filtered.total_scanned, 4);
+        let matched: Vec<u64> = filtered.entries.iter().map(|e| e.line_number).collect();
+        assert_eq!(matched, vec![1]);
+    }
+
+    #[test]
+    fn test_filter_lines_with_parser_config() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogCore: Critical: Out of memory")?;
+        writeln!(temp_file, "[Net] Error Timeout")?;
+        writeln!(temp_file, "LogInit: Display: Ready")?;
+
+        let index = crate::streaming::index_file(temp_file.path())?;
+        let groups = crate::parser::ParseGroupMap {
+            category: Some("cat".to_string()),
+            level: Some("level".to_string()),
+            message: Some("msg".to_string()),
+            ..Default::default()
+        };
+        let mut config = ParserConfig {
+            custom_pattern: Some(
+                crate::parser::CustomPattern::new(
+                    r"^\[(?P<cat>\w+)\] (?P<level>\w+) (?P<msg>.*)$",
+                    groups,
+                )
+                .unwrap(),
+            ),
+            ..Default::default()
+        };
+        config
+            .level_aliases
+            .insert("Critical".to_string(), LogLevel::Error);
+
+        let errors = [LogLevel::Error];
+        let no_categories = std::collections::HashSet::new();
+        let lines = scan_file_for_filter(
+            temp_file.path(),
+            &index,
+            &config,
+            &errors,
+            None,
+            &no_categories,
+        )?;
+        assert_eq!(lines, vec![1, 2]);
+
+        let net: std::collections::HashSet<String> = ["Net".to_string()].into_iter().collect();
+        let lines = scan_file_for_filter(temp_file.path(), &index, &config, &[], None, &net)?;
+        assert_eq!(lines, vec![2]);
+
+        // Without the config neither line is an error
+        let lines = scan_file_for_filter(
+            temp_file.path(),
+            &index,
+            &ParserConfig::default(),
+            &errors,
+            None,
+            &no_categories,
+        )?;
+        assert!(lines.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_region_levels() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=50 {
+            writeln!(temp_file, "LogNet: Error: Failure {}", i)?;
+        }
+        for i in 1..=50 {
+            writeln!(temp_file, "LogInit: Display: Ok {}", i)?;
+        }
+
+        let levels = scan_region_levels(temp_file.path(), &ParserConfig::default(), 100, 2)?;
+        assert_eq!(levels, vec![LogLevel::Error, LogLevel::Display]);
+
+        // Oversized requests are clamped to one region per line
+        let levels =
+            scan_region_levels(temp_file.path(), &ParserConfig::default(), 100, usize::MAX)?;
+        assert_eq!(levels.len(), 100);
+        assert_eq!(levels[0], LogLevel::Error);
+        assert_eq!(levels[99], LogLevel::Display);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_region_levels_lossy_with_config() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"LogNet: Error: bad byte \xFF here\n")?;
+        writeln!(temp_file, "LogCore: Critical: Out of memory")?;
+
+        let mut config = ParserConfig::default();
+        config
+            .level_aliases
+            .insert("Critical".to_string(), LogLevel::Error);
+        let levels = scan_region_levels(temp_file.path(), &config, 2, 2)?;
+        assert_eq!(levels, vec![LogLevel::Error, LogLevel::Error]);
+
+        let no_filter = std::collections::HashSet::new();
+        let index = crate::streaming::index_file(temp_file.path())?;
+        let lines = scan_file_for_filter(
+            temp_file.path(),
+            &index,
+            &config,
+            &[LogLevel::Error],
+            None,
+            &no_filter,
+        )?;
+        assert_eq!(lines, vec![1, 2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sort_categories() {
+        let categories: HashMap<String, u64> = [("LogNet", 5), ("LogAudio", 2), ("LogInit", 5)]
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+
+        let sorted = sort_categories(&categories);
+        let names: Vec<&str> = sorted.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["LogInit", "LogNet", "LogAudio"]);
+        assert!(sort_categories(&HashMap::new()).is_empty());
+    }
+
+    #[test]
+    fn test_region_levels_tie_prefers_severe() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Ok")?;
+        writeln!(temp_file, "LogCore: Warning: Slow")?;
+
+        let levels = scan_region_levels(temp_file.path(), &ParserConfig::default(), 2, 1)?;
+        assert_eq!(levels, vec![LogLevel::Warning]);
+
+        Ok(())
+    }
+}
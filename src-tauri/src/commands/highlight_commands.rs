@@ -0,0 +1,40 @@
+//! 高亮命令
+
+use std::sync::Mutex;
+
+use crate::commands::file_commands::AppState;
+use crate::parser::highlight::{Highlighter, DEFAULT_HIGHLIGHTER};
+use crate::parser::{HighlightConfig, HighlightSpan};
+
+/// 计算一行的高亮片段 (按起始位置升序，互不重叠)
+///
+/// 未配置自定义模式时使用默认的路径/UUID/数字模式
+#[tauri::command]
+pub fn highlight_line(
+    text: String,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<HighlightSpan>, String> {
+    let state = state.lock().map_err(|e| e.to_string())?;
+    let highlighter = state.highlighter.as_ref().unwrap_or(&DEFAULT_HIGHLIGHTER);
+    Ok(highlighter.spans(&text))
+}
+
+/// 设置高亮模式 (kind, regex)，排在前面的优先级更高
+///
+/// 模式只在这里编译一次；传入空列表恢复默认模式
+#[tauri::command]
+pub fn set_highlight_patterns(
+    patterns: Vec<(String, String)>,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let config = HighlightConfig { patterns };
+    let highlighter = if config.patterns.is_empty() {
+        None
+    } else {
+        Some(Highlighter::new(&config)?)
+    };
+
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    state.highlighter = highlighter;
+    Ok(())
+}
@@ -1,7 +1,13 @@
 //! Tauri 命令模块
 
+pub mod analyze_commands;
+pub mod bookmark_commands;
+pub mod diff_commands;
+pub mod export_commands;
 pub mod file_commands;
 pub mod filter_commands;
+pub mod highlight_commands;
 pub mod search_commands;
+pub mod watch_commands;
 
 pub use file_commands::AppState;
@@ -1,31 +1,271 @@
 //! 搜索命令
 
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tauri::{AppHandle, Emitter};
 
 use crate::commands::file_commands::AppState;
-use crate::parser::{SearchOptions, SearchResult};
-use crate::search::SearchEngine;
+use crate::parser::{
+    FileIndex, NextMatch, ParserConfig, RegexErrorInfo, SearchHitWithContext, SearchOptions,
+    SearchResult, SearchResults,
+};
+use crate::search::{self, SearchEngine};
 
-/// 执行搜索
-#[tauri::command]
+/// 分批搜索结果事件名
+pub const SEARCH_BATCH_EVENT: &str = "search-batch";
+
+/// 分批搜索完成事件名
+pub const SEARCH_COMPLETE_EVENT: &str = "search-complete";
+
+/// 每个 search-batch 事件最多携带的结果数
+const SEARCH_BATCH_SIZE: usize = 500;
+
+/// 执行搜索，结果数超过 options.max_results (默认 50000) 时截断并标记 truncated
+///
+/// 在后台线程执行，扫描期间不持有状态锁；可通过 cancel_search 取消，
+/// 此时返回已找到的结果并标记 cancelled。新的搜索会取消仍在进行的搜索
+#[tauri::command(async)]
 pub fn search_logs(
     options: SearchOptions,
     state: tauri::State<'_, Mutex<AppState>>,
-) -> Result<Vec<SearchResult>, String> {
+) -> Result<SearchResults, String> {
+    run_search(&options, &state)
+}
+
+/// search_logs 的实现: 只在开始和结束时短暂持有状态锁 (索引以 Arc 共享，不复制)，
+/// 扫描期间 get_file_index 等命令不会被阻塞
+fn run_search(options: &SearchOptions, state: &Mutex<AppState>) -> Result<SearchResults, String> {
+    let (file_path, index, parser_config, cancel) = begin_search(state)?;
+    let result = search_file(options, parser_config, &file_path, &index, &cancel);
+    end_search(state, &cancel)?;
+    result
+}
+
+/// run_search 的扫描阶段，不持有状态锁
+fn search_file(
+    options: &SearchOptions,
+    parser_config: ParserConfig,
+    file_path: &Path,
+    index: &FileIndex,
+    cancel: &Arc<AtomicBool>,
+) -> Result<SearchResults, String> {
+    search_engine(options, parser_config).and_then(|engine| {
+        engine
+            .with_cancel(cancel.clone())
+            .search_in_file(file_path, index, options)
+            .map_err(|e| e.to_string())
+    })
+}
+
+/// 执行搜索，按行号顺序以 `search-batch` 事件分批发送结果 (每批至多 500 个)，
+/// 结束后发送 `search-complete` 事件 (结果总数、是否截断、是否取消)
+///
+/// 与 search_logs 共用取消标志和结果数上限，忽略排序选项。在后台线程执行
+#[tauri::command(async)]
+pub fn search_streaming(
+    app: AppHandle,
+    options: SearchOptions,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let (file_path, index, parser_config, cancel) = begin_search(&state)?;
+
+    let result = search_engine(&options, parser_config).and_then(|engine| {
+        engine
+            .with_cancel(cancel.clone())
+            .search_in_file_batched(&file_path, &index, &options, SEARCH_BATCH_SIZE, |batch| {
+                if let Err(e) = app.emit(SEARCH_BATCH_EVENT, batch) {
+                    log::warn!("Failed to emit {}: {}", SEARCH_BATCH_EVENT, e);
+                }
+            })
+            .map_err(|e| e.to_string())
+    });
+
+    end_search(&state, &cancel)?;
+    app.emit(SEARCH_COMPLETE_EVENT, result?)
+        .map_err(|e| e.to_string())
+}
+
+/// 在合并打开的所有文件中搜索 (见 open_merged_files)，结果带 source_file，行号为各文件中的行号
+///
+/// 按打开顺序逐个文件搜索并拼接结果，options.max_results 为所有文件共享的上限。
+/// 与 search_logs 共用取消标志，在后台线程执行
+#[tauri::command(async)]
+pub fn search_all_files(
+    options: SearchOptions,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<SearchResults, String> {
+    let (files, parser_config, cancel) = {
+        let mut state = state.lock().map_err(|e| e.to_string())?;
+        let merged = state
+            .merged_reader
+            .clone()
+            .ok_or("No merged files opened")?;
+        let files = merged.lock().map_err(|e| e.to_string())?.files();
+        let parser_config = state.parser_config.clone();
+        (files, parser_config, register_search(&mut state))
+    };
+
+    let result = search_engine(&options, parser_config).and_then(|engine| {
+        engine
+            .with_cancel(cancel.clone())
+            .search_in_files(&files, &options)
+            .map_err(|e| e.to_string())
+    });
+
+    end_search(&state, &cancel)?;
+    result
+}
+
+/// 登记新的搜索 (取消仍在进行的搜索)，返回文件、索引、解析配置和本次搜索的取消标志
+fn begin_search(
+    state: &Mutex<AppState>,
+) -> Result<(PathBuf, Arc<FileIndex>, ParserConfig, Arc<AtomicBool>), String> {
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    let (file_path, index) = state.file_and_index()?;
+    let parser_config = state.parser_config.clone();
+    Ok((file_path, index, parser_config, register_search(&mut state)))
+}
+
+/// 当前文件的路径、索引和解析配置 (不登记取消标志)
+fn search_target(
+    state: &Mutex<AppState>,
+) -> Result<(PathBuf, Arc<FileIndex>, ParserConfig), String> {
     let state = state.lock().map_err(|e| e.to_string())?;
+    let (file_path, index) = state.file_and_index()?;
+    Ok((file_path, index, state.parser_config.clone()))
+}
+
+/// 创建搜索引擎，按当前解析配置识别命中行的级别与类别
+fn search_engine(
+    options: &SearchOptions,
+    parser_config: ParserConfig,
+) -> Result<SearchEngine, String> {
+    SearchEngine::new(options)
+        .map(|engine| engine.with_parser_config(parser_config))
+        .map_err(|e| format!("Invalid search pattern: {}", e))
+}
 
-    let file_path = state.current_file.as_ref().ok_or("No file opened")?;
+/// 创建本次搜索的取消标志，并取消仍在进行的搜索
+fn register_search(state: &mut AppState) -> Arc<AtomicBool> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    if let Some(previous) = state.search_cancel.replace(cancel.clone()) {
+        previous.store(true, Ordering::Relaxed);
+    }
+    cancel
+}
 
-    let index = state
-        .current_index
+/// 搜索结束，只清除自己的标志 (期间可能已有新的搜索开始)
+fn end_search(state: &Mutex<AppState>, cancel: &Arc<AtomicBool>) -> Result<(), String> {
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    if state
+        .search_cancel
         .as_ref()
-        .ok_or("No file index available")?;
+        .is_some_and(|current| Arc::ptr_eq(current, cancel))
+    {
+        state.search_cancel = None;
+    }
+    Ok(())
+}
 
-    let engine =
-        SearchEngine::new(&options).map_err(|e| format!("Invalid search pattern: {}", e))?;
+/// 只在指定类别的行中搜索
+///
+/// 已构建类别行号索引 (见 get_category_lines) 且该类别的行号完整时只读取这些行，
+/// 否则退回全文件扫描加类别过滤。与 search_logs 共用取消标志，在后台线程执行
+#[tauri::command(async)]
+pub fn search_in_category(
+    category: String,
+    options: SearchOptions,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<SearchResults, String> {
+    let options = SearchOptions {
+        category_filter: vec![category.clone()],
+        ..options
+    };
+    let (file_path, index, parser_config, cancel) = begin_search(&state)?;
+
+    let result = search_engine(&options, parser_config).and_then(|engine| {
+        let engine = engine.with_cancel(cancel.clone());
+        let category_lines = state
+            .lock()
+            .map_err(|e| e.to_string())?
+            .category_lines_of(&index);
+        match category_lines
+            .as_ref()
+            .and_then(|category_lines| category_lines.complete(&category))
+        {
+            Some(lines) => engine
+                .search_in_lines(&file_path, &index, lines, &options)
+                .map(|(results, _)| results),
+            None => engine.search_in_file(&file_path, &index, &options),
+        }
+        .map_err(|e| e.to_string())
+    });
+
+    end_search(&state, &cancel)?;
+    result
+}
+
+/// 取消正在进行的 search_logs / search_streaming / search_all_files
+#[tauri::command]
+pub fn cancel_search(state: tauri::State<'_, Mutex<AppState>>) -> Result<(), String> {
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    if let Some(cancel) = state.search_cancel.take() {
+        cancel.store(true, Ordering::Relaxed);
+    }
+    Ok(())
+}
+
+/// 执行搜索，每个命中附带前后 context_lines 行
+///
+/// 上下文行总数有上限 (见 SearchOptions::MAX_CONTEXT_TOTAL_LINES)。在后台线程执行，
+/// 只在读取上下文时短暂锁定行读取器
+#[tauri::command(async)]
+pub fn search_with_context(
+    options: SearchOptions,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<SearchHitWithContext>, String> {
+    let (file_path, index, parser_config, reader) = {
+        let state = state.lock().map_err(|e| e.to_string())?;
+        let (file_path, index) = state.file_and_index()?;
+        let parser_config = state.parser_config.clone();
+        (file_path, index, parser_config, state.reader()?)
+    };
+
+    let engine = search_engine(&options, parser_config)?;
+
+    engine
+        .search_with_context(&file_path, &index, &options, &reader)
+        .map_err(|e| e.to_string())
+}
+
+/// 统计匹配总数 (不返回搜索结果)
+#[tauri::command(async)]
+pub fn count_matches(
+    options: SearchOptions,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<u64, String> {
+    let (file_path, index, parser_config) = search_target(&state)?;
+
+    let engine = search_engine(&options, parser_config)?;
+
+    engine
+        .count_matches(&file_path, &index, &options)
+        .map_err(|e| e.to_string())
+}
+
+/// 有匹配的行号列表 (升序去重)，用于搜索小地图；不受结果数上限影响
+#[tauri::command(async)]
+pub fn search_matching_lines(
+    options: SearchOptions,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<u64>, String> {
+    let (file_path, index, parser_config) = search_target(&state)?;
+
+    let engine = search_engine(&options, parser_config)?;
 
     engine
-        .search_in_file(file_path, index, &options)
+        .matching_lines(&file_path, &index, &options)
         .map_err(|e| e.to_string())
 }
 
@@ -37,23 +277,74 @@ pub fn search_next(
     options: SearchOptions,
     state: tauri::State<'_, Mutex<AppState>>,
 ) -> Result<Vec<SearchResult>, String> {
-    let state = state.lock().map_err(|e| e.to_string())?;
+    let (file_path, index, parser_config) = search_target(&state)?;
 
-    let file_path = state.current_file.as_ref().ok_or("No file opened")?;
+    let engine = search_engine(&options, parser_config)?;
 
-    let index = state
-        .current_index
-        .as_ref()
-        .ok_or("No file index available")?;
+    engine
+        .search_next_page(&file_path, &index, from_line, max_results, &options)
+        .map_err(|e| e.to_string())
+}
+
+/// 查找 from_line 之后的下一个匹配行 (逐个跳转匹配时使用，不收集结果)
+///
+/// wrap 时到达末尾后从开头继续，结果为 Wrapped 以便提示“已从顶部继续搜索”
+#[tauri::command(async)]
+pub fn find_next(
+    from_line: u64,
+    options: SearchOptions,
+    wrap: bool,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<NextMatch, String> {
+    let (file_path, index, parser_config) = search_target(&state)?;
+
+    let engine = search_engine(&options, parser_config)?;
+
+    engine
+        .find_next_wrapping(&file_path, &index, from_line, &options, wrap)
+        .map_err(|e| e.to_string())
+}
+
+/// 查找 from_line 之前的上一个匹配行，wrap 时到达开头后从末尾继续
+#[tauri::command(async)]
+pub fn find_prev(
+    from_line: u64,
+    options: SearchOptions,
+    wrap: bool,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<NextMatch, String> {
+    let (file_path, index, parser_config) = search_target(&state)?;
 
-    let engine =
-        SearchEngine::new(&options).map_err(|e| format!("Invalid search pattern: {}", e))?;
+    let engine = search_engine(&options, parser_config)?;
 
     engine
-        .search_next_page(file_path, index, from_line, max_results)
+        .find_prev_wrapping(&file_path, &index, from_line, &options, wrap)
         .map_err(|e| e.to_string())
 }
 
+/// 搜索上一页 (从 from_line 向前)
+#[tauri::command(async)]
+pub fn search_prev(
+    from_line: u64,
+    max_results: usize,
+    options: SearchOptions,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<SearchResult>, String> {
+    let (file_path, index, parser_config) = search_target(&state)?;
+
+    let engine = search_engine(&options, parser_config)?;
+
+    engine
+        .search_prev_page(&file_path, &index, from_line, max_results, &options)
+        .map_err(|e| e.to_string())
+}
+
+/// 校验正则表达式 (不执行搜索)，错误中带出错位置
+#[tauri::command]
+pub fn validate_regex(pattern: String) -> Result<(), RegexErrorInfo> {
+    search::validate_regex(&pattern)
+}
+
 /// 在字符串中测试正则表达式
 #[tauri::command]
 pub fn test_regex(
@@ -72,3 +363,124 @@ pub fn test_regex(
 
     Ok(engine.search_in_string(&text, 0))
 }
+
+/// 在多行样本中批量测试正则表达式 (每个样本返回一组结果)
+#[tauri::command]
+pub fn test_regex_batch(
+    pattern: String,
+    texts: Vec<String>,
+    case_insensitive: bool,
+) -> Result<Vec<Vec<SearchResult>>, String> {
+    let options = SearchOptions {
+        pattern,
+        use_regex: true,
+        case_insensitive,
+        ..Default::default()
+    };
+
+    let engine = SearchEngine::new(&options).map_err(|e| format!("Invalid regex: {}", e))?;
+
+    Ok(texts
+        .iter()
+        .map(|text| engine.search_in_string(text, 0))
+        .collect())
+}
+
+/// 计算可折叠的行范围 ("只显示匹配" 视图)，在后台线程执行
+#[tauri::command(async)]
+pub fn search_fold_ranges(
+    options: SearchOptions,
+    context: u64,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<Vec<(u64, u64)>, String> {
+    let (file_path, index, parser_config) = search_target(&state)?;
+
+    let engine = search_engine(&options, parser_config)?;
+
+    engine
+        .fold_ranges(&file_path, &index, &options, context)
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::index_file;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    #[test]
+    fn test_regex_batch_per_sample() {
+        let texts = vec![
+            "LogNet: Error: Timeout after 30s".to_string(),
+            "LogInit: Display: Ready".to_string(),
+            "LogNet: Error: Timeout after 5s".to_string(),
+        ];
+
+        let results = test_regex_batch(r"Timeout after \d+s".to_string(), texts, false).unwrap();
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].len(), 1);
+        assert!(results[1].is_empty());
+        assert_eq!(results[2][0].matched_text, "Timeout after 5s");
+    }
+
+    #[test]
+    fn test_regex_batch_invalid_pattern() {
+        assert!(test_regex_batch("(".to_string(), vec![], false).is_err());
+    }
+
+    #[test]
+    fn test_file_index_available_during_search() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=100 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let state = Mutex::new(AppState {
+            current_file: Some(temp_file.path().to_path_buf()),
+            current_index: Some(Arc::new(index)),
+            ..Default::default()
+        });
+        let options = SearchOptions {
+            pattern: "Line".to_string(),
+            use_regex: false,
+            ..Default::default()
+        };
+
+        // 按 run_search 的步骤执行: 登记之后、扫描期间不持有状态锁
+        let (file_path, index, parser_config, cancel) = begin_search(&state).unwrap();
+        {
+            let state = state.try_lock().expect("state lock held during search");
+            // 与 get_file_index 相同的读取
+            assert_eq!(
+                state.current_index.clone().map(|index| index.total_lines),
+                Some(100)
+            );
+        }
+
+        // 新的搜索取消这一次，扫描立即以 cancelled 返回
+        let (_, _, _, next) = begin_search(&state).unwrap();
+        assert!(cancel.load(Ordering::Relaxed));
+        let found =
+            search_file(&options, parser_config.clone(), &file_path, &index, &cancel).unwrap();
+        assert!(found.cancelled);
+        assert!(found.results.is_empty());
+
+        // 被取消的搜索结束时不清除新搜索的标志
+        end_search(&state, &cancel).unwrap();
+        assert!(state
+            .lock()
+            .unwrap()
+            .search_cancel
+            .as_ref()
+            .is_some_and(|current| Arc::ptr_eq(current, &next)));
+
+        let found = search_file(&options, parser_config, &file_path, &index, &next).unwrap();
+        assert!(!found.cancelled);
+        assert_eq!(found.results.len(), 100);
+        end_search(&state, &next).unwrap();
+        assert!(state.lock().unwrap().search_cancel.is_none());
+    }
+}
@@ -0,0 +1,280 @@
+//! 目录监视命令 - 日志目录中出现新的 .log 或已有的 .log 明显增长时通知前端
+
+use notify::event::{ModifyKind, RenameMode};
+use notify::{Event, EventKind, RecursiveMode, Watcher};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use crate::commands::file_commands::AppState;
+use crate::parser::LogFileInfo;
+use crate::streaming::log_file_info;
+
+/// 发现新日志文件的事件名
+pub const NEW_LOG_FILE_EVENT: &str = "new-log-file";
+
+/// 合并文件系统事件的时间窗口 (写日志时每次追加都会产生事件)
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// 已有文件比上次通知时增长超过这么多字节才再次通知
+const GROWTH_THRESHOLD: u64 = 1024 * 1024;
+
+/// 开始监视目录 (不含子目录)，出现新的 .log 文件，或已有的 .log 比上次通知时
+/// 增长超过 GROWTH_THRESHOLD 时发送携带 LogFileInfo 的 `new-log-file` 事件
+///
+/// 开始监视时已存在的文件不会通知。已在监视其他目录时先停止
+#[tauri::command]
+pub fn watch_directory(
+    app: AppHandle,
+    dir: String,
+    state: tauri::State<'_, Mutex<AppState>>,
+) -> Result<(), String> {
+    let dir = PathBuf::from(dir);
+    if !dir.is_dir() {
+        return Err(format!("Not a directory: {}", dir.display()));
+    }
+
+    let tracker = LogTracker::scan(&dir);
+    let (tx, rx) = std::sync::mpsc::channel();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("Failed to create watcher: {}", e))?;
+    watcher
+        .watch(&dir, RecursiveMode::NonRecursive)
+        .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+
+    // 监视器被 drop 后通道关闭，线程随之退出
+    std::thread::spawn(move || {
+        watch_loop(rx, tracker, DEBOUNCE, |info| {
+            if let Err(e) = app.emit(NEW_LOG_FILE_EVENT, info) {
+                log::warn!("Failed to emit {}: {}", NEW_LOG_FILE_EVENT, e);
+            }
+        })
+    });
+
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    state.dir_watcher = Some(watcher);
+    Ok(())
+}
+
+/// 停止监视目录
+#[tauri::command]
+pub fn stop_watching(state: tauri::State<'_, Mutex<AppState>>) -> Result<(), String> {
+    let mut state = state.lock().map_err(|e| e.to_string())?;
+    state.dir_watcher = None;
+    Ok(())
+}
+
+/// 处理文件系统事件直到通道关闭: 每批事件收集 debounce 时长后统一检查涉及的文件
+fn watch_loop(
+    rx: Receiver<notify::Result<Event>>,
+    mut tracker: LogTracker,
+    debounce: Duration,
+    mut on_file: impl FnMut(LogFileInfo),
+) {
+    while let Ok(first) = rx.recv() {
+        let mut paths = HashSet::new();
+        collect_paths(first, &mut paths, &mut tracker);
+
+        let deadline = Instant::now() + debounce;
+        let mut closed = false;
+        loop {
+            match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(event) => collect_paths(event, &mut paths, &mut tracker),
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => {
+                    closed = true;
+                    break;
+                }
+            }
+        }
+
+        for path in paths {
+            if let Some(info) = tracker.check(&path) {
+                on_file(info);
+            }
+        }
+        if closed {
+            break;
+        }
+    }
+}
+
+/// 记录事件涉及的路径；重命名的目标与新建的文件一样检查 (如先写临时文件再改名为 .log)，删除不算新文件
+///
+/// 重命名同时给出新旧路径时，旧路径的记录转给新路径: 已有日志改名 (如 UE 把旧日志移入 Backup 名) 不算新文件
+fn collect_paths(
+    event: notify::Result<Event>,
+    paths: &mut HashSet<PathBuf>,
+    tracker: &mut LogTracker,
+) {
+    match event {
+        Ok(event) => match event.kind {
+            EventKind::Remove(_) | EventKind::Access(_) => {}
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let [from, to] = &event.paths[..] {
+                    tracker.rename(from, to);
+                }
+                paths.extend(event.paths);
+            }
+            _ => paths.extend(event.paths),
+        },
+        Err(e) => log::warn!("Directory watch error: {}", e),
+    }
+}
+
+/// 各 .log 文件上次通知 (或开始监视) 时的大小
+struct LogTracker {
+    sizes: HashMap<PathBuf, u64>,
+}
+
+impl LogTracker {
+    /// 记录目录中已有的 .log 文件，它们不算新文件
+    fn scan(dir: &Path) -> Self {
+        let mut sizes = HashMap::new();
+        match std::fs::read_dir(dir) {
+            Ok(entries) => {
+                for path in entries.flatten().map(|entry| entry.path()) {
+                    if has_log_extension(&path) && path.is_file() {
+                        if let Ok(metadata) = std::fs::metadata(&path) {
+                            sizes.insert(path, metadata.len());
+                        }
+                    }
+                }
+            }
+            Err(e) => log::warn!("Failed to read directory {}: {}", dir.display(), e),
+        }
+        Self { sizes }
+    }
+
+    /// 已记录的文件改名后沿用原来的记录
+    fn rename(&mut self, from: &Path, to: &Path) {
+        if let Some(size) = self.sizes.remove(from) {
+            self.sizes.insert(to.to_path_buf(), size);
+        }
+    }
+
+    /// 文件是新出现的、被截断后重新写入的，或比上次通知时增长超过阈值时返回其信息
+    fn check(&mut self, path: &Path) -> Option<LogFileInfo> {
+        if !has_log_extension(path) {
+            return None;
+        }
+        let Ok(info) = log_file_info(path) else {
+            // 已被删除
+            self.sizes.remove(path);
+            return None;
+        };
+
+        let notify = match self.sizes.get(path) {
+            None => true,
+            Some(&last) => info.size < last || info.size - last >= GROWTH_THRESHOLD,
+        };
+        if notify {
+            self.sizes.insert(path.to_path_buf(), info.size);
+            Some(info)
+        } else {
+            None
+        }
+    }
+}
+
+/// 扩展名是否为 .log (不区分大小写)
+fn has_log_extension(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("log"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::event::{CreateKind, DataChange, RenameMode};
+
+    fn event(kind: EventKind, path: &Path) -> notify::Result<Event> {
+        Ok(Event::new(kind).add_path(path.to_path_buf()))
+    }
+
+    #[test]
+    fn test_watch_loop_debounces_and_filters() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let existing = dir.path().join("Game.log");
+        std::fs::write(&existing, "LogInit: Display: Old\n")?;
+        let tracker = LogTracker::scan(dir.path());
+
+        let fresh = dir.path().join("Crash.log");
+        std::fs::write(&fresh, "LogWindows: Error: Fatal error\n")?;
+        let moved_in = dir.path().join("Server.log");
+        std::fs::write(&moved_in, "LogInit: Display: Written elsewhere\n")?;
+        let backup = dir.path().join("Game-backup-2026.02.14.log");
+        std::fs::rename(&existing, &backup)?;
+        let other = dir.path().join("Hero.uasset");
+        std::fs::write(&other, "binary")?;
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let data = EventKind::Modify(ModifyKind::Data(DataChange::Content));
+        // 同一文件的多次事件只通知一次
+        tx.send(event(EventKind::Create(CreateKind::File), &fresh))
+            .unwrap();
+        tx.send(event(data, &fresh)).unwrap();
+        tx.send(event(data, &fresh)).unwrap();
+        // 改名为 .log 的文件与新建的一样通知
+        tx.send(event(
+            EventKind::Modify(ModifyKind::Name(RenameMode::To)),
+            &moved_in,
+        ))
+        .unwrap();
+        // 已有文件改名、非日志文件都不通知
+        tx.send(event(
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+            &existing,
+        ))
+        .unwrap();
+        tx.send(Ok(Event::new(EventKind::Modify(ModifyKind::Name(
+            RenameMode::Both,
+        )))
+        .add_path(existing.clone())
+        .add_path(backup.clone())))
+            .unwrap();
+        tx.send(event(EventKind::Create(CreateKind::File), &other))
+            .unwrap();
+        drop(tx);
+
+        let mut found = Vec::new();
+        watch_loop(rx, tracker, Duration::from_millis(10), |info| {
+            found.push(info.path)
+        });
+        found.sort();
+        let mut expected = vec![
+            fresh.to_string_lossy().to_string(),
+            moved_in.to_string_lossy().to_string(),
+        ];
+        expected.sort();
+        assert_eq!(found, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tracker_growth_threshold() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("Game.log");
+        std::fs::write(&path, "LogInit: Display: Start\n")?;
+        let mut tracker = LogTracker::scan(dir.path());
+        assert!(tracker.check(&path).is_none());
+
+        std::fs::write(&path, vec![b'x'; GROWTH_THRESHOLD as usize + 100])?;
+        let info = tracker.check(&path).unwrap();
+        assert_eq!(info.size, GROWTH_THRESHOLD + 100);
+        assert!(tracker.check(&path).is_none());
+
+        // 被截断后重新写入视为新文件
+        std::fs::write(&path, "LogInit: Display: New session\n")?;
+        assert!(tracker.check(&path).is_some());
+
+        std::fs::remove_file(&path)?;
+        assert!(tracker.check(&path).is_none());
+
+        Ok(())
+    }
+}
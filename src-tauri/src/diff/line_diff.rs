@@ -0,0 +1,443 @@
+//! 行级差异 - 比较两个日志文件
+//!
+//! 每行只保留哈希参与比较 (Myers 差分)，差异块的原始行在比较结束后按需读取
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs::File;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use crate::parser::patterns::PATTERN_TIMESTAMP_PREFIX;
+use crate::parser::{DiffBlock, DiffKind, DiffResult};
+use crate::streaming::encoding::decode_lines_lossy;
+use crate::streaming::{buffered, index_file, skip_bom, LineReader};
+
+/// 每个差异块最多返回的原始行数
+pub const DIFF_BLOCK_LINES_CAP: usize = 1000;
+
+/// Myers 差分的编辑距离上限，超过后中间部分不再对齐
+///
+/// 回溯用的 V 数组共约 D² 个 isize，上限 1000 时最多约 8MB
+pub const MAX_EDIT_DISTANCE: usize = 1000;
+
+/// 逐行比较两个文件
+///
+/// ignore_timestamps 为 true 时先去掉行首的 `[时间戳][帧号]` 再比较，只比较消息部分
+pub fn diff_files<P: AsRef<Path>, Q: AsRef<Path>>(
+    path_a: P,
+    path_b: Q,
+    ignore_timestamps: bool,
+) -> std::io::Result<DiffResult> {
+    let a = line_hashes(path_a.as_ref(), ignore_timestamps)?;
+    let b = line_hashes(path_b.as_ref(), ignore_timestamps)?;
+
+    let (ops, approximate) = diff_ops(&a, &b, MAX_EDIT_DISTANCE);
+    let mut blocks = blocks_from_ops(&ops);
+
+    let mut reader_a = LineReader::from_index(path_a.as_ref(), index_file(path_a.as_ref())?)?;
+    let mut reader_b = LineReader::from_index(path_b.as_ref(), index_file(path_b.as_ref())?)?;
+    for block in &mut blocks {
+        block.lines = match block.kind {
+            DiffKind::Removed => read_lines(&mut reader_a, block.a_start, block.a_count)?,
+            DiffKind::Added => read_lines(&mut reader_b, block.b_start, block.b_count)?,
+            DiffKind::Equal => Vec::new(),
+        };
+    }
+
+    Ok(DiffResult {
+        blocks,
+        a_total_lines: a.len() as u64,
+        b_total_lines: b.len() as u64,
+        approximate,
+    })
+}
+
+/// 每行 (CRLF 的 \r 不计入) 的哈希；无效字节按替换字符参与比较，与读取时一致
+fn line_hashes(path: &Path, ignore_timestamps: bool) -> std::io::Result<Vec<u64>> {
+    let mut reader = buffered(File::open(path)?);
+    let encoding = skip_bom(&mut reader)?;
+
+    decode_lines_lossy(reader, encoding)
+        .map(|line| {
+            let (line, _) = line?;
+            let mut line = line.strip_suffix('\r').unwrap_or(&line);
+            if ignore_timestamps {
+                if let Some(prefix) = PATTERN_TIMESTAMP_PREFIX.find(line) {
+                    line = &line[prefix.end()..];
+                }
+            }
+
+            let mut hasher = DefaultHasher::new();
+            line.hash(&mut hasher);
+            Ok(hasher.finish())
+        })
+        .collect()
+}
+
+/// 从 start 行起读取 count 行的原始内容 (最多 DIFF_BLOCK_LINES_CAP 行)
+fn read_lines(reader: &mut LineReader, start: u64, count: u64) -> std::io::Result<Vec<String>> {
+    let count = count.min(DIFF_BLOCK_LINES_CAP as u64) as usize;
+    reader
+        .entries_from(start)?
+        .take(count)
+        .map(|entry| entry.map(|entry| entry.raw.to_string()))
+        .collect()
+}
+
+/// 编辑操作 (连续的同类操作合并)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Op {
+    kind: DiffKind,
+    count: usize,
+}
+
+/// 计算 a -> b 的编辑操作；返回的 bool 表示是否因超过 max_distance 而未完整对齐
+fn diff_ops(a: &[u64], b: &[u64], max_distance: usize) -> (Vec<Op>, bool) {
+    // 先去掉公共前缀和后缀，差分只处理中间部分
+    let prefix = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a_mid = &a[prefix..a.len() - suffix];
+    let b_mid = &b[prefix..b.len() - suffix];
+
+    let mut ops = Vec::new();
+    push_op(&mut ops, DiffKind::Equal, prefix);
+
+    let approximate = match myers(a_mid, b_mid, max_distance) {
+        Some(mid_ops) => {
+            for op in mid_ops {
+                push_op(&mut ops, op.kind, op.count);
+            }
+            false
+        }
+        None => {
+            push_op(&mut ops, DiffKind::Removed, a_mid.len());
+            push_op(&mut ops, DiffKind::Added, b_mid.len());
+            true
+        }
+    };
+
+    push_op(&mut ops, DiffKind::Equal, suffix);
+    (ops, approximate)
+}
+
+/// 追加操作，与上一个同类操作合并
+fn push_op(ops: &mut Vec<Op>, kind: DiffKind, count: usize) {
+    if count == 0 {
+        return;
+    }
+    match ops.last_mut() {
+        Some(last) if last.kind == kind => last.count += count,
+        _ => ops.push(Op { kind, count }),
+    }
+}
+
+/// Myers 差分，编辑距离超过 max_distance 时返回 None
+///
+/// 保存每一步的 V 数组用于回溯，内存约为 O(D²)
+fn myers(a: &[u64], b: &[u64], max_distance: usize) -> Option<Vec<Op>> {
+    let (n, m) = (a.len() as isize, b.len() as isize);
+    let max_d = (n + m).min(max_distance as isize);
+
+    // trace[d][k + d] 为第 d 步对角线 k 上能到达的最远 x
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+
+    for d in 0..=max_d {
+        let mut v = vec![0isize; 2 * d as usize + 1];
+        for k in (-d..=d).step_by(2) {
+            let mut x = if d == 0 {
+                0
+            } else {
+                let prev = &trace[d as usize - 1];
+                let at = |k: isize| prev[(k + d - 1) as usize];
+                if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+                    at(k + 1)
+                } else {
+                    at(k - 1) + 1
+                }
+            };
+            let mut y = x - k;
+            while x < n && y < m && a[x as usize] == b[y as usize] {
+                x += 1;
+                y += 1;
+            }
+            v[(k + d) as usize] = x;
+
+            if x >= n && y >= m {
+                trace.push(v);
+                return Some(backtrack(&trace, n, m));
+            }
+        }
+        trace.push(v);
+    }
+
+    None
+}
+
+/// 从终点沿 trace 回溯出编辑操作
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize) -> Vec<Op> {
+    let mut reversed = Vec::new();
+    let (mut x, mut y) = (n, m);
+
+    for d in (1..trace.len() as isize).rev() {
+        let prev = &trace[d as usize - 1];
+        let at = |k: isize| prev[(k + d - 1) as usize];
+        let k = x - y;
+
+        let prev_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
+        } else {
+            k - 1
+        };
+        let prev_x = at(prev_k);
+        let prev_y = prev_x - prev_k;
+
+        // 向下一步为插入 B 的行，向右一步为删除 A 的行
+        let (kind, edit_x) = if prev_k == k + 1 {
+            (DiffKind::Added, prev_x)
+        } else {
+            (DiffKind::Removed, prev_x + 1)
+        };
+        reversed.push(Op {
+            kind: DiffKind::Equal,
+            count: (x - edit_x) as usize,
+        });
+        reversed.push(Op { kind, count: 1 });
+
+        x = prev_x;
+        y = prev_y;
+    }
+    reversed.push(Op {
+        kind: DiffKind::Equal,
+        count: x as usize,
+    });
+    debug_assert_eq!(x, y);
+
+    let mut ops = Vec::new();
+    for op in reversed.into_iter().rev() {
+        push_op(&mut ops, op.kind, op.count);
+    }
+    ops
+}
+
+/// 将编辑操作转换为差异块，两段相同行之间的删除排在新增之前
+fn blocks_from_ops(ops: &[Op]) -> Vec<DiffBlock> {
+    let mut builder = BlockBuilder {
+        blocks: Vec::new(),
+        a_line: 1,
+        b_line: 1,
+        removed: 0,
+        added: 0,
+    };
+
+    for op in ops {
+        let count = op.count as u64;
+        match op.kind {
+            DiffKind::Removed => builder.removed += count,
+            DiffKind::Added => builder.added += count,
+            DiffKind::Equal => {
+                builder.flush_changes();
+                builder.push(DiffKind::Equal, count, count);
+            }
+        }
+    }
+    builder.flush_changes();
+
+    builder.blocks
+}
+
+/// 按顺序生成差异块，记录两侧的当前行号和尚未输出的删除/新增行数
+struct BlockBuilder {
+    blocks: Vec<DiffBlock>,
+    a_line: u64,
+    b_line: u64,
+    removed: u64,
+    added: u64,
+}
+
+impl BlockBuilder {
+    /// 输出累计的删除和新增
+    fn flush_changes(&mut self) {
+        let (removed, added) = (self.removed, self.added);
+        self.removed = 0;
+        self.added = 0;
+        if removed > 0 {
+            self.push(DiffKind::Removed, removed, 0);
+        }
+        if added > 0 {
+            self.push(DiffKind::Added, 0, added);
+        }
+    }
+
+    fn push(&mut self, kind: DiffKind, a_count: u64, b_count: u64) {
+        self.blocks.push(DiffBlock {
+            kind,
+            a_start: self.a_line,
+            a_count,
+            b_start: self.b_line,
+            b_count,
+            lines: Vec::new(),
+        });
+        self.a_line += a_count;
+        self.b_line += b_count;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn ops_of(a: &[u64], b: &[u64]) -> Vec<(DiffKind, usize)> {
+        let (ops, approximate) = diff_ops(a, b, MAX_EDIT_DISTANCE);
+        assert!(!approximate);
+        ops.iter().map(|op| (op.kind, op.count)).collect()
+    }
+
+    /// 按操作重建 b，验证编辑脚本正确
+    fn apply(a: &[u64], b: &[u64], ops: &[(DiffKind, usize)]) -> Vec<u64> {
+        let (mut i, mut j) = (0, 0);
+        let mut out = Vec::new();
+        for &(kind, count) in ops {
+            match kind {
+                DiffKind::Equal => {
+                    out.extend_from_slice(&a[i..i + count]);
+                    i += count;
+                    j += count;
+                }
+                DiffKind::Removed => i += count,
+                DiffKind::Added => {
+                    out.extend_from_slice(&b[j..j + count]);
+                    j += count;
+                }
+            }
+        }
+        assert_eq!(i, a.len());
+        out
+    }
+
+    #[test]
+    fn test_diff_ops() {
+        use DiffKind::*;
+
+        assert_eq!(ops_of(&[1, 2, 3], &[1, 2, 3]), vec![(Equal, 3)]);
+        assert_eq!(ops_of(&[], &[1, 2]), vec![(Added, 2)]);
+        assert_eq!(
+            ops_of(&[1, 2, 3, 4], &[1, 3, 4, 5]),
+            vec![(Equal, 1), (Removed, 1), (Equal, 2), (Added, 1)]
+        );
+
+        let a = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        let b = [1, 9, 3, 4, 10, 11, 6, 2, 8, 9];
+        let ops = ops_of(&a, &b);
+        assert_eq!(apply(&a, &b, &ops), b);
+    }
+
+    #[test]
+    fn test_diff_ops_over_limit() {
+        let a: Vec<u64> = (0..100).collect();
+        let b: Vec<u64> = (100..200).collect();
+
+        let (ops, approximate) = diff_ops(&a, &b, 10);
+        assert!(approximate);
+        assert_eq!(
+            ops,
+            vec![
+                Op {
+                    kind: DiffKind::Removed,
+                    count: 100
+                },
+                Op {
+                    kind: DiffKind::Added,
+                    count: 100
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_files_invalid_utf8() -> std::io::Result<()> {
+        let mut file_a = NamedTempFile::new()?;
+        file_a.write_all(b"LogInit: Display: Start\nLogNet: Error: bad \xFF byte\n")?;
+        let mut file_b = NamedTempFile::new()?;
+        file_b.write_all(b"LogInit: Display: Start\nLogNet: Error: other\n")?;
+
+        let result = diff_files(file_a.path(), file_b.path(), false)?;
+        assert_eq!((result.a_total_lines, result.b_total_lines), (2, 2));
+        let kinds: Vec<DiffKind> = result.blocks.iter().map(|b| b.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![DiffKind::Equal, DiffKind::Removed, DiffKind::Added]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_diff_files_ignore_timestamps() -> std::io::Result<()> {
+        let mut file_a = NamedTempFile::new()?;
+        writeln!(
+            file_a,
+            "[2026.02.14-03.33.56:070][  0]LogInit: Display: Start"
+        )?;
+        writeln!(
+            file_a,
+            "[2026.02.14-03.33.57:000][  1]LogNet: Display: Connected"
+        )?;
+        writeln!(
+            file_a,
+            "[2026.02.14-03.33.58:000][  2]LogTemp: Display: Tick"
+        )?;
+        let mut file_b = NamedTempFile::new()?;
+        writeln!(
+            file_b,
+            "[2026.02.15-10.00.00:000][  0]LogInit: Display: Start"
+        )?;
+        writeln!(
+            file_b,
+            "[2026.02.15-10.00.01:000][  1]LogNet: Error: Timeout"
+        )?;
+        writeln!(file_b, "[2026.02.15-10.00.01:500][  2]LogNet: Error: Retry")?;
+        writeln!(
+            file_b,
+            "[2026.02.15-10.00.02:000][  3]LogTemp: Display: Tick"
+        )?;
+
+        let result = diff_files(file_a.path(), file_b.path(), true)?;
+        assert!(!result.approximate);
+        assert_eq!((result.a_total_lines, result.b_total_lines), (3, 4));
+
+        let kinds: Vec<DiffKind> = result.blocks.iter().map(|b| b.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                DiffKind::Equal,
+                DiffKind::Removed,
+                DiffKind::Added,
+                DiffKind::Equal
+            ]
+        );
+        let removed = &result.blocks[1];
+        assert_eq!(
+            (removed.a_start, removed.a_count, removed.b_start),
+            (2, 1, 2)
+        );
+        assert!(removed.lines[0].ends_with("Connected"));
+        let added = &result.blocks[2];
+        assert_eq!((added.a_start, added.b_start, added.b_count), (3, 2, 2));
+        assert!(added.lines[1].ends_with("Retry"));
+        let tail = &result.blocks[3];
+        assert_eq!((tail.a_start, tail.b_start, tail.a_count), (3, 4, 1));
+
+        // 不忽略时间戳时每一行都不同
+        let result = diff_files(file_a.path(), file_b.path(), false)?;
+        assert!(result.blocks.iter().all(|b| b.kind != DiffKind::Equal));
+
+        Ok(())
+    }
+}
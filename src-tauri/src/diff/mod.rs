@@ -0,0 +1,5 @@
+//! 日志比较模块
+
+pub mod line_diff;
+
+pub use line_diff::{diff_files, DIFF_BLOCK_LINES_CAP, MAX_EDIT_DISTANCE};
@@ -2,10 +2,11 @@
 //!
 //! 高性能 Unreal Engine 日志解析器
 
-mod commands;
-mod parser;
-mod search;
-mod streaming;
+pub mod commands;
+pub mod diff;
+pub mod parser;
+pub mod search;
+pub mod streaming;
 
 use std::sync::Mutex;
 use tauri::Manager;
@@ -26,16 +27,97 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // 文件命令
             commands::file_commands::open_log_file,
+            commands::file_commands::open_log_file_with_progress,
+            commands::file_commands::open_merged_files,
+            commands::file_commands::load_merged_chunk,
+            commands::file_commands::precompute_index,
+            commands::file_commands::index_benchmark,
+            commands::file_commands::discover_logs,
+            commands::file_commands::refresh_file,
+            commands::file_commands::start_tailing,
+            commands::file_commands::stop_tailing,
             commands::file_commands::load_chunk,
+            commands::file_commands::peek_around,
+            commands::file_commands::load_tail,
+            commands::file_commands::load_chunk_merged,
+            commands::file_commands::load_chunk_deduped,
+            commands::file_commands::load_byte_range,
+            commands::file_commands::byte_offset_to_line,
+            commands::file_commands::line_to_byte_offset,
+            commands::file_commands::load_chunk_projected,
             commands::file_commands::get_file_index,
             commands::file_commands::close_file,
+            commands::file_commands::set_parser_config,
+            commands::file_commands::set_custom_pattern,
+            commands::file_commands::reparse_chunk,
+            commands::file_commands::scan_unparsed,
+            commands::file_commands::parse_stats,
+            commands::file_commands::get_log_start_time,
+            commands::file_commands::get_log_end_time,
+            commands::file_commands::get_time_range,
+            commands::file_commands::goto_timestamp,
+            commands::file_commands::get_frame_range,
+            commands::file_commands::goto_frame,
+            commands::file_commands::detect_session_resets,
+            commands::file_commands::get_sessions,
+            commands::file_commands::set_io_buffer_size,
+            commands::file_commands::next_same_category,
+            commands::file_commands::prev_same_category,
+            // 目录监视命令
+            commands::watch_commands::watch_directory,
+            commands::watch_commands::stop_watching,
             // 搜索命令
             commands::search_commands::search_logs,
+            commands::search_commands::search_all_files,
+            commands::search_commands::search_streaming,
+            commands::search_commands::search_in_category,
+            commands::search_commands::cancel_search,
+            commands::search_commands::search_with_context,
+            commands::search_commands::count_matches,
+            commands::search_commands::search_matching_lines,
             commands::search_commands::search_next,
+            commands::search_commands::find_next,
+            commands::search_commands::find_prev,
+            commands::search_commands::search_prev,
+            commands::search_commands::validate_regex,
             commands::search_commands::test_regex,
+            commands::search_commands::test_regex_batch,
+            commands::search_commands::search_fold_ranges,
             // 过滤命令
             commands::filter_commands::get_filtered_lines,
+            commands::filter_commands::filter_logs,
+            commands::filter_commands::region_levels,
+            commands::filter_commands::discover_categories_sampled,
+            commands::filter_commands::get_category_lines,
+            commands::filter_commands::get_categories_sorted,
+            commands::filter_commands::get_level_histogram,
+            // 导出命令
+            commands::export_commands::export_range,
+            commands::export_commands::export_structured,
+            // 高亮命令
+            commands::highlight_commands::highlight_line,
+            commands::highlight_commands::set_highlight_patterns,
+            // 分析命令
+            commands::analyze_commands::group_messages,
+            commands::analyze_commands::category_timeline,
+            commands::analyze_commands::get_callstacks,
+            commands::analyze_commands::list_ensures,
+            commands::analyze_commands::extract_paths,
+            // 比较命令
+            commands::diff_commands::diff_files,
+            // 书签命令
+            commands::bookmark_commands::add_bookmark,
+            commands::bookmark_commands::remove_bookmark,
+            commands::bookmark_commands::list_bookmarks,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // 退出时停止目录监视，后台线程随之结束
+            if let tauri::RunEvent::Exit = event {
+                if let Ok(mut state) = app.state::<Mutex<AppState>>().lock() {
+                    state.dir_watcher = None;
+                }
+            }
+        });
 }
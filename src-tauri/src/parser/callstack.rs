@@ -0,0 +1,276 @@
+//! 调用栈识别 - 找出崩溃日志中的调用栈块
+//!
+//! 以 Fatal error / Assertion failed 行开始，后接缩进的续行或 `[Callstack]` 行作为栈帧
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::types::{LogEntry, LogLevel};
+
+/// 栈帧: `0x00007ffb1a2b3c4d UnrealEditor-Core.dll!FDebug::AssertFailed() [D:\...\AssertionMacros.cpp:456]`
+///
+/// 地址和 `[文件:行号]` 都可省略
+static FRAME_PATTERN: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:(0x[0-9A-Fa-f]+)\s+)?([\w.\-]+)!(.+?)(?:\s+\[[^\]]*\])?$")
+        .expect("Invalid stack frame pattern")
+});
+
+/// UE 5 在每个栈帧行的消息前加的标记
+const CALLSTACK_MARKER: &str = "[Callstack]";
+
+/// 调用栈中的一帧
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackFrame {
+    /// 行号
+    pub line_number: u64,
+    /// 原始内容
+    pub raw: String,
+    /// 模块名，如 UnrealEditor-Core.dll
+    pub module: Option<String>,
+    /// 函数名，如 FDebug::AssertFailed()
+    pub function: Option<String>,
+    /// 地址 (十六进制文本，超出 JS 安全整数范围，不转为数字)
+    pub offset: Option<String>,
+}
+
+/// 一个调用栈块
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallStack {
+    /// 起始行 (Fatal error / Assertion failed 所在行)
+    pub start_line: u64,
+    /// 栈帧 (按出现顺序，栈顶在前)
+    pub frames: Vec<StackFrame>,
+    /// 错误消息 (紧随起始行、尚未出现栈帧的同类别错误行会合并进来)
+    pub message: String,
+}
+
+/// 扫描条目，找出所有调用栈 (没有任何栈帧的 Fatal 行不计入)
+pub fn detect_callstacks(
+    entries: impl Iterator<Item = std::io::Result<LogEntry>>,
+) -> std::io::Result<Vec<CallStack>> {
+    let mut stacks = Vec::new();
+    let mut current: Option<(CallStack, Option<String>)> = None;
+
+    for entry in entries {
+        let entry = entry?;
+
+        if let Some((stack, category)) = current.as_mut() {
+            // 空行既不是栈帧也不结束调用栈
+            if entry.is_continuation && entry.raw.trim().is_empty() {
+                continue;
+            }
+            if let Some(frame) = frame_text(&entry) {
+                stack.frames.push(parse_frame(entry.line_number, frame));
+                continue;
+            }
+
+            // 栈帧之前的同类别错误行属于同一条错误消息 (如 Fatal error 之后的 Assertion failed)
+            if stack.frames.is_empty()
+                && entry.category.as_deref() == category.as_deref()
+                && entry.level.severity() >= LogLevel::Error.severity()
+            {
+                let message = entry.message.as_deref().unwrap_or_default().trim();
+                if !message.is_empty() {
+                    stack.message.push('\n');
+                    stack.message.push_str(message);
+                }
+                continue;
+            }
+        }
+
+        if let Some((stack, _)) = current.take() {
+            if !stack.frames.is_empty() {
+                stacks.push(stack);
+            }
+        }
+
+        if is_crash_start(&entry) {
+            let message = entry.message.as_deref().unwrap_or(&entry.raw).to_string();
+            let category = entry.category.as_deref().map(str::to_string);
+            current = Some((
+                CallStack {
+                    start_line: entry.line_number,
+                    frames: Vec::new(),
+                    message,
+                },
+                category,
+            ));
+        }
+    }
+
+    if let Some((stack, _)) = current {
+        if !stack.frames.is_empty() {
+            stacks.push(stack);
+        }
+    }
+
+    Ok(stacks)
+}
+
+/// 是否为崩溃的起始行
+fn is_crash_start(entry: &LogEntry) -> bool {
+    if entry.is_continuation {
+        return false;
+    }
+    let message = entry.message.as_deref().unwrap_or(&entry.raw);
+    entry.level == LogLevel::Fatal
+        || message.starts_with("Fatal error")
+        || message.starts_with("Assertion failed")
+}
+
+/// 栈帧行的内容 (缩进的续行，或带 [Callstack] 标记的行)，其他行和空白内容返回 None
+fn frame_text(entry: &LogEntry) -> Option<&str> {
+    let text = if entry.is_continuation {
+        entry.raw.trim()
+    } else {
+        entry
+            .message
+            .as_deref()
+            .and_then(|message| message.strip_prefix(CALLSTACK_MARKER))?
+            .trim()
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// 解析一帧，格式无法识别时只保留原始内容
+fn parse_frame(line_number: u64, text: &str) -> StackFrame {
+    let caps = FRAME_PATTERN.captures(text);
+    let group = |i: usize| {
+        caps.as_ref()
+            .and_then(|caps| caps.get(i))
+            .map(|m| m.as_str().to_string())
+    };
+
+    StackFrame {
+        line_number,
+        raw: text.to_string(),
+        module: group(2),
+        function: group(3),
+        offset: group(1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::LogParser;
+
+    fn entries<'a>(lines: &'a [&'a str]) -> impl Iterator<Item = std::io::Result<LogEntry>> + 'a {
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| Ok(LogParser::parse_line(i as u64 + 1, line)))
+    }
+
+    #[test]
+    fn test_detect_callstack_block() {
+        let lines = [
+            "[2026.02.14-03.40.00:100][512]LogTemp: Display: Before the crash",
+            "[2026.02.14-03.40.00:123][512]LogWindows: Error: Fatal error: [File:D:/Build/Engine/Source/Runtime/Core/Private/Containers/Array.cpp] [Line: 771]",
+            "[2026.02.14-03.40.00:123][512]LogWindows: Error: Assertion failed: (Index >= 0) & (Index < ArrayNum)",
+            "  0x00007ffb1a2b3c4d UnrealEditor-Core.dll!FDebug::CheckVerifyFailedImpl() [D:/Build/Engine/Source/Runtime/Core/Private/Misc/AssertionMacros.cpp:456]",
+            "  0x00007ffb1a2b3d10 UnrealEditor-Core.dll!TArray<int,TSizedDefaultAllocator<32> >::RangeCheck() [D:/Build/Engine/Source/Runtime/Core/Public/Containers/Array.h:771]",
+            "  0x00007ffb2c3d4e5f UnrealEditor-Engine.dll!UWorld::Tick() [D:/Build/Engine/Source/Runtime/Engine/Private/LevelTick.cpp:1520]",
+            "  0x00007ffb2c3d5000 UnrealEditor-Engine.dll!UGameEngine::Tick()",
+            "  0x00007ff6a1b2c3d4 UnrealEditor.exe!FEngineLoop::Tick() [D:/Build/Engine/Source/Runtime/Launch/Private/LaunchEngineLoop.cpp:5825]",
+            "  kernel32.dll!UnknownFunction",
+            "[2026.02.14-03.40.01:000][513]LogExit: Display: Exiting.",
+        ];
+
+        let stacks = detect_callstacks(entries(&lines)).unwrap();
+        assert_eq!(stacks.len(), 1);
+
+        let stack = &stacks[0];
+        assert_eq!(stack.start_line, 2);
+        assert!(stack.message.starts_with("Fatal error:"));
+        assert!(stack
+            .message
+            .ends_with("Assertion failed: (Index >= 0) & (Index < ArrayNum)"));
+        assert_eq!(stack.frames.len(), 6);
+
+        let top = &stack.frames[0];
+        assert_eq!(top.line_number, 4);
+        assert_eq!(top.module.as_deref(), Some("UnrealEditor-Core.dll"));
+        assert_eq!(
+            top.function.as_deref(),
+            Some("FDebug::CheckVerifyFailedImpl()")
+        );
+        assert_eq!(top.offset.as_deref(), Some("0x00007ffb1a2b3c4d"));
+
+        assert_eq!(
+            stack.frames[1].function.as_deref(),
+            Some("TArray<int,TSizedDefaultAllocator<32> >::RangeCheck()")
+        );
+        assert_eq!(
+            stack.frames[3].function.as_deref(),
+            Some("UGameEngine::Tick()")
+        );
+        let last = &stack.frames[5];
+        assert_eq!(last.module.as_deref(), Some("kernel32.dll"));
+        assert_eq!(last.function.as_deref(), Some("UnknownFunction"));
+        assert_eq!(last.offset, None);
+    }
+
+    #[test]
+    fn test_detect_callstack_marker_frames() {
+        let lines = [
+            "[2026.02.14-03.40.00:123][512]LogWindows: Error: Assertion failed: IsValid(Actor)",
+            "[2026.02.14-03.40.00:123][512]LogWindows: Error: ",
+            "[2026.02.14-03.40.00:123][512]LogWindows: Error: [Callstack] 0x00007ffb1a2b3c4d UnrealEditor-Core.dll!FDebug::AssertFailed() [D:/Build/AssertionMacros.cpp:456]",
+            "[2026.02.14-03.40.00:123][512]LogWindows: Error: [Callstack] 0x00007ffb2c3d4e5f UnrealEditor-MyGame.dll!AMyActor::BeginPlay()",
+            "[2026.02.14-03.40.00:200][512]LogWindows: Error: Fatal error!",
+        ];
+
+        let stacks = detect_callstacks(entries(&lines)).unwrap();
+        assert_eq!(stacks.len(), 1);
+        assert_eq!(stacks[0].message, "Assertion failed: IsValid(Actor)");
+        let functions: Vec<_> = stacks[0]
+            .frames
+            .iter()
+            .map(|f| f.function.as_deref().unwrap())
+            .collect();
+        assert_eq!(
+            functions,
+            vec!["FDebug::AssertFailed()", "AMyActor::BeginPlay()"]
+        );
+    }
+
+    #[test]
+    fn test_fatal_without_frames_ignored() {
+        let lines = [
+            "LogWindows: Error: Fatal error: out of memory",
+            "LogExit: Display: Exiting.",
+        ];
+        assert!(detect_callstacks(entries(&lines)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_blank_lines_are_not_frames() {
+        let lines = [
+            "LogWindows: Error: Fatal error: out of memory",
+            "",
+            "LogExit: Display: Exiting.",
+        ];
+        assert!(detect_callstacks(entries(&lines)).unwrap().is_empty());
+
+        // 栈帧之间的空行被跳过，不截断调用栈
+        let lines = [
+            "LogWindows: Error: Fatal error: out of memory",
+            "  UnrealEditor-Core.dll!FMemory::Malloc()",
+            "   ",
+            "  UnrealEditor-Engine.dll!UWorld::Tick()",
+            "LogWindows: Error: [Callstack]   ",
+        ];
+        let stacks = detect_callstacks(entries(&lines)).unwrap();
+        assert_eq!(stacks.len(), 1);
+        let raws: Vec<&str> = stacks[0].frames.iter().map(|f| f.raw.as_str()).collect();
+        assert_eq!(
+            raws,
+            vec![
+                "UnrealEditor-Core.dll!FMemory::Malloc()",
+                "UnrealEditor-Engine.dll!UWorld::Tick()"
+            ]
+        );
+    }
+}
@@ -0,0 +1,246 @@
+//! 高亮 - 计算日志行中需要着色的片段
+//!
+//! 多个模式命中同一段文本时按优先级取舍，结果互不重叠
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+use super::patterns::{HIGHLIGHT_NUMBER, HIGHLIGHT_PATH, HIGHLIGHT_UUID};
+use super::types::{HighlightConfig, HighlightSpan};
+
+/// 路径类型，该类型的片段附带规范化路径
+pub const PATH_KIND: &str = "path";
+
+/// 默认高亮器 (未配置自定义模式时使用)
+pub static DEFAULT_HIGHLIGHTER: Lazy<Highlighter> = Lazy::new(|| Highlighter {
+    patterns: vec![
+        (PATH_KIND.to_string(), HIGHLIGHT_PATH.clone()),
+        ("uuid".to_string(), HIGHLIGHT_UUID.clone()),
+        ("number".to_string(), HIGHLIGHT_NUMBER.clone()),
+    ],
+});
+
+/// 编译好的高亮模式集合，排在前面的模式优先级更高
+#[derive(Debug, Clone)]
+pub struct Highlighter {
+    patterns: Vec<(String, Regex)>,
+}
+
+impl Highlighter {
+    /// 编译配置中的模式，任一模式无效时返回错误
+    pub fn new(config: &HighlightConfig) -> Result<Self, String> {
+        let patterns = config
+            .patterns
+            .iter()
+            .map(|(kind, pattern)| {
+                Regex::new(pattern)
+                    .map(|regex| (kind.clone(), regex))
+                    .map_err(|e| format!("Invalid highlight pattern for '{}': {}", kind, e))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self { patterns })
+    }
+
+    /// 计算一行的高亮片段 (按起始位置升序，互不重叠)
+    pub fn spans(&self, text: &str) -> Vec<HighlightSpan> {
+        let mut spans: Vec<HighlightSpan> = Vec::new();
+        for (kind, regex) in &self.patterns {
+            for m in regex.find_iter(text) {
+                // 已被更高优先级的片段覆盖的部分整体丢弃 (空匹配没有可着色的内容)
+                let overlaps = spans.iter().any(|s| m.start() < s.end && s.start < m.end());
+                if !overlaps && !m.is_empty() {
+                    spans.push(HighlightSpan {
+                        start: m.start(),
+                        end: m.end(),
+                        kind: kind.clone(),
+                        normalized: (kind == PATH_KIND).then(|| normalize_path(m.as_str())),
+                    });
+                }
+            }
+        }
+
+        spans.sort_by_key(|s| s.start);
+        spans
+    }
+
+    /// 将每个高亮片段替换为 `<类型>`，用于把只有数字/路径等不同的消息归为一类
+    pub fn mask(&self, text: &str) -> String {
+        let mut masked = String::with_capacity(text.len());
+        let mut last = 0;
+        for span in self.spans(text) {
+            masked.push_str(&text[last..span.start]);
+            masked.push('<');
+            masked.push_str(&span.kind);
+            masked.push('>');
+            last = span.end;
+        }
+        masked.push_str(&text[last..]);
+        masked
+    }
+}
+
+/// 规范化路径: 分隔符统一为 /，去掉 . 和空段，.. 与前一段抵消
+///
+/// 盘符 (C:) 和 UNC 的 \\server\share 视为根，.. 不会越过根；
+/// 相对路径开头无法抵消的 .. 保留
+pub fn normalize_path(matched: &str) -> String {
+    let path = matched.replace('\\', "/");
+    let (prefix, rest) = match path.strip_prefix("//") {
+        Some(rest) => ("//", rest),
+        None => match path.strip_prefix('/') {
+            Some(rest) => ("/", rest),
+            None => ("", path.as_str()),
+        },
+    };
+
+    let mut parts: Vec<&str> = Vec::new();
+    let mut root_len = 0;
+    for part in rest.split('/') {
+        let is_root = parts.len() == root_len
+            && match prefix {
+                "//" => root_len < 2,
+                _ => root_len == 0 && part.ends_with(':'),
+            };
+        if is_root && !part.is_empty() {
+            parts.push(part);
+            root_len += 1;
+            continue;
+        }
+
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.len() > root_len && parts.last() != Some(&"..") {
+                    parts.pop();
+                } else if root_len == 0 && prefix.is_empty() {
+                    parts.push("..");
+                }
+            }
+            _ => parts.push(part),
+        }
+    }
+
+    let mut normalized = format!("{}{}", prefix, parts.join("/"));
+    if path.ends_with('/') && parts.len() > root_len {
+        normalized.push('/');
+    }
+    normalized
+}
+
+/// 用默认模式计算一行的高亮片段
+///
+/// 重叠时优先级为 path > uuid > number
+pub fn highlight_spans(text: &str) -> Vec<HighlightSpan> {
+    DEFAULT_HIGHLIGHTER.spans(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds<'a>(text: &'a str, spans: &'a [HighlightSpan]) -> Vec<(&'a str, &'a str)> {
+        spans
+            .iter()
+            .map(|s| (&text[s.start..s.end], s.kind.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_highlight_priority() {
+        let text = r"Loaded C:\Game\Map01.umap guid 12345678-1234-1234-1234-123456789012 in 3.5 ms";
+        let spans = highlight_spans(text);
+
+        assert_eq!(
+            kinds(text, &spans),
+            vec![
+                (r"C:\Game\Map01.umap", "path"),
+                ("12345678-1234-1234-1234-123456789012", "uuid"),
+                ("3.5", "number"),
+            ]
+        );
+        assert!(spans.windows(2).all(|w| w[0].end <= w[1].start));
+    }
+
+    #[test]
+    fn test_mask() {
+        assert_eq!(
+            DEFAULT_HIGHLIGHTER.mask(r"Loading asset 123 from C:\Game\A.uasset"),
+            "Loading asset <number> from <path>"
+        );
+        assert_eq!(
+            DEFAULT_HIGHLIGHTER.mask("Loading asset 456"),
+            DEFAULT_HIGHLIGHTER.mask("Loading asset 123")
+        );
+        assert_eq!(DEFAULT_HIGHLIGHTER.mask("ready"), "ready");
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(
+            normalize_path(r"\\server\share\file"),
+            "//server/share/file"
+        );
+        assert_eq!(
+            normalize_path(r"..\Content\x.uasset"),
+            "../Content/x.uasset"
+        );
+        assert_eq!(
+            normalize_path(r"C:\Game\Maps\..\Content\.\\Hero.uasset"),
+            "C:/Game/Content/Hero.uasset"
+        );
+        // .. 不越过盘符和 UNC 共享根
+        assert_eq!(normalize_path(r"C:\..\Windows"), "C:/Windows");
+        assert_eq!(
+            normalize_path(r"\\server\share\..\..\file"),
+            "//server/share/file"
+        );
+        assert_eq!(normalize_path(r"..\a\..\..\b\"), "../../b/");
+    }
+
+    #[test]
+    fn test_path_span_normalized() {
+        let text = r"Saved to C:\Project\Saved\..\Content\Map.umap in 12 ms";
+        let spans = highlight_spans(text);
+
+        assert_eq!(spans.len(), 2);
+        assert_eq!(
+            &text[spans[0].start..spans[0].end],
+            r"C:\Project\Saved\..\Content\Map.umap"
+        );
+        assert_eq!(
+            spans[0].normalized.as_deref(),
+            Some("C:/Project/Content/Map.umap")
+        );
+        assert_eq!(spans[1].kind, "number");
+        assert_eq!(spans[1].normalized, None);
+    }
+
+    #[test]
+    fn test_highlight_plain_text() {
+        assert!(highlight_spans("LogInit: Display: ready").is_empty());
+    }
+
+    #[test]
+    fn test_custom_patterns() {
+        let config = HighlightConfig {
+            patterns: vec![
+                ("asset".to_string(), r"/Game/[\w/]+".to_string()),
+                ("id".to_string(), r"\b[A-Z]{4}\d{4}\b".to_string()),
+            ],
+        };
+        let highlighter = Highlighter::new(&config).unwrap();
+
+        let text = "Spawned /Game/Maps/Arena for ABCD1234 after 12 ms";
+        assert_eq!(
+            kinds(text, &highlighter.spans(text)),
+            vec![("/Game/Maps/Arena", "asset"), ("ABCD1234", "id")]
+        );
+
+        let invalid = HighlightConfig {
+            patterns: vec![("bad".to_string(), "(unclosed".to_string())],
+        };
+        let err = Highlighter::new(&invalid).unwrap_err();
+        assert!(err.contains("'bad'"));
+    }
+}
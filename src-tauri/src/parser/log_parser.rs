@@ -3,86 +3,208 @@
 //! 负责解析单个日志行，提取时间戳、类别、级别等信息
 
 use super::patterns::*;
-use super::types::{LogEntry, LogLevel};
+use super::types::{
+    ContinuationRule, CustomPattern, LineFormat, LogEntry, LogLevel, ParserConfig, Timestamp,
+};
+
+/// ensure 失败行的消息前缀
+const ENSURE_MARKER: &str = "Ensure condition failed:";
 
 /// 日志解析器
 pub struct LogParser;
 
 impl LogParser {
-    /// 解析单行日志
+    /// 解析单行日志 (默认配置)
     pub fn parse_line(line_number: u64, content: &str) -> LogEntry {
+        Self::parse_line_with(&ParserConfig::default(), line_number, content)
+    }
+
+    /// 按指定配置解析单行日志
+    pub fn parse_line_with(config: &ParserConfig, line_number: u64, content: &str) -> LogEntry {
+        Self::parse_line_format(config, line_number, content).1
+    }
+
+    /// 解析单行日志，同时返回命中的格式分支 (用于解析统计)
+    pub fn parse_line_format(
+        config: &ParserConfig,
+        line_number: u64,
+        content: &str,
+    ) -> (LineFormat, LogEntry) {
+        let (format, mut entry) = Self::parse_fields(config, line_number, content);
+        Self::mark_ensure(&mut entry);
+        if matches!(format, LineFormat::Standard | LineFormat::Simple) {
+            entry.sub_tag = entry.message.as_deref().and_then(Self::extract_sub_tag);
+        }
+        (format, entry)
+    }
+
+    /// 按格式分支拆分各字段
+    fn parse_fields(
+        config: &ParserConfig,
+        line_number: u64,
+        content: &str,
+    ) -> (LineFormat, LogEntry) {
         let trimmed = content.trim_end();
 
+        // 优先尝试自定义格式
+        if let Some(custom) = &config.custom_pattern {
+            if let Some(entry) = Self::parse_custom(config, custom, line_number, trimmed) {
+                return (LineFormat::Custom, entry);
+            }
+        }
+
         // 检查是否是续行
-        if Self::is_continuation(trimmed) {
-            return LogEntry {
-                line_number,
-                raw: trimmed.to_string(),
-                timestamp: None,
-                frame: None,
-                category: None,
-                level: LogLevel::Unknown,
-                message: Some(trimmed.to_string()),
-                is_continuation: true,
-            };
+        if config.continuation.is_continuation(trimmed) {
+            return (
+                LineFormat::Continuation,
+                LogEntry {
+                    message: Some(trimmed.to_string()),
+                    is_continuation: true,
+                    ..LogEntry::raw(line_number, trimmed)
+                },
+            );
         }
 
         // 尝试匹配标准格式
         if let Some(caps) = PATTERN_STANDARD.captures(trimmed) {
-            return LogEntry {
-                line_number,
-                raw: trimmed.to_string(),
-                timestamp: Some(caps[1].to_string()),
-                frame: caps[2].parse().ok(),
-                category: Some(caps[3].to_string()),
-                level: LogLevel::from_str(&caps[4]),
-                message: Some(caps[5].to_string()),
-                is_continuation: false,
-            };
+            return (
+                LineFormat::Standard,
+                LogEntry {
+                    timestamp: Some(caps[1].to_string()),
+                    parsed_timestamp: Timestamp::parse(&caps[1]),
+                    frame: caps[2].parse().ok(),
+                    category: Some(caps[3].into()),
+                    level: config.resolve_level(&caps[4]),
+                    message: Some(caps[5].to_string()),
+                    ..LogEntry::raw(line_number, trimmed)
+                },
+            );
         }
 
         // 尝试匹配简单格式
         if let Some(caps) = PATTERN_SIMPLE.captures(trimmed) {
-            return LogEntry {
-                line_number,
-                raw: trimmed.to_string(),
-                timestamp: None,
-                frame: None,
-                category: Some(caps[1].to_string()),
-                level: LogLevel::from_str(&caps[2]),
-                message: Some(caps[3].to_string()),
-                is_continuation: false,
-            };
+            return (
+                LineFormat::Simple,
+                LogEntry {
+                    category: Some(caps[1].into()),
+                    level: config.resolve_level(&caps[2]),
+                    message: Some(caps[3].to_string()),
+                    ..LogEntry::raw(line_number, trimmed)
+                },
+            );
         }
 
         // 检查是否是文件头
         if PATTERN_HEADER.is_match(trimmed) {
-            return LogEntry {
-                line_number,
-                raw: trimmed.to_string(),
-                timestamp: Some(trimmed.replace("Log file open, ", "")),
-                frame: None,
-                category: Some("LogFile".to_string()),
-                level: LogLevel::Display,
-                message: Some("Log file opened".to_string()),
-                is_continuation: false,
-            };
+            return (
+                LineFormat::Header,
+                LogEntry {
+                    timestamp: Some(trimmed.replace("Log file open, ", "")),
+                    category: Some("LogFile".into()),
+                    level: LogLevel::Display,
+                    message: Some("Log file opened".to_string()),
+                    ..LogEntry::raw(line_number, trimmed)
+                },
+            );
+        }
+
+        // 省略级别的行 (UE 按 Display 输出)，只接受像日志类别的第一个词
+        if let Some(caps) = PATTERN_SIMPLE_NOLEVEL.captures(trimmed) {
+            if Self::is_category_like(&caps[1]) {
+                return (
+                    LineFormat::Simple,
+                    LogEntry {
+                        category: Some(caps[1].into()),
+                        level: LogLevel::Display,
+                        message: Some(caps[2].to_string()),
+                        ..LogEntry::raw(line_number, trimmed)
+                    },
+                );
+            }
         }
 
         // 无法解析的行，作为原始内容返回
-        LogEntry::raw(line_number, trimmed.to_string())
+        (LineFormat::Raw, LogEntry::raw(line_number, trimmed))
     }
 
-    /// 检查是否是续行
-    fn is_continuation(line: &str) -> bool {
-        line.starts_with(' ') || line.starts_with('>') || line.is_empty()
+    /// 按自定义格式解析，不匹配时返回 None
+    ///
+    /// 未映射或未参与匹配的级别按 Display 处理，与省略级别的内置格式一致
+    fn parse_custom(
+        config: &ParserConfig,
+        custom: &CustomPattern,
+        line_number: u64,
+        trimmed: &str,
+    ) -> Option<LogEntry> {
+        let caps = custom.regex.captures(trimmed)?;
+        let group = |name: &Option<String>| {
+            name.as_deref()
+                .and_then(|name| caps.name(name))
+                .map(|m| m.as_str())
+        };
+
+        let timestamp = group(&custom.groups.timestamp);
+        Some(LogEntry {
+            timestamp: timestamp.map(str::to_string),
+            parsed_timestamp: timestamp.and_then(Timestamp::parse),
+            frame: group(&custom.groups.frame).and_then(|frame| frame.trim().parse().ok()),
+            category: group(&custom.groups.category).map(Into::into),
+            level: group(&custom.groups.level)
+                .map(|level| config.resolve_level(level))
+                .unwrap_or(LogLevel::Display),
+            message: group(&custom.groups.message).map(str::to_string),
+            ..LogEntry::raw(line_number, trimmed)
+        })
+    }
+
+    /// ensure 失败行无论原级别都按 Warning 处理，与致命错误区分
+    fn mark_ensure(entry: &mut LogEntry) {
+        let is_ensure = !entry.is_continuation
+            && entry
+                .message
+                .as_deref()
+                .is_some_and(|message| message.trim_start().starts_with(ENSURE_MARKER));
+        if is_ensure {
+            entry.level = LogLevel::Warning;
+            entry.is_ensure = true;
+        }
+    }
+
+    /// 提取消息开头的子标签 (时间戳和帧号已在消息之外，不会被当成子标签)
+    pub fn extract_sub_tag(message: &str) -> Option<String> {
+        PATTERN_SUB_TAG
+            .captures(message.trim_start())
+            .map(|caps| caps[1].to_string())
     }
 
-    /// 从行中提取日志级别
+    /// 按默认规则检查是否是续行 (见 ContinuationRule)
+    pub fn is_continuation(line: &str) -> bool {
+        ContinuationRule::default().is_continuation(line)
+    }
+
+    /// 是否像 UE 日志类别 (Log 后接大写字母，如 LogTemp)
+    fn is_category_like(word: &str) -> bool {
+        word.strip_prefix("Log")
+            .and_then(|rest| rest.chars().next())
+            .is_some_and(|c| c.is_ascii_uppercase())
+    }
+
+    /// 从行中提取日志级别 (省略级别的日志行视为 Display，与 parse_line 一致)
     pub fn extract_level(line: &str) -> Option<LogLevel> {
-        EXTRACT_LEVEL
-            .captures(line)
-            .map(|caps| LogLevel::from_str(&caps[1]))
+        if let Some(caps) = EXTRACT_LEVEL.captures(line) {
+            // 与 parse_line 一致，ensure 失败行按 Warning 统计
+            let rest = &line[caps.get(0).map_or(0, |m| m.end())..];
+            if rest.trim_start().starts_with(ENSURE_MARKER) {
+                return Some(LogLevel::Warning);
+            }
+            return Some(LogLevel::from_str(&caps[1]));
+        }
+
+        let omitted = !PATTERN_SIMPLE.is_match(line)
+            && PATTERN_SIMPLE_NOLEVEL
+                .captures(line)
+                .is_some_and(|caps| Self::is_category_like(&caps[1]));
+        omitted.then_some(LogLevel::Display)
     }
 
     /// 从行中提取日志类别
@@ -93,6 +215,13 @@ impl LogParser {
             .map(|m| m.as_str().to_string())
     }
 
+    /// 将 UE 时间戳 (2026.02.14-03.33.56:070) 转换为 Unix 毫秒时间 (按 UTC)
+    ///
+    /// 文件头等其他格式的时间返回 None
+    pub fn timestamp_millis(timestamp: &str) -> Option<i64> {
+        Timestamp::parse(timestamp).and_then(|ts| ts.unix_millis())
+    }
+
     /// 批量解析日志行
     pub fn parse_lines(lines: &[(&u64, &str)]) -> Vec<LogEntry> {
         lines
@@ -105,6 +234,7 @@ impl LogParser {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::types::ParseGroupMap;
 
     #[test]
     fn test_parse_standard_format() {
@@ -114,10 +244,29 @@ mod tests {
         assert_eq!(entry.line_number, 1);
         assert_eq!(entry.timestamp, Some("2026.02.14-03.33.56:070".to_string()));
         assert_eq!(entry.frame, Some(0));
-        assert_eq!(entry.category, Some("LogWindows".to_string()));
+        assert_eq!(entry.category.as_deref(), Some("LogWindows"));
         assert_eq!(entry.level, LogLevel::Error);
         assert_eq!(entry.message, Some("Test error message".to_string()));
         assert!(!entry.is_continuation);
+        assert_eq!(entry.source_file, None);
+    }
+
+    #[test]
+    fn test_parse_zero_frame_timestamp() {
+        let line = "[2026.02.14-03.33.56:070][  0]LogInit: Display: Engine started";
+        let entry = LogParser::parse_line(1, line);
+
+        assert_eq!(entry.frame, Some(0));
+        assert_eq!(entry.timestamp.as_deref(), Some("2026.02.14-03.33.56:070"));
+        let ts = entry
+            .parsed_timestamp
+            .expect("standard line has a timestamp");
+        assert_eq!((ts.year, ts.month, ts.day), (2026, 2, 14));
+        assert_eq!((ts.hour, ts.minute, ts.second, ts.millis), (3, 33, 56, 70));
+
+        let entry = LogParser::parse_line(2, "LogInit: Display: No time here");
+        assert_eq!(entry.timestamp, None);
+        assert_eq!(entry.parsed_timestamp, None);
     }
 
     #[test]
@@ -125,11 +274,112 @@ mod tests {
         let line = "LogInit: Warning: Initialization issue";
         let entry = LogParser::parse_line(1, line);
 
-        assert_eq!(entry.category, Some("LogInit".to_string()));
+        assert_eq!(entry.category.as_deref(), Some("LogInit"));
         assert_eq!(entry.level, LogLevel::Warning);
         assert_eq!(entry.message, Some("Initialization issue".to_string()));
     }
 
+    #[test]
+    fn test_parse_fatal() {
+        let line = "[2026.02.14-03.33.56:070][ 12]LogWindows: Fatal: [AssetCheck] Assertion failed";
+        let entry = LogParser::parse_line(1, line);
+
+        assert_eq!(entry.category.as_deref(), Some("LogWindows"));
+        assert_eq!(entry.level, LogLevel::Fatal);
+        assert_eq!(
+            entry.message.as_deref(),
+            Some("[AssetCheck] Assertion failed")
+        );
+        assert_eq!(LogParser::extract_level(line), Some(LogLevel::Fatal));
+        assert!(LogLevel::Fatal.severity() > LogLevel::Error.severity());
+    }
+
+    #[test]
+    fn test_parse_omitted_level() {
+        let entry = LogParser::parse_line(1, "LogTemp: Hello world");
+        assert_eq!(entry.category.as_deref(), Some("LogTemp"));
+        assert_eq!(entry.level, LogLevel::Display);
+        assert_eq!(entry.message.as_deref(), Some("Hello world"));
+
+        // Key: Value 形式的普通行不当作日志类别
+        for line in ["Version: 5.3.2", "Logged: yes", "Login: ok"] {
+            let entry = LogParser::parse_line(1, line);
+            assert_eq!(entry.category, None, "{}", line);
+            assert_eq!(entry.level, LogLevel::Unknown);
+        }
+
+        assert!(LogParser::parse_line(1, "  LogTemp: indented").is_continuation);
+
+        // 索引统计与过滤使用的级别提取保持一致
+        assert_eq!(
+            LogParser::extract_level("LogTemp: Hello world"),
+            Some(LogLevel::Display)
+        );
+        assert_eq!(LogParser::extract_level("LogTemp: Odd: value"), None);
+        assert_eq!(LogParser::extract_level("Version: 5.3.2"), None);
+    }
+
+    #[test]
+    fn test_parse_sub_tag() {
+        let line =
+            "[2026.02.14-03.33.56:070][  0]LogAssetCheck: Warning: [AssetCheck] Missing material";
+        let entry = LogParser::parse_line(1, line);
+        assert_eq!(entry.sub_tag.as_deref(), Some("AssetCheck"));
+        // 子标签保留在消息中
+        assert_eq!(
+            entry.message.as_deref(),
+            Some("[AssetCheck] Missing material")
+        );
+
+        let simple = LogParser::parse_line(2, "LogCook: Display: [Cook.Stats] Done");
+        assert_eq!(simple.sub_tag.as_deref(), Some("Cook.Stats"));
+
+        // 时间戳和帧号的方括号不是子标签
+        let plain =
+            LogParser::parse_line(3, "[2026.02.14-03.33.56:070][  0]LogTemp: Display: Hello");
+        assert_eq!(plain.sub_tag, None);
+        let path = LogParser::parse_line(
+            4,
+            "LogWindows: Error: [File:D:/Build/Array.cpp] [Line: 771]",
+        );
+        assert_eq!(path.sub_tag, None);
+        let numeric = LogParser::parse_line(5, "LogTemp: Display: [2026.02.14] nested time");
+        assert_eq!(numeric.sub_tag, None);
+        assert_eq!(
+            LogParser::parse_line(6, "  [AssetCheck] indented").sub_tag,
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_ensure() {
+        let line = "[2026.02.14-03.40.00:123][512]LogOutputDevice: Error: Ensure condition failed: IsValid(Component) [File:D:/Build/Game/Source/Weapon.cpp] [Line: 88]";
+        let entry = LogParser::parse_line(1, line);
+
+        assert!(entry.is_ensure);
+        assert_eq!(entry.level, LogLevel::Warning);
+        assert_eq!(entry.category.as_deref(), Some("LogOutputDevice"));
+        assert!(entry
+            .message
+            .as_deref()
+            .unwrap()
+            .starts_with("Ensure condition failed: IsValid(Component)"));
+        assert_eq!(LogParser::extract_level(line), Some(LogLevel::Warning));
+
+        let fatal = LogParser::parse_line(2, "LogWindows: Error: Assertion failed: IsValid(Actor)");
+        assert!(!fatal.is_ensure);
+        assert_eq!(fatal.level, LogLevel::Error);
+
+        // 栈帧续行和只在消息中间提到 ensure 的行不算
+        assert!(!LogParser::parse_line(3, "  Ensure condition failed: X").is_ensure);
+        let mention = LogParser::parse_line(
+            4,
+            "LogTemp: Display: Retrying after Ensure condition failed: X",
+        );
+        assert!(!mention.is_ensure);
+        assert_eq!(mention.level, LogLevel::Display);
+    }
+
     #[test]
     fn test_parse_continuation() {
         let line = "  continued message here";
@@ -137,6 +387,56 @@ mod tests {
 
         assert!(entry.is_continuation);
         assert_eq!(entry.level, LogLevel::Unknown);
+        assert_eq!(entry.source_file, None);
+    }
+
+    #[test]
+    fn test_source_file_omitted_for_single_file() {
+        let entry = LogParser::parse_line(1, "LogInit: Display: Hello");
+        let json = serde_json::to_value(&entry).unwrap();
+        assert!(json.get("source_file").is_none());
+
+        let parsed: LogEntry = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.source_file, None);
+
+        let merged = LogEntry {
+            source_file: Some("D:/Logs/Backup/Game.log".into()),
+            ..entry
+        };
+        let json = serde_json::to_value(&merged).unwrap();
+        assert_eq!(json["source_file"], "D:/Logs/Backup/Game.log");
+    }
+
+    #[test]
+    fn test_continuation_rule() {
+        let default = ParserConfig::default();
+        let tab = LogParser::parse_line_with(&default, 2, "\tat Foo() in Bar.cpp");
+        assert!(!tab.is_continuation);
+        assert!(LogParser::parse_line_with(&default, 3, "").is_continuation);
+
+        let config = ParserConfig {
+            continuation: ContinuationRule {
+                tab_indent: true,
+                blank_line_separate: true,
+            },
+            ..Default::default()
+        };
+
+        let tab = LogParser::parse_line_with(&config, 2, "\tat Foo() in Bar.cpp");
+        assert!(tab.is_continuation);
+        assert!(LogParser::parse_line_with(&config, 3, "  spaces still continue").is_continuation);
+
+        // 空行 (包括只有空白的行) 作为独立条目
+        for line in ["", "\t  "] {
+            let blank = LogParser::parse_line_with(&config, 4, line);
+            assert!(!blank.is_continuation, "{:?}", line);
+            assert_eq!(blank.raw.as_ref(), "");
+            assert_eq!(blank.level, LogLevel::Unknown);
+        }
+
+        let entry = LogParser::parse_line_with(&config, 5, "LogInit: Display: Start");
+        assert!(!entry.is_continuation);
+        assert_eq!(entry.category.as_deref(), Some("LogInit"));
     }
 
     #[test]
@@ -144,7 +444,83 @@ mod tests {
         let line = "Log file open, 02/14/26 11:33:35";
         let entry = LogParser::parse_line(1, line);
 
-        assert_eq!(entry.category, Some("LogFile".to_string()));
+        assert_eq!(entry.category.as_deref(), Some("LogFile"));
         assert_eq!(entry.level, LogLevel::Display);
+        assert_eq!(entry.source_file, None);
+    }
+
+    #[test]
+    fn test_timestamp_millis() {
+        assert_eq!(
+            LogParser::timestamp_millis("2026.02.14-03.33.56:070"),
+            Some(1_771_040_036_070)
+        );
+        assert_eq!(LogParser::timestamp_millis("02/14/26 11:33:35"), None);
+    }
+
+    #[test]
+    fn test_parse_level_alias() {
+        let line = "LogCore: Critical: Out of memory";
+        assert_eq!(LogParser::parse_line(1, line).level, LogLevel::Unknown);
+
+        let mut config = ParserConfig::default();
+        config
+            .level_aliases
+            .insert("critical".to_string(), LogLevel::Error);
+        let entry = LogParser::parse_line_with(&config, 1, line);
+
+        assert_eq!(entry.level, LogLevel::Error);
+        assert_eq!(entry.category.as_deref(), Some("LogCore"));
+    }
+
+    #[test]
+    fn test_parse_custom_pattern() {
+        let groups = ParseGroupMap {
+            timestamp: Some("ts".to_string()),
+            frame: Some("frame".to_string()),
+            category: Some("cat".to_string()),
+            level: Some("level".to_string()),
+            message: Some("msg".to_string()),
+        };
+        let pattern = r"^\[PID (?P<pid>\d+)\]\[(?P<ts>[\d.:-]+)\]\[\s*(?P<frame>\d+)\](?P<cat>\w+): (?:(?P<level>\w+): )?(?P<msg>.*)$";
+        let config = ParserConfig {
+            custom_pattern: Some(CustomPattern::new(pattern, groups.clone()).unwrap()),
+            ..Default::default()
+        };
+
+        let line = "[PID 4242][2026.02.14-03.33.56:070][ 12]LogNet: Warning: Connection lost";
+        assert_eq!(LogParser::parse_line(1, line).category, None);
+
+        let (format, entry) = LogParser::parse_line_format(&config, 1, line);
+        assert_eq!(format, LineFormat::Custom);
+        assert_eq!(entry.timestamp.as_deref(), Some("2026.02.14-03.33.56:070"));
+        assert!(entry.parsed_timestamp.is_some());
+        assert_eq!(entry.frame, Some(12));
+        assert_eq!(entry.category.as_deref(), Some("LogNet"));
+        assert_eq!(entry.level, LogLevel::Warning);
+        assert_eq!(entry.message.as_deref(), Some("Connection lost"));
+
+        // 级别组未参与匹配时按 Display 处理
+        let entry = LogParser::parse_line_with(
+            &config,
+            2,
+            "[PID 1][2026.02.14-03.33.56:070][0]LogTemp: hi",
+        );
+        assert_eq!(entry.level, LogLevel::Display);
+
+        // 不匹配自定义格式的行仍按内置格式解析
+        let (format, entry) =
+            LogParser::parse_line_format(&config, 3, "LogInit: Display: Starting");
+        assert_eq!(format, LineFormat::Simple);
+        assert_eq!(entry.category.as_deref(), Some("LogInit"));
+
+        // 映射到不存在的捕获组在设置时报错
+        let missing = ParseGroupMap {
+            level: Some("severity".to_string()),
+            ..groups
+        };
+        let err = CustomPattern::new(pattern, missing).unwrap_err();
+        assert!(err.contains("severity"));
+        assert!(CustomPattern::new(r"(?P<msg>", ParseGroupMap::default()).is_err());
     }
 }
@@ -1,5 +1,7 @@
 //! 日志解析模块
 
+pub mod callstack;
+pub mod highlight;
 pub mod log_parser;
 pub mod patterns;
 pub mod types;
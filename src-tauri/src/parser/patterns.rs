@@ -17,17 +17,33 @@ pub static PATTERN_STANDARD: Lazy<Regex> = Lazy::new(|| {
 pub static PATTERN_SIMPLE: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^(\w+):\s*(\w+):\s*(.*)$").expect("Invalid simple pattern"));
 
+/// 省略级别的格式 (按 Display 处理): LogTemp: Message
+///
+/// 会命中任意 `Key: Value`，调用方需再确认第一个词像日志类别
+pub static PATTERN_SIMPLE_NOLEVEL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^(\w+):\s*(.*)$").expect("Invalid no-level pattern"));
+
 /// 文件头格式: Log file open, 02/14/26 11:33:35
 pub static PATTERN_HEADER: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"^Log file open,\s*(\d{2}/\d{2}/\d{2}\s+\d{2}:\d{2}:\d{2})")
         .expect("Invalid header pattern")
 });
 
+/// 行首的时间戳和帧号: [2026.02.14-03.33.56:070][  0]
+pub static PATTERN_TIMESTAMP_PREFIX: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[[^\]]*\]\[[^\]]*\]").expect("Invalid timestamp prefix pattern"));
+
+/// 消息开头的子标签: [AssetCheck] Missing material
+///
+/// 须以字母开头，不会误把 [2026.02.14-...] 之类的时间戳或 [File:...] 当成子标签
+pub static PATTERN_SUB_TAG: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\[([A-Za-z][\w.\-]*)\]").expect("Invalid sub-tag pattern"));
+
 /// 续行模式 (以空格或 > 开头)
 pub static PATTERN_CONTINUATION: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"^(\s|>).*$").expect("Invalid continuation pattern"));
 
-/// 高亮模式
+// 高亮模式
 
 /// Windows 路径: C:\xxx 或 \\xxx
 pub static HIGHLIGHT_PATH: Lazy<Regex> =
@@ -50,7 +66,7 @@ pub static EXTRACT_CATEGORY: Lazy<Regex> = Lazy::new(|| {
 
 /// 详细级别提取
 pub static EXTRACT_LEVEL: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r":\s*(Error|Warning|Display|Verbose|VeryVerbose):")
+    Regex::new(r":\s*(Fatal|Error|Warning|Display|Verbose|VeryVerbose):")
         .expect("Invalid level extract pattern")
 });
 
@@ -2,13 +2,20 @@
 //!
 //! 定义日志条目、详细级别、文件信息等核心类型
 
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-/// 日志详细级别
+/// 日志详细级别 (按严重程度排序: Fatal 最大，Unknown 最小)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum LogLevel {
+    Fatal,
     Error,
     Warning,
     Display,
@@ -19,8 +26,10 @@ pub enum LogLevel {
 
 impl LogLevel {
     /// 从字符串解析日志级别
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
+            "fatal" => LogLevel::Fatal,
             "error" => LogLevel::Error,
             "warning" => LogLevel::Warning,
             "display" => LogLevel::Display,
@@ -33,6 +42,7 @@ impl LogLevel {
     /// 获取显示名称
     pub fn display_name(&self) -> &'static str {
         match self {
+            LogLevel::Fatal => "Fatal",
             LogLevel::Error => "Error",
             LogLevel::Warning => "Warning",
             LogLevel::Display => "Display",
@@ -41,6 +51,178 @@ impl LogLevel {
             LogLevel::Unknown => "Unknown",
         }
     }
+
+    /// 严重程度 (数值越大越严重)
+    pub fn severity(&self) -> u8 {
+        match self {
+            LogLevel::Fatal => 6,
+            LogLevel::Error => 5,
+            LogLevel::Warning => 4,
+            LogLevel::Display => 3,
+            LogLevel::Verbose => 2,
+            LogLevel::VeryVerbose => 1,
+            LogLevel::Unknown => 0,
+        }
+    }
+}
+
+impl PartialOrd for LogLevel {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for LogLevel {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.severity().cmp(&other.severity())
+    }
+}
+
+/// 解析器配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ParserConfig {
+    /// 级别别名 (不区分大小写)，如 "Critical" -> Error
+    #[serde(default)]
+    pub level_aliases: HashMap<String, LogLevel>,
+    /// 自定义行格式，配置后优先于内置格式尝试 (通过 set_custom_pattern 设置)
+    #[serde(skip)]
+    pub custom_pattern: Option<CustomPattern>,
+    /// 续行判定规则
+    #[serde(default)]
+    pub continuation: ContinuationRule,
+}
+
+impl ParserConfig {
+    /// 解析级别字符串，优先匹配别名
+    pub fn resolve_level(&self, s: &str) -> LogLevel {
+        self.level_aliases
+            .iter()
+            .find(|(alias, _)| alias.eq_ignore_ascii_case(s))
+            .map(|(_, level)| *level)
+            .unwrap_or_else(|| LogLevel::from_str(s))
+    }
+}
+
+/// 续行判定规则 (默认: 以空格或 > 开头的行和空行是续行)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ContinuationRule {
+    /// 以制表符缩进的行也是续行
+    #[serde(default)]
+    pub tab_indent: bool,
+    /// 空行作为独立条目，不并入上一条
+    #[serde(default)]
+    pub blank_line_separate: bool,
+}
+
+impl ContinuationRule {
+    /// 是否为续行 (line 已去掉行尾空白)
+    pub fn is_continuation(&self, line: &str) -> bool {
+        if line.is_empty() {
+            return !self.blank_line_separate;
+        }
+        line.starts_with(' ')
+            || line.starts_with('>')
+            || (self.tab_indent && line.starts_with('\t'))
+    }
+}
+
+/// 自定义格式中各字段对应的命名捕获组 (None 表示该格式没有此字段)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ParseGroupMap {
+    #[serde(default)]
+    pub timestamp: Option<String>,
+    #[serde(default)]
+    pub frame: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub level: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl ParseGroupMap {
+    /// 已配置的捕获组名
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        [
+            &self.timestamp,
+            &self.frame,
+            &self.category,
+            &self.level,
+            &self.message,
+        ]
+        .into_iter()
+        .filter_map(|name| name.as_deref())
+    }
+}
+
+/// 编译好的自定义行格式，用于被启动器加上 PID / 线程号等前缀的日志
+#[derive(Debug, Clone)]
+pub struct CustomPattern {
+    pub regex: Regex,
+    pub groups: ParseGroupMap,
+}
+
+impl CustomPattern {
+    /// 编译正则并确认映射中的捕获组都存在
+    pub fn new(pattern: &str, groups: ParseGroupMap) -> Result<Self, String> {
+        let regex = Regex::new(pattern).map_err(|e| format!("Invalid custom pattern: {}", e))?;
+
+        if let Some(missing) = groups
+            .names()
+            .find(|name| !regex.capture_names().any(|group| group == Some(*name)))
+        {
+            return Err(format!(
+                "Capture group '{}' not found in custom pattern",
+                missing
+            ));
+        }
+
+        Ok(Self { regex, groups })
+    }
+}
+
+/// UE 日志时间戳 (2026.02.14-03.33.56:070)
+///
+/// 字段按从大到小的时间单位排列，派生的排序即时间先后
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct Timestamp {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+    pub millis: u16,
+}
+
+impl Timestamp {
+    /// 解析 UE 时间戳格式 `YYYY.MM.DD-HH.MM.SS:mmm`，其他格式返回 None
+    pub fn parse(s: &str) -> Option<Timestamp> {
+        let dt = NaiveDateTime::parse_from_str(s, "%Y.%m.%d-%H.%M.%S:%3f").ok()?;
+
+        Some(Timestamp {
+            year: u16::try_from(dt.year()).ok()?,
+            month: dt.month() as u8,
+            day: dt.day() as u8,
+            hour: dt.hour() as u8,
+            minute: dt.minute() as u8,
+            second: dt.second() as u8,
+            millis: (dt.nanosecond() / 1_000_000) as u16,
+        })
+    }
+
+    /// 转换为 Unix 毫秒时间 (按 UTC)，字段不构成合法日期时返回 None
+    pub fn unix_millis(&self) -> Option<i64> {
+        let dt = NaiveDate::from_ymd_opt(self.year.into(), self.month.into(), self.day.into())?
+            .and_hms_milli_opt(
+                self.hour.into(),
+                self.minute.into(),
+                self.second.into(),
+                self.millis.into(),
+            )?;
+        Some(dt.and_utc().timestamp_millis())
+    }
 }
 
 /// 日志条目
@@ -48,34 +230,138 @@ impl LogLevel {
 pub struct LogEntry {
     /// 行号 (1-based)
     pub line_number: u64,
-    /// 原始内容
-    pub raw: String,
-    /// 时间戳 (可选)
+    /// 原始内容 (共享存储，缓存与返回结果之间克隆无需复制)
+    pub raw: Arc<str>,
+    /// 时间戳 (可选，原始文本用于显示)
     pub timestamp: Option<String>,
+    /// 解析后的时间戳 (仅 UE 标准时间格式)
+    #[serde(default)]
+    pub parsed_timestamp: Option<Timestamp>,
     /// 帧号 (可选)
     pub frame: Option<u64>,
-    /// 日志类别
-    pub category: Option<String>,
+    /// 日志类别 (LineReader 会对相同类别复用同一份存储)
+    pub category: Option<Arc<str>>,
     /// 详细级别
     pub level: LogLevel,
     /// 消息内容
     pub message: Option<String>,
+    /// 消息开头方括号内的子标签，如 `[AssetCheck] Missing material` 中的 AssetCheck
+    #[serde(default)]
+    pub sub_tag: Option<String>,
     /// 是否是多行日志的续行
     pub is_continuation: bool,
+    /// 是否为 ensure 失败行 (`Ensure condition failed:`，非致命，按 Warning 处理)
+    #[serde(default)]
+    pub is_ensure: bool,
+    /// 原始行含无效 UTF-8 (已替换为 U+FFFD 显示)
+    #[serde(default)]
+    pub has_invalid_utf8: bool,
+    /// 合并到本条目的续行数 (仅合并读取时非零)
+    #[serde(default)]
+    pub continuation_count: u64,
+    /// 连续相同行的数量，含本行 (仅去重读取时非零)
+    #[serde(default)]
+    pub repeat_count: u64,
+    /// 来源文件路径 (仅多文件合并视图中有值)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_file: Option<Arc<str>>,
 }
 
 impl LogEntry {
-    /// 创建未解析的日志行
-    pub fn raw(line_number: u64, content: String) -> Self {
+    /// 创建未解析的日志行 (各格式分支也以此为基础，只填写解析出的字段)
+    pub fn raw(line_number: u64, content: impl Into<Arc<str>>) -> Self {
         Self {
             line_number,
-            raw: content,
+            raw: content.into(),
             timestamp: None,
+            parsed_timestamp: None,
             frame: None,
             category: None,
             level: LogLevel::Unknown,
             message: None,
+            sub_tag: None,
             is_continuation: false,
+            is_ensure: false,
+            has_invalid_utf8: false,
+            continuation_count: 0,
+            repeat_count: 0,
+            source_file: None,
+        }
+    }
+}
+
+/// 文件编码 (根据 BOM 检测，无 BOM 时按 UTF-8 处理)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// 根据文件开头的字节检测编码
+    pub fn detect(bytes: &[u8]) -> Self {
+        if bytes.starts_with(&[0xEF, 0xBB, 0xBF]) {
+            Encoding::Utf8Bom
+        } else if bytes.starts_with(&[0xFF, 0xFE]) {
+            Encoding::Utf16Le
+        } else if bytes.starts_with(&[0xFE, 0xFF]) {
+            Encoding::Utf16Be
+        } else {
+            Encoding::Utf8
+        }
+    }
+
+    /// BOM 长度 (字节)，第一行从这里开始
+    pub fn bom_len(&self) -> usize {
+        match self {
+            Encoding::Utf8 => 0,
+            Encoding::Utf8Bom => 3,
+            Encoding::Utf16Le | Encoding::Utf16Be => 2,
+        }
+    }
+
+    /// 是否为 UTF-16 (按两字节码元处理换行)
+    pub fn is_utf16(&self) -> bool {
+        matches!(self, Encoding::Utf16Le | Encoding::Utf16Be)
+    }
+}
+
+/// 类别行号索引 (按需构建，与文件索引分开保存)
+#[derive(Debug, Clone, Default)]
+pub struct CategoryLines {
+    /// 每个类别出现的行号 (升序)
+    pub lines: HashMap<String, Vec<u64>>,
+    /// 每个类别最多记录的行数
+    pub cap: usize,
+}
+
+impl CategoryLines {
+    /// 取出索引构建时记录的类别行号，未记录时返回 None
+    pub fn take_from(index: &mut FileIndex) -> Option<Self> {
+        Some(Self {
+            lines: index.category_lines.take()?,
+            cap: index.category_lines_cap,
+        })
+    }
+
+    /// 放回索引，随增量索引一起更新
+    pub fn put_into(self, index: &mut FileIndex) {
+        index.category_lines = Some(self.lines);
+        index.category_lines_cap = self.cap;
+    }
+
+    /// 某个类别的完整行号列表 (升序)
+    ///
+    /// 该类别的行数达到上限 (可能不完整) 时返回 None；文件中没有该类别时返回空列表
+    pub fn complete(&self, category: &str) -> Option<&[u64]> {
+        match self.lines.get(category) {
+            Some(lines) if lines.len() >= self.cap => None,
+            Some(lines) => Some(lines),
+            None => Some(&[]),
         }
     }
 }
@@ -89,30 +375,197 @@ pub struct FileIndex {
     pub total_lines: u64,
     /// 文件大小 (字节)
     pub file_size: u64,
-    /// 行偏移索引 (每 INDEX_INTERVAL 行记录一次)
+    /// 文件编码
+    #[serde(default)]
+    pub encoding: Encoding,
+    /// 建索引时文件的修改时间 (Unix 毫秒)，用于判断持久化索引是否过期
+    #[serde(default)]
+    pub modified_time: u64,
+    /// 建索引时文件首尾采样的内容哈希 (见 ContentSignature，旧版本的 sidecar 没有记录)
+    #[serde(default)]
+    pub content_hash: Option<u64>,
+    /// 最后一个完整行 (以换行结束) 之后的字节位置，增量更新从这里继续扫描
+    ///
+    /// 旧版本的 sidecar 没有记录，为 0
+    #[serde(default)]
+    pub last_indexed_offset: u64,
+    /// 行偏移索引 (每 index_interval 行记录一次)
     pub line_offsets: Vec<u64>,
-    /// 索引间隔
+    /// 索引间隔 (建索引时按文件大小选择)
     pub index_interval: u64,
+    /// 是否记录了每一行的偏移 (小文件自动启用)
+    pub dense_offsets: bool,
+    /// 每一行的起始偏移 (仅 dense_offsets 时填充，不传给前端)
+    #[serde(skip)]
+    pub dense_line_offsets: Vec<u64>,
     /// 检测到的日志类别及其数量
     pub categories: HashMap<String, u64>,
+    /// 每个类别出现的行号 (升序，可选构建，不传给前端；构建后用 CategoryLines::take_from 取出)
+    #[serde(skip)]
+    pub category_lines: Option<HashMap<String, Vec<u64>>>,
+    /// category_lines 中每个类别最多记录的行数
+    #[serde(skip)]
+    pub category_lines_cap: usize,
     /// 各级别日志数量
     pub level_counts: HashMap<String, u64>,
 }
 
 impl FileIndex {
+    /// 默认索引间隔
     pub const INDEX_INTERVAL: u64 = 1000;
+    /// 自适应索引间隔的范围
+    pub const MIN_INDEX_INTERVAL: u64 = 100;
+    pub const MAX_INDEX_INTERVAL: u64 = 10_000;
+    /// 自适应间隔的目标索引点数
+    const TARGET_INDEX_POINTS: u64 = 10_000;
+    /// 估算行数时假定的平均行长 (字节)
+    const ESTIMATED_LINE_BYTES: u64 = 100;
+    /// 低于此大小的文件记录完整行偏移
+    pub const DENSE_OFFSETS_THRESHOLD: u64 = 50 * 1024 * 1024;
 
     pub fn new(file_path: String, file_size: u64) -> Self {
         Self {
             file_path,
             total_lines: 0,
             file_size,
+            encoding: Encoding::default(),
+            modified_time: 0,
+            content_hash: None,
+            last_indexed_offset: 0,
             line_offsets: Vec::new(),
             index_interval: Self::INDEX_INTERVAL,
+            dense_offsets: false,
+            dense_line_offsets: Vec::new(),
             categories: HashMap::new(),
+            category_lines: None,
+            category_lines_cap: 0,
             level_counts: HashMap::new(),
         }
     }
+
+    /// 按文件大小选择索引间隔，使索引点数大致为 TARGET_INDEX_POINTS
+    ///
+    /// 约 1GB 的文件对应默认间隔 1000
+    pub fn interval_for_size(file_size: u64) -> u64 {
+        let estimated_lines = file_size / Self::ESTIMATED_LINE_BYTES;
+        (estimated_lines / Self::TARGET_INDEX_POINTS)
+            .clamp(Self::MIN_INDEX_INTERVAL, Self::MAX_INDEX_INTERVAL)
+    }
+
+    /// 建索引时文件的内容签名，与文件当前的签名不同时持久化索引已过期
+    pub fn content_signature(&self) -> ContentSignature {
+        ContentSignature {
+            file_size: self.file_size,
+            modified_time: self.modified_time,
+            content_hash: self.content_hash,
+        }
+    }
+
+    /// 持久化索引文件路径: <logpath>.ueidx
+    pub fn sidecar_path<P: AsRef<Path>>(log_path: P) -> PathBuf {
+        let mut path = log_path.as_ref().as_os_str().to_owned();
+        path.push(".ueidx");
+        PathBuf::from(path)
+    }
+
+    /// 保存索引 (包括完整行偏移)
+    pub fn save_to<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(
+            &mut writer,
+            &IndexSidecarRef {
+                index: self,
+                dense_line_offsets: &self.dense_line_offsets,
+            },
+        )?;
+        writer.flush()
+    }
+
+    /// 读取保存的索引
+    pub fn load_from<P: AsRef<Path>>(path: P) -> std::io::Result<FileIndex> {
+        let reader = BufReader::new(File::open(path)?);
+        let sidecar: IndexSidecar = serde_json::from_reader(reader)?;
+        let mut index = sidecar.index;
+        index.dense_line_offsets = sidecar.dense_line_offsets;
+        Ok(index)
+    }
+
+    /// 定位某行: 返回 (起始读取的字节偏移, 该偏移之前的行数)
+    ///
+    /// 有完整偏移时直接命中目标行，否则回退到最近的稀疏索引点
+    pub fn seek_position(&self, line_number: u64) -> (u64, u64) {
+        let line_number = line_number.max(1);
+
+        let lines_before = line_number - 1;
+
+        if self.dense_offsets {
+            let offset = usize::try_from(lines_before)
+                .ok()
+                .and_then(|i| self.dense_line_offsets.get(i));
+            if let Some(&offset) = offset {
+                return (offset, lines_before);
+            }
+        }
+
+        let offset_index = lines_before / self.index_interval.max(1);
+        let offset = usize::try_from(offset_index)
+            .ok()
+            .and_then(|i| self.line_offsets.get(i));
+        match offset {
+            Some(&offset) => (offset, offset_index * self.index_interval),
+            None => (self.line_offsets.first().copied().unwrap_or(0), 0),
+        }
+    }
+
+    /// 定位字节偏移: 返回 (不晚于 offset 的最近已知行首, 该行首之前的行数)
+    ///
+    /// 有完整偏移时即为 offset 所在行的行首，否则为最近的稀疏索引点
+    pub fn seek_position_for_byte(&self, offset: u64) -> (u64, u64) {
+        if self.dense_offsets && !self.dense_line_offsets.is_empty() {
+            let i = self
+                .dense_line_offsets
+                .partition_point(|&start| start <= offset)
+                .saturating_sub(1);
+            return (self.dense_line_offsets[i], i as u64);
+        }
+
+        let i = self
+            .line_offsets
+            .partition_point(|&start| start <= offset)
+            .saturating_sub(1);
+        match self.line_offsets.get(i) {
+            Some(&start) => (start, i as u64 * self.index_interval),
+            None => (0, 0),
+        }
+    }
+}
+
+/// 文件内容签名，用于判断持久化索引是否过期
+///
+/// 只比较大小和修改时间会漏掉大小不变、修改时间又被保留的原地修改，因此再加上
+/// 文件首尾各 1MB 的哈希。采样让每次打开最多只多读 2MB，代价是只改动中间部分
+/// 且大小、修改时间都不变的编辑仍检测不到；哈希为 64 位非加密哈希 (FNV-1a)，
+/// 用于区分同一文件的不同版本时偶然碰撞的概率可以忽略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentSignature {
+    pub file_size: u64,
+    pub modified_time: u64,
+    /// 首尾采样的哈希 (None 表示未记录，与任何文件都不匹配)
+    pub content_hash: Option<u64>,
+}
+
+/// 持久化索引的文件格式 (dense_line_offsets 不随 FileIndex 序列化，单独保存)
+#[derive(Serialize)]
+struct IndexSidecarRef<'a> {
+    index: &'a FileIndex,
+    dense_line_offsets: &'a [u64],
+}
+
+#[derive(Deserialize)]
+struct IndexSidecar {
+    index: FileIndex,
+    #[serde(default)]
+    dense_line_offsets: Vec<u64>,
 }
 
 /// 日志块 (用于流式加载)
@@ -124,6 +577,79 @@ pub struct LogChunk {
     pub end_line: u64,
     /// 日志条目
     pub entries: Vec<LogEntry>,
+    /// 读取失败的行 (行号, 错误信息)，这些行在 entries 中以标记条目占位
+    #[serde(default)]
+    pub read_errors: Vec<(u64, String)>,
+}
+
+impl LogChunk {
+    /// 按列投影，只保留请求的字段
+    pub fn project(&self, fields: &[EntryField]) -> ProjectedChunk {
+        let wants = |field: EntryField| fields.contains(&field);
+
+        ProjectedChunk {
+            start_line: self.start_line,
+            end_line: self.end_line,
+            len: self.entries.len(),
+            line_numbers: wants(EntryField::LineNumber)
+                .then(|| self.entries.iter().map(|e| e.line_number).collect()),
+            raw: wants(EntryField::Raw)
+                .then(|| self.entries.iter().map(|e| e.raw.clone()).collect()),
+            timestamps: wants(EntryField::Timestamp)
+                .then(|| self.entries.iter().map(|e| e.timestamp.clone()).collect()),
+            frames: wants(EntryField::Frame)
+                .then(|| self.entries.iter().map(|e| e.frame).collect()),
+            categories: wants(EntryField::Category)
+                .then(|| self.entries.iter().map(|e| e.category.clone()).collect()),
+            levels: wants(EntryField::Level)
+                .then(|| self.entries.iter().map(|e| e.level).collect()),
+            messages: wants(EntryField::Message)
+                .then(|| self.entries.iter().map(|e| e.message.clone()).collect()),
+            is_continuation: wants(EntryField::IsContinuation)
+                .then(|| self.entries.iter().map(|e| e.is_continuation).collect()),
+        }
+    }
+}
+
+/// 可投影的日志条目字段
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EntryField {
+    LineNumber,
+    Raw,
+    Timestamp,
+    Frame,
+    Category,
+    Level,
+    Message,
+    IsContinuation,
+}
+
+/// 列式日志块 (每个请求的字段一个并行数组，未请求的字段不序列化)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProjectedChunk {
+    /// 起始行号
+    pub start_line: u64,
+    /// 结束行号
+    pub end_line: u64,
+    /// 条目数量 (各数组长度)
+    pub len: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_numbers: Option<Vec<u64>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<Vec<Arc<str>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamps: Option<Vec<Option<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frames: Option<Vec<Option<u64>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub categories: Option<Vec<Option<Arc<str>>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub levels: Option<Vec<LogLevel>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub messages: Option<Vec<Option<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_continuation: Option<Vec<bool>>,
 }
 
 /// 搜索结果
@@ -133,10 +659,117 @@ pub struct SearchResult {
     pub line_number: u64,
     /// 匹配内容
     pub matched_text: String,
-    /// 匹配起始位置 (字符偏移)
+    /// 匹配起始位置 (字节偏移)
     pub start: usize,
+    /// 匹配结束位置 (字节偏移)
+    pub end: usize,
+    /// 匹配起始位置 (字符偏移，供前端高亮)
+    #[serde(default)]
+    pub char_start: usize,
     /// 匹配结束位置 (字符偏移)
+    #[serde(default)]
+    pub char_end: usize,
+    /// 所在行的日志级别
+    pub level: LogLevel,
+    /// 所在行的日志类别
+    pub category: Option<String>,
+    /// 命名捕获组的内容 (正则没有命名组时为 None，未参与匹配的组不包含在内)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captures: Option<HashMap<String, String>>,
+    /// 来源文件路径 (仅多文件搜索时有值，此时 line_number 为该文件中的行号)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_file: Option<Arc<str>>,
+}
+
+/// 文件搜索结果 (超过结果数上限时截断)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchResults {
+    /// 搜索结果 (至多 result_limit 个)
+    pub results: Vec<SearchResult>,
+    /// 是否还有更多结果未返回
+    pub truncated: bool,
+    /// 是否被取消 (results 为取消前已找到的结果)
+    #[serde(default)]
+    pub cancelled: bool,
+}
+
+/// 分批搜索的汇总
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchComplete {
+    /// 返回的结果总数
+    pub total: u64,
+    /// 是否还有更多结果未返回 (超过结果数上限)
+    pub truncated: bool,
+    /// 是否被取消
+    pub cancelled: bool,
+}
+
+/// 跳转到下一个/上一个匹配的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "line", rename_all = "lowercase")]
+pub enum NextMatch {
+    /// 在搜索方向上找到
+    Found(u64),
+    /// 越过文件末尾 (或开头) 回绕后找到
+    Wrapped(u64),
+    /// 没有匹配
+    None,
+}
+
+/// 带上下文的搜索命中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchHitWithContext {
+    /// 命中结果
+    pub result: SearchResult,
+    /// 命中行之前的行 (按行号升序)
+    pub before: Vec<LogEntry>,
+    /// 命中行之后的行 (按行号升序)
+    pub after: Vec<LogEntry>,
+}
+
+/// 正则表达式校验错误
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegexErrorInfo {
+    /// 错误描述
+    pub message: String,
+    /// 出错位置在模式中的字节偏移 (无法定位时为 None)
+    pub offset: Option<usize>,
+}
+
+/// 高亮配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HighlightConfig {
+    /// (类型, 正则表达式)，排在前面的优先级更高
+    pub patterns: Vec<(String, String)>,
+}
+
+/// 高亮片段
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HighlightSpan {
+    /// 起始位置 (字节偏移)
+    pub start: usize,
+    /// 结束位置 (字节偏移，不含)
     pub end: usize,
+    /// 高亮类型 (默认模式为 path/uuid/number)
+    pub kind: String,
+    /// path 类型的规范化路径 (分隔符统一为 /，折叠 . 和 ..)，供前端生成可点击的链接
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized: Option<String>,
+}
+
+/// 搜索结果排序方式
+///
+/// 排序需要先缓冲全部结果，只作用于一次性返回的搜索，分页/流式搜索始终按行号升序
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResultSort {
+    /// 按行号升序
+    #[default]
+    LineAsc,
+    /// 按行号降序
+    LineDesc,
+    /// 按级别 (严重的在前)，同级别按行号升序
+    LevelThenLine,
 }
 
 /// 搜索选项
@@ -148,10 +781,54 @@ pub struct SearchOptions {
     pub use_regex: bool,
     /// 是否忽略大小写
     pub case_insensitive: bool,
+    /// 全词匹配 (仅字面量搜索，正则模式下忽略)
+    #[serde(default)]
+    pub whole_word: bool,
+    /// 忽略大小写时只折叠 ASCII 字母 (仅字面量搜索，正则模式下忽略)
+    ///
+    /// 只影响匹配语义 (K 开尔文符号、ſ 等不再匹配 k、s)，不影响速度:
+    /// regex_engine 的 bench_case_folding 在 100 万行 (约 90MB) 的日志上计数，
+    /// 两种折叠耗时相当 (release 下均约 100-180ms)，差异在测量误差之内
+    #[serde(default)]
+    pub ascii_case_insensitive: bool,
     /// 搜索范围起始行
     pub start_line: Option<u64>,
     /// 搜索范围结束行
     pub end_line: Option<u64>,
+    /// 结果排序方式
+    #[serde(default)]
+    pub sort: ResultSort,
+    /// 只搜索这些级别的行 (空 = 不限)
+    #[serde(default)]
+    pub level_filter: Vec<LogLevel>,
+    /// 只搜索这些类别的行 (空 = 不限)
+    #[serde(default)]
+    pub category_filter: Vec<String>,
+    /// 每个命中前后附带的上下文行数 (上限 MAX_CONTEXT_LINES)
+    #[serde(default)]
+    pub context_lines: u64,
+    /// 最多返回的结果数 (None = DEFAULT_MAX_RESULTS)
+    #[serde(default)]
+    pub max_results: Option<usize>,
+    /// 把日志行与其续行以 \n 连接后整体匹配，`.` 可匹配换行 (仅整文件搜索)
+    ///
+    /// 结果的行号为所属日志行，start/end 等偏移相对于连接后的文本
+    #[serde(default)]
+    pub search_merged: bool,
+}
+
+impl SearchOptions {
+    /// 上下文行数上限，避免单个命中读出大段文件
+    pub const MAX_CONTEXT_LINES: u64 = 100;
+    /// 一次带上下文的搜索最多返回的上下文行总数 (所有命中的 before + after)
+    pub const MAX_CONTEXT_TOTAL_LINES: usize = 50_000;
+    /// 默认的结果数上限，避免过于宽泛的模式在大文件上耗尽内存
+    pub const DEFAULT_MAX_RESULTS: usize = 50_000;
+
+    /// 实际生效的结果数上限
+    pub fn result_limit(&self) -> usize {
+        self.max_results.unwrap_or(Self::DEFAULT_MAX_RESULTS)
+    }
 }
 
 impl Default for SearchOptions {
@@ -160,8 +837,16 @@ impl Default for SearchOptions {
             pattern: String::new(),
             use_regex: true,
             case_insensitive: true,
+            whole_word: false,
+            ascii_case_insensitive: false,
             start_line: None,
             end_line: None,
+            sort: ResultSort::default(),
+            level_filter: Vec::new(),
+            category_filter: Vec::new(),
+            context_lines: 0,
+            max_results: None,
+            search_merged: false,
         }
     }
 }
@@ -173,8 +858,295 @@ pub struct FilterOptions {
     pub categories: Vec<String>,
     /// 要包含的日志级别 (空 = 全部)
     pub levels: Vec<LogLevel>,
+    /// 最低级别，只保留不低于该级别的条目 (与 levels 同时设置时两者都需满足)
+    #[serde(default)]
+    pub min_level: Option<LogLevel>,
     /// 排除的日志类别
     pub exclude_categories: Vec<String>,
+    /// 要包含的消息子标签 (空 = 全部)
+    #[serde(default)]
+    pub sub_tags: Vec<String>,
+}
+
+impl FilterOptions {
+    /// 判断条目是否通过过滤 (包含列表为空表示全部)
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        let category = entry.category.as_deref();
+        let in_list = |list: &[String]| category.is_some_and(|c| list.iter().any(|x| x == c));
+
+        (self.levels.is_empty() || self.levels.contains(&entry.level))
+            && !matches!(self.min_level, Some(min) if entry.level < min)
+            && (self.categories.is_empty() || in_list(&self.categories))
+            && !in_list(&self.exclude_categories)
+            && (self.sub_tags.is_empty()
+                || entry
+                    .sub_tag
+                    .as_deref()
+                    .is_some_and(|tag| self.sub_tags.iter().any(|x| x == tag)))
+    }
+}
+
+/// 结构化导出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// 逗号分隔，首行为列名
+    Csv,
+    /// 每行一个 JSON 对象
+    Ndjson,
+}
+
+/// 各级别日志数量 (由 FileIndex::level_counts 映射，缺失的级别为 0)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct LevelHistogram {
+    pub fatal: u64,
+    pub error: u64,
+    pub warning: u64,
+    pub display: u64,
+    pub verbose: u64,
+    pub very_verbose: u64,
+    pub unknown: u64,
+}
+
+impl LevelHistogram {
+    /// 从按级别名 (小写) 计数的表构建
+    pub fn from_counts(counts: &HashMap<String, u64>) -> Self {
+        let mut histogram = Self::default();
+        for (name, &count) in counts {
+            let slot = match LogLevel::from_str(name) {
+                LogLevel::Fatal => &mut histogram.fatal,
+                LogLevel::Error => &mut histogram.error,
+                LogLevel::Warning => &mut histogram.warning,
+                LogLevel::Display => &mut histogram.display,
+                LogLevel::Verbose => &mut histogram.verbose,
+                LogLevel::VeryVerbose => &mut histogram.very_verbose,
+                LogLevel::Unknown => &mut histogram.unknown,
+            };
+            *slot += count;
+        }
+        histogram
+    }
+}
+
+/// 采样得到的日志类别
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CategorySample {
+    /// 类别 (按首次出现顺序)
+    pub categories: Vec<String>,
+    /// 是否扫描到了文件末尾 (false 表示结果可能不完整)
+    pub complete: bool,
+    /// 实际扫描的行数
+    pub lines_scanned: u64,
+}
+
+/// 目录扫描发现的日志文件 (不建立索引)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogFileInfo {
+    /// 文件路径
+    pub path: String,
+    /// 文件大小 (字节)
+    pub size: u64,
+    /// 修改时间 (Unix 毫秒)
+    pub modified: u64,
+    /// 按文件开头的平均行长估算的行数 (小文件为精确值)
+    pub line_count_estimate: u64,
+}
+
+/// 一次索引构建的耗时与吞吐量 (用于性能诊断)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IndexStats {
+    /// 扫描的字节数
+    pub bytes: u64,
+    /// 索引的行数
+    pub lines: u64,
+    /// 耗时 (毫秒，含小数)
+    pub elapsed_ms: f64,
+    /// 吞吐量 (MB/s，耗时为 0 时为 0)
+    pub mb_per_sec: f64,
+}
+
+impl IndexStats {
+    /// 由扫描量和耗时计算吞吐量
+    pub fn new(bytes: u64, lines: u64, elapsed: std::time::Duration) -> Self {
+        let secs = elapsed.as_secs_f64();
+        let mb_per_sec = if secs > 0.0 {
+            bytes as f64 / (1024.0 * 1024.0) / secs
+        } else {
+            0.0
+        };
+        Self {
+            bytes,
+            lines,
+            elapsed_ms: secs * 1000.0,
+            mb_per_sec,
+        }
+    }
+}
+
+/// 未能解析的行样本 (落入 LogEntry::raw 且不是续行)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnparsedSample {
+    /// 行号
+    pub line_number: u64,
+    /// 原始内容
+    pub raw: String,
+}
+
+/// 行命中的解析分支 (见 LogParser::parse_line_format)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineFormat {
+    /// 自定义格式 (ParserConfig::custom_pattern)
+    Custom,
+    /// [时间戳][帧号]类别: 级别: 消息
+    Standard,
+    /// 类别: 级别: 消息 (含省略级别的 类别: 消息)
+    Simple,
+    /// Log file open, ... 文件头
+    Header,
+    /// 续行
+    Continuation,
+    /// 无法解析
+    Raw,
+}
+
+/// 各解析分支命中的行数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct ParseStats {
+    pub custom: u64,
+    pub standard: u64,
+    pub simple: u64,
+    pub header: u64,
+    pub continuation: u64,
+    pub raw: u64,
+}
+
+impl ParseStats {
+    /// 记录一行
+    pub fn record(&mut self, format: LineFormat) {
+        let slot = match format {
+            LineFormat::Custom => &mut self.custom,
+            LineFormat::Standard => &mut self.standard,
+            LineFormat::Simple => &mut self.simple,
+            LineFormat::Header => &mut self.header,
+            LineFormat::Continuation => &mut self.continuation,
+            LineFormat::Raw => &mut self.raw,
+        };
+        *slot += 1;
+    }
+}
+
+/// 会话边界 (同一文件中追加的多次运行)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionBoundary {
+    /// 会话起始行号
+    pub start_line: u64,
+    /// 起始行的时间戳 (原始文本，起始行没有时间戳时为 None)
+    pub timestamp: Option<String>,
+}
+
+/// 书签
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// 行号 (1-based)
+    pub line_number: u64,
+    /// 备注
+    pub note: String,
+}
+
+impl Bookmark {
+    /// 书签 sidecar 文件路径 (<logpath>.bookmarks.json)
+    pub fn sidecar_path<P: AsRef<Path>>(log_path: P) -> PathBuf {
+        let mut path = log_path.as_ref().as_os_str().to_owned();
+        path.push(".bookmarks.json");
+        PathBuf::from(path)
+    }
+
+    /// 保存书签列表
+    pub fn save_all<P: AsRef<Path>>(path: P, bookmarks: &[Bookmark]) -> std::io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, bookmarks)?;
+        writer.flush()
+    }
+
+    /// 读取保存的书签列表
+    pub fn load_all<P: AsRef<Path>>(path: P) -> std::io::Result<Vec<Bookmark>> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+/// 差异块类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    /// 只在 B 中出现的行
+    Added,
+    /// 只在 A 中出现的行
+    Removed,
+    /// 两侧相同的行
+    Equal,
+}
+
+/// 差异块 (行号均为 1-based)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiffBlock {
+    pub kind: DiffKind,
+    /// A 侧起始行号 (Added 块为插入位置之后的行)
+    pub a_start: u64,
+    /// A 侧行数 (Added 块为 0)
+    pub a_count: u64,
+    /// B 侧起始行号 (Removed 块为删除位置之后的行)
+    pub b_start: u64,
+    /// B 侧行数 (Removed 块为 0)
+    pub b_count: u64,
+    /// 块内的原始行 (Removed 取自 A，Added 取自 B，最多 DIFF_BLOCK_LINES_CAP 行；Equal 块为空)
+    pub lines: Vec<String>,
+}
+
+/// 两个文件的行级差异
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffResult {
+    /// 按顺序排列的差异块，同一位置的删除排在新增之前
+    pub blocks: Vec<DiffBlock>,
+    /// A 的总行数
+    pub a_total_lines: u64,
+    /// B 的总行数
+    pub b_total_lines: u64,
+    /// 差异过大 (超过编辑距离上限) 时，未对齐的中间部分整体按删除 + 新增返回
+    pub approximate: bool,
+}
+
+/// 按归一化消息聚合的日志组
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MessageGroup {
+    /// 数字、路径、UUID 替换为占位符后的消息
+    pub normalized_message: String,
+    /// 出现次数
+    pub count: u64,
+    /// 首次出现的行号
+    pub first_line: u64,
+    /// 首次出现的原始行
+    pub sample: String,
+}
+
+/// 时间窗口内各类别的条目数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimeBucket {
+    /// 窗口起始时间 (UE 时间戳格式)
+    pub start_timestamp: String,
+    /// 各类别的条目数
+    pub counts: HashMap<String, u64>,
+}
+
+/// 打开文件时预览的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewMode {
+    /// 文件开头
+    #[default]
+    Head,
+    /// 文件末尾 (查看崩溃时更有用)
+    Tail,
 }
 
 /// 文件打开结果
@@ -182,7 +1154,20 @@ pub struct FilterOptions {
 pub struct OpenFileResult {
     /// 文件索引
     pub index: FileIndex,
-    /// 前 N 行预览
+    /// 检测到的文件编码
+    pub encoding: Encoding,
+    /// 前 N 行或末尾 N 行预览 (见 PreviewMode)
+    pub preview: Vec<LogEntry>,
+}
+
+/// 多文件合并打开结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenMergedResult {
+    /// 合并后的总行数
+    pub total_lines: u64,
+    /// 参与合并的文件 (按传入顺序，时间戳相同时靠前的文件先出现)
+    pub sources: Vec<String>,
+    /// 合并视图的前 N 行预览
     pub preview: Vec<LogEntry>,
 }
 
@@ -210,3 +1195,198 @@ impl From<regex::Error> for LogParserError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timestamp() {
+        let ts = Timestamp::parse("2026.02.14-03.33.56:070").unwrap();
+        assert_eq!(
+            ts,
+            Timestamp {
+                year: 2026,
+                month: 2,
+                day: 14,
+                hour: 3,
+                minute: 33,
+                second: 56,
+                millis: 70,
+            }
+        );
+
+        assert!(ts < Timestamp::parse("2026.02.14-03.33.56:071").unwrap());
+        assert!(Timestamp::parse("02/14/26 11:33:35").is_none());
+        assert!(Timestamp::parse("2026.13.14-03.33.56:070").is_none());
+
+        assert_eq!(ts.unix_millis(), Some(1_771_040_036_070));
+        let invalid = Timestamp { month: 13, ..ts };
+        assert_eq!(invalid.unix_millis(), None);
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        assert_eq!(Encoding::detect(b"LogInit: Display"), Encoding::Utf8);
+        assert_eq!(Encoding::detect(b"\xEF\xBB\xBFLogInit"), Encoding::Utf8Bom);
+        assert_eq!(Encoding::detect(b"\xFF\xFEL\x00"), Encoding::Utf16Le);
+        assert_eq!(Encoding::detect(b"\xFE\xFF\x00L"), Encoding::Utf16Be);
+        assert_eq!(Encoding::detect(b""), Encoding::Utf8);
+        assert_eq!(Encoding::Utf16Le.bom_len(), 2);
+    }
+
+    #[test]
+    fn test_level_ordering() {
+        let mut levels = vec![
+            LogLevel::Display,
+            LogLevel::Unknown,
+            LogLevel::Fatal,
+            LogLevel::VeryVerbose,
+            LogLevel::Warning,
+            LogLevel::Error,
+            LogLevel::Verbose,
+        ];
+        levels.sort();
+        assert_eq!(
+            levels,
+            vec![
+                LogLevel::Unknown,
+                LogLevel::VeryVerbose,
+                LogLevel::Verbose,
+                LogLevel::Display,
+                LogLevel::Warning,
+                LogLevel::Error,
+                LogLevel::Fatal,
+            ]
+        );
+        assert!(LogLevel::Fatal > LogLevel::Error);
+        assert!(LogLevel::Warning >= LogLevel::Warning);
+        assert!(LogLevel::Unknown < LogLevel::VeryVerbose);
+        assert_eq!(LogLevel::Error.max(LogLevel::Display), LogLevel::Error);
+    }
+
+    #[test]
+    fn test_level_histogram_from_counts() {
+        let counts: HashMap<String, u64> = [("error", 3), ("veryverbose", 2), ("fatal", 1)]
+            .into_iter()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
+
+        let histogram = LevelHistogram::from_counts(&counts);
+        assert_eq!(
+            histogram,
+            LevelHistogram {
+                fatal: 1,
+                error: 3,
+                very_verbose: 2,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_filter_options_matches() {
+        let entry = |category: &str, level: LogLevel| LogEntry {
+            category: Some(category.into()),
+            level,
+            ..LogEntry::raw(1, String::new())
+        };
+        let net_error = entry("LogNet", LogLevel::Error);
+        let net_display = entry("LogNet", LogLevel::Display);
+        let spam_error = entry("LogSpam", LogLevel::Error);
+        let raw = LogEntry::raw(1, "plain".to_string());
+
+        let all = FilterOptions::default();
+        assert!(all.matches(&net_error) && all.matches(&raw));
+
+        let errors = FilterOptions {
+            levels: vec![LogLevel::Error],
+            exclude_categories: vec!["LogSpam".to_string()],
+            ..Default::default()
+        };
+        assert!(errors.matches(&net_error));
+        assert!(!errors.matches(&net_display));
+        assert!(!errors.matches(&spam_error));
+
+        let net = FilterOptions {
+            categories: vec!["LogNet".to_string()],
+            ..Default::default()
+        };
+        assert!(net.matches(&net_display));
+        assert!(!net.matches(&spam_error));
+        assert!(!net.matches(&raw));
+
+        let tagged = LogEntry {
+            sub_tag: Some("AssetCheck".to_string()),
+            ..entry("LogAssetCheck", LogLevel::Warning)
+        };
+        let asset_check = FilterOptions {
+            sub_tags: vec!["AssetCheck".to_string()],
+            ..Default::default()
+        };
+        assert!(asset_check.matches(&tagged));
+        assert!(!asset_check.matches(&net_display));
+
+        let warnings_up = FilterOptions {
+            min_level: Some(LogLevel::Warning),
+            ..Default::default()
+        };
+        assert!(warnings_up.matches(&net_error));
+        assert!(warnings_up.matches(&tagged));
+        assert!(!warnings_up.matches(&net_display));
+        assert!(!warnings_up.matches(&raw));
+    }
+
+    #[test]
+    fn test_seek_position_extreme_lines() {
+        let mut index = FileIndex::new("Game.log".to_string(), 4096);
+        index.total_lines = 2500;
+        index.line_offsets = vec![0, 1000, 2000];
+
+        assert_eq!(index.seek_position(0), (0, 0));
+        assert_eq!(index.seek_position(1), (0, 0));
+        assert_eq!(index.seek_position(2001), (2000, 2000));
+        assert_eq!(index.seek_position(u64::MAX), (0, 0));
+
+        index.dense_offsets = true;
+        index.dense_line_offsets = vec![0, 10, 20];
+        assert_eq!(index.seek_position(3), (20, 2));
+        assert_eq!(index.seek_position(u64::MAX), (0, 0));
+    }
+
+    #[test]
+    fn test_project_chunk() {
+        let entries = vec![
+            LogEntry::raw(1, "plain line".to_string()),
+            LogEntry {
+                level: LogLevel::Error,
+                message: Some("Failed".to_string()),
+                ..LogEntry::raw(2, "LogNet: Error: Failed".to_string())
+            },
+        ];
+        let chunk = LogChunk {
+            start_line: 1,
+            end_line: 2,
+            entries,
+            read_errors: vec![],
+        };
+
+        let projected = chunk.project(&[EntryField::Level, EntryField::Message]);
+
+        assert_eq!(projected.len, 2);
+        assert_eq!(
+            projected.levels,
+            Some(chunk.entries.iter().map(|e| e.level).collect())
+        );
+        assert_eq!(
+            projected.messages,
+            Some(chunk.entries.iter().map(|e| e.message.clone()).collect())
+        );
+        assert!(projected.line_numbers.is_none());
+        assert!(projected.raw.is_none());
+
+        let json = serde_json::to_value(&projected).unwrap();
+        assert!(json.get("levels").is_some());
+        assert!(json.get("raw").is_none());
+    }
+}
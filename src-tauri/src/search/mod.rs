@@ -1,5 +1,7 @@
 //! 搜索模块
 
 pub mod regex_engine;
+pub mod tokenizer;
 
-pub use regex_engine::{search, SearchEngine};
+pub use regex_engine::{search, validate_regex, SearchEngine};
+pub use tokenizer::{DefaultTokenizer, Token, Tokenizer};
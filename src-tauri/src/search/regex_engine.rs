@@ -2,160 +2,2501 @@
 //!
 //! 支持 regex 和字面量搜索，流式搜索大文件
 
+use memchr::memmem;
 use regex::{Regex, RegexBuilder};
+use std::cmp::Reverse;
+use std::collections::HashMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
-use crate::parser::{FileIndex, SearchOptions, SearchResult};
+use crate::parser::{
+    Encoding, FileIndex, FilterOptions, LogParser, NextMatch, ParserConfig, RegexErrorInfo,
+    ResultSort, SearchComplete, SearchHitWithContext, SearchOptions, SearchResult, SearchResults,
+};
+use crate::search::tokenizer::{DefaultTokenizer, Token, Tokenizer};
+use crate::streaming::encoding::decode_lines_lossy;
+use crate::streaming::{buffered, map_file, LineReader};
+
+/// 分页搜索每次最多扫描的行数
+const PAGE_SCAN_LINES: u64 = 10000;
+
+/// 按行号跳读时的缓冲区大小 (每次定位都会重新填充，不宜过大)
+const POINT_READ_BUFFER_SIZE: usize = 8 * 1024;
+
+/// 命名捕获组 -> 捕获内容
+type NamedCaptures = HashMap<String, String>;
+
+/// 一个匹配 (起始字节, 结束字节, 命名捕获)
+type MatchSpan = (usize, usize, Option<NamedCaptures>);
+
+/// 匹配器
+enum Matcher {
+    /// 正则匹配
+    Regex(Regex),
+    /// 区分大小写的字面量匹配 (memmem 子串查找，不经过正则)
+    Literal(Box<memmem::Finder<'static>>),
+}
 
 /// 搜索引擎
 pub struct SearchEngine {
-    regex: Regex,
+    matcher: Matcher,
+    /// 全词匹配时判断词边界的分词器 (匹配的首尾不能落在词元内部)
+    word_tokenizer: Option<Box<dyn Tokenizer>>,
+    /// 级别/类别过滤 (无过滤条件时为 None，扫描文件时不必解析每一行)
+    filter: Option<FilterOptions>,
+    /// 取消标志，置位后文件扫描在下一行停止
+    cancel: Option<Arc<AtomicBool>>,
+    /// 解析命中行和过滤时使用的配置 (自定义格式、级别别名)
+    parser_config: ParserConfig,
 }
 
 impl SearchEngine {
     /// 创建搜索引擎
     pub fn new(options: &SearchOptions) -> Result<Self, regex::Error> {
+        let filter = (!options.level_filter.is_empty() || !options.category_filter.is_empty())
+            .then(|| FilterOptions {
+                levels: options.level_filter.clone(),
+                categories: options.category_filter.clone(),
+                ..Default::default()
+            });
+
+        let word_tokenizer = (options.whole_word && !options.use_regex)
+            .then(|| Box::new(DefaultTokenizer) as Box<dyn Tokenizer>);
+
+        // 区分大小写的字面量搜索直接走子串查找 (全词匹配由分词器判断边界，同样适用)
+        if !options.use_regex && !options.case_insensitive && !options.pattern.is_empty() {
+            let finder = memmem::Finder::new(options.pattern.as_bytes()).into_owned();
+            return Ok(Self {
+                matcher: Matcher::Literal(Box::new(finder)),
+                word_tokenizer,
+                filter,
+                cancel: None,
+                parser_config: ParserConfig::default(),
+            });
+        }
+
         let regex = if options.use_regex {
             RegexBuilder::new(&options.pattern)
                 .case_insensitive(options.case_insensitive)
+                .dot_matches_new_line(options.search_merged)
                 .build()?
         } else {
             // 字面量搜索: 转义所有特殊字符
             let escaped = regex::escape(&options.pattern);
+            // ASCII 折叠不使用 Unicode 大小写表，不会把 K (开尔文符号) 等视为 k
             RegexBuilder::new(&escaped)
                 .case_insensitive(options.case_insensitive)
+                .unicode(!options.ascii_case_insensitive)
                 .build()?
         };
 
-        Ok(Self { regex })
+        Ok(Self {
+            matcher: Matcher::Regex(regex),
+            word_tokenizer,
+            filter,
+            cancel: None,
+            parser_config: ParserConfig::default(),
+        })
+    }
+
+    /// 设置取消标志: search_in_file / search_in_file_batched / search_next_page 每行检查一次，置位后返回已找到的结果
+    pub fn with_cancel(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// 设置解析配置，与行读取器一致地识别自定义格式和级别别名
+    pub fn with_parser_config(mut self, parser_config: ParserConfig) -> Self {
+        self.parser_config = parser_config;
+        self
+    }
+
+    /// 是否已被取消
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
     }
 
     /// 在字符串中搜索所有匹配
+    ///
+    /// 正则含命名捕获组时为每个匹配附带 captures，否则只查找匹配位置
     pub fn search_in_string(&self, text: &str, line_number: u64) -> Vec<SearchResult> {
-        self.regex
-            .find_iter(text)
-            .map(|m| SearchResult {
-                line_number,
-                matched_text: m.as_str().to_string(),
-                start: m.start(),
-                end: m.end(),
+        self.search_in_text(text, text, line_number)
+    }
+
+    /// 在 text 中搜索，结果的级别与类别取自 line (合并续行搜索时为所属日志行)
+    fn search_in_text(&self, text: &str, line: &str, line_number: u64) -> Vec<SearchResult> {
+        let spans = self.match_spans(text);
+        if spans.is_empty() {
+            return Vec::new();
+        }
+
+        // 解析命中的行，附带级别与类别，前端着色时无需再次读取
+        let entry = LogParser::parse_line_with(&self.parser_config, line_number, line);
+        let category = entry.category.as_deref().map(str::to_string);
+
+        // 匹配按位置升序，字符偏移从上一个匹配处累加
+        let (mut byte_pos, mut char_pos) = (0, 0);
+        spans
+            .into_iter()
+            .map(|(start, end, captures)| {
+                let char_start = char_pos + text[byte_pos..start].chars().count();
+                let char_end = char_start + text[start..end].chars().count();
+                (byte_pos, char_pos) = (end, char_end);
+
+                SearchResult {
+                    line_number,
+                    matched_text: text[start..end].to_string(),
+                    start,
+                    end,
+                    char_start,
+                    char_end,
+                    level: entry.level,
+                    category: category.clone(),
+                    captures,
+                    source_file: None,
+                }
             })
             .collect()
     }
 
+    /// 字符串中的所有匹配，按位置升序 (全词匹配时只保留首尾都在词边界上的)
+    fn match_spans(&self, text: &str) -> Vec<MatchSpan> {
+        if let Some(tokenizer) = &self.word_tokenizer {
+            return self.whole_word_spans(text, tokenizer.as_ref());
+        }
+        match &self.matcher {
+            Matcher::Regex(regex) if regex.capture_names().flatten().next().is_some() => regex
+                .captures_iter(text)
+                .map(|caps| {
+                    let m = caps.get(0).expect("group 0 always participates");
+                    (m.start(), m.end(), Some(named_captures(regex, &caps)))
+                })
+                .collect(),
+            Matcher::Regex(regex) => regex
+                .find_iter(text)
+                .map(|m| (m.start(), m.end(), None))
+                .collect(),
+            Matcher::Literal(finder) => {
+                let len = finder.needle().len();
+                finder
+                    .find_iter(text.as_bytes())
+                    .map(|start| (start, start + len, None))
+                    .collect()
+            }
+        }
+    }
+
+    /// 全词匹配: 逐个查找候选，首尾落在词元内部的候选被丢弃并从其后一个字符重新查找，
+    /// 不会漏掉与被丢弃候选重叠的全词匹配。只在出现候选时才分词
+    ///
+    /// 全词匹配只用于字面量搜索，没有捕获组
+    fn whole_word_spans(&self, text: &str, tokenizer: &dyn Tokenizer) -> Vec<MatchSpan> {
+        let next_char = |pos: usize| pos + text[pos..].chars().next().map_or(1, char::len_utf8);
+        let mut spans = Vec::new();
+        let mut tokens = None;
+        let mut from = 0;
+
+        while from <= text.len() {
+            let Some((start, end)) = self.find_at(text, from) else {
+                break;
+            };
+            let line_tokens = tokens.get_or_insert_with(|| tokenizer.tokenize(text));
+            if inside_token(line_tokens, start) || inside_token(line_tokens, end) {
+                from = next_char(start);
+                continue;
+            }
+            spans.push((start, end, None));
+            from = if end > start { end } else { next_char(end) };
+        }
+        spans
+    }
+
+    /// 从 from 字节处开始查找第一个匹配
+    fn find_at(&self, text: &str, from: usize) -> Option<(usize, usize)> {
+        match &self.matcher {
+            Matcher::Regex(regex) => regex.find_at(text, from).map(|m| (m.start(), m.end())),
+            Matcher::Literal(finder) => finder
+                .find(&text.as_bytes()[from..])
+                .map(|start| (from + start, from + start + finder.needle().len())),
+        }
+    }
+
+    /// 统计字符串中的匹配数 (与 search_in_string 返回的结果数一致)
+    pub fn count_in_string(&self, text: &str) -> usize {
+        if let Some(tokenizer) = &self.word_tokenizer {
+            return self.whole_word_spans(text, tokenizer.as_ref()).len();
+        }
+        match &self.matcher {
+            Matcher::Regex(regex) => regex.find_iter(text).count(),
+            Matcher::Literal(finder) => finder.find_iter(text.as_bytes()).count(),
+        }
+    }
+
     /// 在文件中搜索 (流式)
     pub fn search_in_file<P: AsRef<Path>>(
         &self,
         path: P,
         index: &FileIndex,
         options: &SearchOptions,
-    ) -> std::io::Result<Vec<SearchResult>> {
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
+    ) -> std::io::Result<SearchResults> {
+        let limit = options.result_limit();
+        let mut results = Vec::new();
+        let mut truncated = false;
 
-        let start_line = options.start_line.unwrap_or(1);
-        let end_line = options.end_line.unwrap_or(index.total_lines);
+        let cancelled = self.scan_file(path, index, options, |matches| {
+            results.extend(matches);
 
-        // 计算起始偏移
-        let offset_index = ((start_line - 1) / FileIndex::INDEX_INTERVAL) as usize;
-        if offset_index < index.line_offsets.len() {
-            reader.seek(SeekFrom::Start(index.line_offsets[offset_index]))?;
+            // 找到超出上限的结果后即可停止，只保留前 limit 个
+            if results.len() > limit {
+                results.truncate(limit);
+                truncated = true;
+            }
+            !truncated
+        })?;
+
+        Ok(SearchResults {
+            results: sort_results(results, options.sort),
+            truncated,
+            cancelled,
+        })
+    }
+
+    /// 依次搜索多个文件并拼接结果，每个结果的 source_file 为来源文件
+    ///
+    /// options.max_results 为所有文件共享的上限；排序只在各文件内部进行，文件按传入顺序排列。
+    /// 取消或达到上限后不再搜索剩余的文件
+    pub fn search_in_files<P: AsRef<Path>>(
+        &self,
+        files: &[(P, FileIndex)],
+        options: &SearchOptions,
+    ) -> std::io::Result<SearchResults> {
+        let limit = options.result_limit();
+        let mut all = SearchResults::default();
+
+        for (path, index) in files {
+            let remaining = SearchOptions {
+                max_results: Some(limit - all.results.len()),
+                ..options.clone()
+            };
+            let found = self.search_in_file(path, index, &remaining)?;
+
+            let source: Arc<str> = path.as_ref().to_string_lossy().into();
+            all.results
+                .extend(found.results.into_iter().map(|mut result| {
+                    result.source_file = Some(source.clone());
+                    result
+                }));
+            all.truncated = found.truncated;
+            all.cancelled = found.cancelled;
+            if all.truncated || all.cancelled {
+                break;
+            }
         }
 
-        let mut results = Vec::new();
-        let start_offset = (offset_index as u64) * FileIndex::INDEX_INTERVAL;
+        Ok(all)
+    }
+
+    /// 在文件中搜索，结果按行号顺序每 batch_size 个交给 on_batch 一次 (最后一批可能不足)
+    ///
+    /// 忽略 options.sort；结果总数同样受 options.max_results 限制
+    pub fn search_in_file_batched<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        options: &SearchOptions,
+        batch_size: usize,
+        mut on_batch: impl FnMut(Vec<SearchResult>),
+    ) -> std::io::Result<SearchComplete> {
+        let batch_size = batch_size.max(1);
+        let limit = options.result_limit();
+        let mut batch = Vec::new();
+        let mut total = 0;
+        let mut truncated = false;
+
+        let cancelled = self.scan_file(path, index, options, |mut matches| {
+            let room = limit - total;
+            if matches.len() > room {
+                matches.truncate(room);
+                truncated = true;
+            }
+            total += matches.len();
+            batch.extend(matches);
+
+            while batch.len() >= batch_size {
+                on_batch(batch.drain(..batch_size).collect());
+            }
+            !truncated
+        })?;
+
+        if !batch.is_empty() {
+            on_batch(batch);
+        }
+
+        Ok(SearchComplete {
+            total: total as u64,
+            truncated,
+            cancelled,
+        })
+    }
+
+    /// 只搜索 line_numbers 中的行 (升序，如某个类别的行号)，不扫描整个文件
+    ///
+    /// 遵循 options 的行范围、结果数上限和排序。返回结果以及实际读取的行数
+    /// (稀疏索引下需从索引点读到目标行，读取行数可能多于目标行数)
+    pub fn search_in_lines<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        line_numbers: &[u64],
+        options: &SearchOptions,
+    ) -> std::io::Result<(SearchResults, u64)> {
+        let file = File::open(path)?;
+        let start_line = options.start_line.unwrap_or(1).max(1);
+        let end_line = options
+            .end_line
+            .unwrap_or(index.total_lines)
+            .min(index.total_lines);
 
-        for (i, line_result) in reader.lines().enumerate() {
-            let line_number = start_offset + i as u64 + 1;
+        let limit = options.result_limit();
+        let mut results = Vec::new();
+        let mut truncated = false;
+        let mut cancelled = false;
+        let mut lines_read = 0;
+        // 当前的顺序读取位置: (解码器, 已读过的行数)
+        let mut cursor = None;
 
+        for &line_number in line_numbers {
+            if line_number < start_line {
+                continue;
+            }
             if line_number > end_line {
                 break;
             }
+            if self.is_cancelled() {
+                cancelled = true;
+                break;
+            }
 
-            if line_number < start_line {
+            // 目标行在当前位置之后且没有更近的索引点时继续顺序读，否则重新定位
+            let (offset, lines_before) = index.seek_position(line_number);
+            let (mut lines, mut position) = match cursor.take() {
+                Some((lines, position)) if position < line_number && position >= lines_before => {
+                    (lines, position)
+                }
+                _ => {
+                    let mut reader = BufReader::with_capacity(POINT_READ_BUFFER_SIZE, &file);
+                    reader.seek(SeekFrom::Start(offset))?;
+                    (lossy_lines(reader, index.encoding), lines_before)
+                }
+            };
+
+            let mut line = None;
+            while position < line_number {
+                let Some(next) = lines.next() else {
+                    break;
+                };
+                position += 1;
+                lines_read += 1;
+                if position == line_number {
+                    line = Some(next?);
+                }
+            }
+            cursor = Some((lines, position));
+
+            let Some(line) = line else {
+                break;
+            };
+            if !self.passes_filter(&line) {
                 continue;
             }
+            results.extend(self.search_in_string(&line, line_number));
 
-            let line = line_result?;
-            let matches = self.search_in_string(&line, line_number);
-            results.extend(matches);
+            if results.len() > limit {
+                results.truncate(limit);
+                truncated = true;
+                break;
+            }
         }
 
-        Ok(results)
+        let results = SearchResults {
+            results: sort_results(results, options.sort),
+            truncated,
+            cancelled,
+        };
+        Ok((results, lines_read))
     }
 
-    /// 搜索下一页结果 (用于增量搜索)
-    pub fn search_next_page<P: AsRef<Path>>(
+    /// 按行号顺序扫描 options 指定的行范围，每个有命中的行调用一次 on_matches
+    ///
+    /// on_matches 返回 false 时停止；返回值表示是否因取消而停止
+    fn scan_file<P: AsRef<Path>>(
         &self,
         path: P,
         index: &FileIndex,
-        from_line: u64,
-        max_results: usize,
-    ) -> std::io::Result<Vec<SearchResult>> {
-        let file = File::open(path)?;
-        let mut reader = BufReader::new(file);
+        options: &SearchOptions,
+        mut on_matches: impl FnMut(Vec<SearchResult>) -> bool,
+    ) -> std::io::Result<bool> {
+        self.scan_texts(path, index, options, |text, line, line_number| {
+            let matches = self.search_in_text(text, line, line_number);
+            matches.is_empty() || on_matches(matches)
+        })
+    }
 
-        let end_line = (from_line + 10000).min(index.total_lines);
+    /// 按行号顺序扫描 options 指定的行范围，每个通过过滤的行调用一次 on_text(文本, 所属行, 行号)
+    ///
+    /// 合并续行搜索时文本为整条日志，所属行为其首行；只需计数或判断是否匹配时不必构造搜索结果。
+    /// UTF-8 文件直接在内存映射上按行切片，不为每行分配字符串；UTF-16、合并续行
+    /// 搜索和空文件逐行解码。on_text 返回 false 时停止；返回值表示是否因取消而停止
+    fn scan_texts<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        options: &SearchOptions,
+        on_text: impl FnMut(&str, &str, u64) -> bool,
+    ) -> std::io::Result<bool> {
+        let start_line = options.start_line.unwrap_or(1).max(1);
+        let end_line = options
+            .end_line
+            .unwrap_or(index.total_lines)
+            .min(index.total_lines);
+        if start_line > end_line {
+            return Ok(false);
+        }
 
-        // 计算起始偏移
-        let offset_index = ((from_line - 1) / FileIndex::INDEX_INTERVAL) as usize;
-        if offset_index < index.line_offsets.len() {
-            reader.seek(SeekFrom::Start(index.line_offsets[offset_index]))?;
+        if !options.search_merged && !index.encoding.is_utf16() {
+            if let Some(mmap) = map_file(&path)? {
+                return self.scan_mapped(&mmap, index, start_line, end_line, on_text);
+            }
         }
 
-        let mut results = Vec::new();
-        let start_offset = (offset_index as u64) * FileIndex::INDEX_INTERVAL;
+        let file = File::open(path)?;
+        self.scan_buffered(file, index, options, start_line, end_line, on_text)
+    }
 
-        for (i, line_result) in reader.lines().enumerate() {
-            let line_number = start_offset + i as u64 + 1;
+    /// 在内存映射的 UTF-8 内容上逐行匹配，行的切分与解码与 lossy_lines 一致
+    fn scan_mapped(
+        &self,
+        data: &[u8],
+        index: &FileIndex,
+        start_line: u64,
+        end_line: u64,
+        mut on_text: impl FnMut(&str, &str, u64) -> bool,
+    ) -> std::io::Result<bool> {
+        let (offset, lines_before) = index.seek_position(start_line);
+        let mut pos = usize::try_from(offset).map_or(data.len(), |offset| offset.min(data.len()));
+        let mut line_number = lines_before;
 
-            if line_number > end_line || results.len() >= max_results {
-                break;
+        while pos < data.len() && line_number < end_line {
+            line_number += 1;
+
+            // 去掉行尾的 \n 或 \r\n；最后一行没有换行时保留原样
+            let (bytes, next) = match memchr::memchr(b'\n', &data[pos..]) {
+                Some(len) => {
+                    let bytes = &data[pos..pos + len];
+                    (bytes.strip_suffix(b"\r").unwrap_or(bytes), pos + len + 1)
+                }
+                None => (&data[pos..], data.len()),
+            };
+            pos = next;
+
+            if line_number < start_line {
+                continue;
+            }
+            if self.is_cancelled() {
+                return Ok(true);
             }
 
-            if line_number < from_line {
+            // 无效 UTF-8 替换为 U+FFFD (有效的行不分配)
+            let line = String::from_utf8_lossy(bytes);
+            if !self.passes_filter(&line) {
                 continue;
             }
+            if !on_text(&line, &line, line_number) {
+                break;
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// 逐行解码并匹配 (UTF-16、合并续行搜索等无法直接在映射上切片的情况)
+    fn scan_buffered(
+        &self,
+        file: File,
+        index: &FileIndex,
+        options: &SearchOptions,
+        start_line: u64,
+        end_line: u64,
+        mut on_text: impl FnMut(&str, &str, u64) -> bool,
+    ) -> std::io::Result<bool> {
+        let mut reader = buffered(file);
+
+        // 计算起始偏移
+        let start_offset = seek_to_line(&mut reader, index, start_line)?;
+
+        let lines = lossy_lines(reader, index.encoding)
+            .enumerate()
+            .map(|(i, line_result)| (start_offset + i as u64 + 1, line_result))
+            .skip_while(|(line_number, _)| *line_number < start_line)
+            .take_while(|(line_number, _)| *line_number <= end_line);
+
+        if options.search_merged {
+            return self.scan_merged(lines, on_text);
+        }
+
+        for (line_number, line_result) in lines {
+            if self.is_cancelled() {
+                return Ok(true);
+            }
 
             let line = line_result?;
-            let matches = self.search_in_string(&line, line_number);
-            results.extend(matches);
+            if !self.passes_filter(&line) {
+                continue;
+            }
+            if !on_text(&line, &line, line_number) {
+                break;
+            }
         }
 
-        Ok(results)
+        Ok(false)
     }
-}
 
-/// 便捷搜索函数
-pub fn search<P: AsRef<Path>>(
-    path: P,
-    index: &FileIndex,
-    options: &SearchOptions,
-) -> Result<Vec<SearchResult>, Box<dyn std::error::Error>> {
-    let engine = SearchEngine::new(options)?;
-    Ok(engine.search_in_file(path, index, options)?)
-}
+    /// 查找 from_line 之后 (不含) 第一个匹配所在的行号，找到即停止扫描
+    ///
+    /// 仍遵守 options 中的范围、过滤和 search_merged；被取消或没有匹配时返回 None
+    pub fn find_next_match<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        from_line: u64,
+        options: &SearchOptions,
+    ) -> std::io::Result<Option<u64>> {
+        let options = SearchOptions {
+            start_line: Some(
+                options
+                    .start_line
+                    .unwrap_or(1)
+                    .max(from_line.saturating_add(1)),
+            ),
+            ..options.clone()
+        };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let mut found = None;
+        self.scan_file(path, index, &options, |matches| {
+            found = matches.first().map(|m| m.line_number);
+            false
+        })?;
 
-    #[test]
-    fn test_regex_search() {
-        let options = SearchOptions {
-            pattern: r"Error:\s*(\w+)".to_string(),
-            use_regex: true,
-            case_insensitive: true,
-            ..Default::default()
+        Ok(found)
+    }
+
+    /// 查找 from_line 之前 (不含) 最后一个匹配所在的行号
+    ///
+    /// 与 search_prev_page 一样按索引块向前扫描，找到即停止；
+    /// search_merged 时跨块的续行会在块边界处被拆开
+    pub fn find_prev_match<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        from_line: u64,
+        options: &SearchOptions,
+    ) -> std::io::Result<Option<u64>> {
+        let start_line = options.start_line.unwrap_or(1).max(1);
+        let mut block_end = from_line
+            .saturating_sub(1)
+            .min(options.end_line.unwrap_or(u64::MAX))
+            .min(index.total_lines);
+
+        while block_end >= start_line && !self.is_cancelled() {
+            let interval = index.index_interval;
+            let block_start = ((block_end - 1) / interval * interval + 1).max(start_line);
+            let block = SearchOptions {
+                start_line: Some(block_start),
+                end_line: Some(block_end),
+                ..options.clone()
+            };
+
+            let mut last = None;
+            self.scan_file(path.as_ref(), index, &block, |matches| {
+                last = matches.last().map(|m| m.line_number);
+                true
+            })?;
+            if last.is_some() {
+                return Ok(last);
+            }
+
+            block_end = block_start - 1;
+        }
+
+        Ok(None)
+    }
+
+    /// 查找下一个匹配，wrap 时在 from_line 之后没有匹配则从范围开头继续找
+    ///
+    /// 回绕后最多扫描到 from_line (含)，整个范围没有匹配时扫描一遍即返回 None
+    pub fn find_next_wrapping<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        from_line: u64,
+        options: &SearchOptions,
+        wrap: bool,
+    ) -> std::io::Result<NextMatch> {
+        if let Some(line) = self.find_next_match(path.as_ref(), index, from_line, options)? {
+            return Ok(NextMatch::Found(line));
+        }
+        if !wrap {
+            return Ok(NextMatch::None);
+        }
+
+        let head = SearchOptions {
+            end_line: Some(options.end_line.unwrap_or(u64::MAX).min(from_line)),
+            ..options.clone()
+        };
+        Ok(match self.find_next_match(path, index, 0, &head)? {
+            Some(line) => NextMatch::Wrapped(line),
+            None => NextMatch::None,
+        })
+    }
+
+    /// 查找上一个匹配，wrap 时在 from_line 之前没有匹配则从范围末尾继续向前找
+    ///
+    /// 回绕后最多扫描到 from_line (含)，整个范围没有匹配时扫描一遍即返回 None
+    pub fn find_prev_wrapping<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        from_line: u64,
+        options: &SearchOptions,
+        wrap: bool,
+    ) -> std::io::Result<NextMatch> {
+        if let Some(line) = self.find_prev_match(path.as_ref(), index, from_line, options)? {
+            return Ok(NextMatch::Found(line));
+        }
+        if !wrap {
+            return Ok(NextMatch::None);
+        }
+
+        let tail = SearchOptions {
+            start_line: Some(options.start_line.unwrap_or(1).max(from_line)),
+            ..options.clone()
         };
+        Ok(match self.find_prev_match(path, index, u64::MAX, &tail)? {
+            Some(line) => NextMatch::Wrapped(line),
+            None => NextMatch::None,
+        })
+    }
 
-        let engine = SearchEngine::new(&options).unwrap();
-        let results = engine.search_in_string("LogWindows: Error: TestMessage", 1);
+    /// scan_texts 的合并续行模式: 日志行与其后的续行以 \n 连接后整体匹配
+    ///
+    /// 范围开头没有所属日志行的续行单独成为一条
+    fn scan_merged(
+        &self,
+        lines: impl Iterator<Item = (u64, std::io::Result<String>)>,
+        mut on_text: impl FnMut(&str, &str, u64) -> bool,
+    ) -> std::io::Result<bool> {
+        // 匹配一条合并后的日志 (行号, 首行长度, 连接后的文本)，返回是否继续
+        let mut search_merged = |(line_number, line_len, text): (u64, usize, String)| {
+            let line = &text[..line_len];
+            !self.passes_filter(line) || on_text(&text, line, line_number)
+        };
 
-        assert_eq!(results.len(), 1);
-        assert_eq!(results[0].matched_text, "Error: TestMessage");
+        let mut pending: Option<(u64, usize, String)> = None;
+        for (line_number, line_result) in lines {
+            if self.is_cancelled() {
+                return Ok(true);
+            }
+
+            let line = line_result?;
+            if LogParser::is_continuation(line.trim_end()) {
+                if let Some((_, _, text)) = pending.as_mut() {
+                    text.push('\n');
+                    text.push_str(&line);
+                    continue;
+                }
+            }
+
+            if let Some(merged) = pending.take() {
+                if !search_merged(merged) {
+                    return Ok(false);
+                }
+            }
+            pending = Some((line_number, line.len(), line));
+        }
+
+        if let Some(merged) = pending {
+            search_merged(merged);
+        }
+
+        Ok(false)
+    }
+
+    /// 在文件中搜索，并为每个命中附带前后 context_lines 行
+    ///
+    /// 上下文不越过文件边界，行数上限为 SearchOptions::MAX_CONTEXT_LINES；同一行的多个命中
+    /// 共用一次读取。上下文行总数将超过 SearchOptions::MAX_CONTEXT_TOTAL_LINES 时不再返回后续命中。
+    /// 只在读取每个命中的上下文时短暂锁定 reader，扫描期间其他读取不受影响
+    pub fn search_with_context<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        options: &SearchOptions,
+        reader: &Mutex<LineReader>,
+    ) -> std::io::Result<Vec<SearchHitWithContext>> {
+        let results = self.search_in_file(path, index, options)?.results;
+        let context = options.context_lines.min(SearchOptions::MAX_CONTEXT_LINES);
+
+        let mut hits: Vec<SearchHitWithContext> = Vec::with_capacity(results.len());
+        let mut total_context = 0;
+
+        for result in results {
+            let line = result.line_number;
+            let (before, after) = match hits.last().filter(|h| h.result.line_number == line) {
+                Some(previous) => (previous.before.clone(), previous.after.clone()),
+                None if context == 0 => (Vec::new(), Vec::new()),
+                None => {
+                    let chunk = reader
+                        .lock()
+                        .map_err(|e| std::io::Error::other(e.to_string()))?
+                        .read_range(
+                            line.saturating_sub(context).max(1),
+                            line.saturating_add(context),
+                        )?;
+                    chunk
+                        .entries
+                        .into_iter()
+                        .filter(|e| e.line_number != line)
+                        .partition(|e| e.line_number < line)
+                }
+            };
+
+            total_context += before.len() + after.len();
+            if total_context > SearchOptions::MAX_CONTEXT_TOTAL_LINES {
+                break;
+            }
+            hits.push(SearchHitWithContext {
+                result,
+                before,
+                after,
+            });
+        }
+
+        Ok(hits)
+    }
+
+    /// 统计文件中的匹配总数 (流式)
+    ///
+    /// 同一行的多个匹配分别计数，不受结果数上限影响 (与 search_in_file 未截断时的结果数一致)；
+    /// 与 search_in_file 一样遵循 options 的行范围、过滤和 search_merged
+    pub fn count_matches<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        options: &SearchOptions,
+    ) -> std::io::Result<u64> {
+        let mut count = 0;
+        self.scan_texts(path, index, options, |text, _, _| {
+            count += self.count_in_string(text) as u64;
+            true
+        })?;
+        Ok(count)
+    }
+
+    /// 至少有一个匹配的行号 (升序，同一行只出现一次)，用于滚动条小地图
+    ///
+    /// 遵循 options 的行范围、过滤和 search_merged，不受结果数上限影响；被取消时返回已找到的行
+    pub fn matching_lines<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        options: &SearchOptions,
+    ) -> std::io::Result<Vec<u64>> {
+        let mut lines: Vec<u64> = Vec::new();
+        self.scan_file(path, index, options, |matches| {
+            if let Some(line_number) = matches.first().map(|m| m.line_number) {
+                if lines.last() != Some(&line_number) {
+                    lines.push(line_number);
+                }
+            }
+            true
+        })?;
+        Ok(lines)
+    }
+
+    /// 搜索下一页结果 (用于增量搜索)
+    ///
+    /// 从 from_line 起最多扫描 PAGE_SCAN_LINES 行，找到 max_results 个结果后在该行结束处停止；
+    /// 与 search_in_file 一样遵循 options 的行范围、过滤和 search_merged
+    pub fn search_next_page<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        from_line: u64,
+        max_results: usize,
+        options: &SearchOptions,
+    ) -> std::io::Result<Vec<SearchResult>> {
+        let mut results = Vec::new();
+        if max_results == 0 {
+            return Ok(results);
+        }
+
+        let from_line = from_line.max(1);
+        let page = SearchOptions {
+            start_line: Some(options.start_line.unwrap_or(1).max(from_line)),
+            end_line: Some(
+                options
+                    .end_line
+                    .unwrap_or(u64::MAX)
+                    .min(from_line.saturating_add(PAGE_SCAN_LINES)),
+            ),
+            ..options.clone()
+        };
+        self.scan_file(path, index, &page, |matches| {
+            results.extend(matches);
+            results.len() < max_results
+        })?;
+
+        Ok(results)
+    }
+
+    /// 搜索上一页结果 (from_line 之前的行，离 from_line 最近的在前)
+    ///
+    /// 从 from_line 所在的索引块开始按块向前扫描，不必每次从文件开头读取，最多扫描
+    /// PAGE_SCAN_LINES 行；遵循 options 的行范围、过滤和 search_merged
+    /// (与 find_prev_match 一样，跨块的续行会在块边界处被拆开)
+    pub fn search_prev_page<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        from_line: u64,
+        max_results: usize,
+        options: &SearchOptions,
+    ) -> std::io::Result<Vec<SearchResult>> {
+        let end_line = from_line
+            .saturating_sub(1)
+            .min(options.end_line.unwrap_or(u64::MAX))
+            .min(index.total_lines);
+        if end_line == 0 || max_results == 0 {
+            return Ok(Vec::new());
+        }
+        let start_line = end_line
+            .saturating_sub(PAGE_SCAN_LINES)
+            .max(options.start_line.unwrap_or(1))
+            .max(1);
+
+        let mut results = Vec::new();
+        let mut block_end = end_line;
+
+        while block_end >= start_line && results.len() < max_results && !self.is_cancelled() {
+            // 当前块的第一行 (不早于扫描窗口)
+            let interval = index.index_interval;
+            let block_start = ((block_end - 1) / interval * interval + 1).max(start_line);
+            let block = SearchOptions {
+                start_line: Some(block_start),
+                end_line: Some(block_end),
+                ..options.clone()
+            };
+
+            let mut block_results = Vec::new();
+            self.scan_file(path.as_ref(), index, &block, |matches| {
+                block_results.extend(matches);
+                true
+            })?;
+
+            results.extend(block_results.into_iter().rev());
+            block_end = block_start - 1;
+        }
+
+        results.truncate(max_results);
+        Ok(results)
+    }
+
+    /// 判断行是否通过级别/类别过滤 (先于匹配执行)
+    fn passes_filter(&self, line: &str) -> bool {
+        match &self.filter {
+            Some(filter) => {
+                filter.matches(&LogParser::parse_line_with(&self.parser_config, 0, line))
+            }
+            None => true,
+        }
+    }
+
+    /// 判断字符串中是否存在匹配
+    pub fn is_match(&self, text: &str) -> bool {
+        if let Some(tokenizer) = &self.word_tokenizer {
+            return !self.whole_word_spans(text, tokenizer.as_ref()).is_empty();
+        }
+        match &self.matcher {
+            Matcher::Regex(regex) => regex.is_match(text),
+            Matcher::Literal(finder) => finder.find(text.as_bytes()).is_some(),
+        }
+    }
+
+    /// 计算可折叠的行范围 (不含匹配的区间)
+    ///
+    /// 每个命中行前后保留 context 行，相邻命中的上下文会合并。
+    /// 只考虑 options 的行范围，返回该范围内的闭区间 (起始行, 结束行)，按行号升序；
+    /// search_merged 时以命中所属的日志行为准
+    pub fn fold_ranges<P: AsRef<Path>>(
+        &self,
+        path: P,
+        index: &FileIndex,
+        options: &SearchOptions,
+        context: u64,
+    ) -> std::io::Result<Vec<(u64, u64)>> {
+        let start_line = options.start_line.unwrap_or(1).max(1);
+        let end_line = options
+            .end_line
+            .unwrap_or(index.total_lines)
+            .min(index.total_lines);
+
+        let mut folds = Vec::new();
+        if start_line > end_line {
+            return Ok(folds);
+        }
+        // 下一个尚未被上下文覆盖的行
+        let mut next_unfolded = start_line;
+
+        self.scan_texts(path, index, options, |text, _, line_number| {
+            if !self.is_match(text) {
+                return true;
+            }
+            let visible_start = line_number.saturating_sub(context).max(start_line);
+            if visible_start > next_unfolded {
+                folds.push((next_unfolded, visible_start - 1));
+            }
+            next_unfolded =
+                next_unfolded.max(line_number.saturating_add(context).saturating_add(1));
+            true
+        })?;
+
+        if next_unfolded <= end_line {
+            folds.push((next_unfolded, end_line));
+        }
+
+        Ok(folds)
+    }
+}
+
+/// 定位到 start_line (或其所在的索引块)，返回读取位置之前的行数
+///
+/// 偏移计算与 FileIndex::seek_position 一致，使用索引自身的 index_interval
+fn seek_to_line<R: Seek>(
+    reader: &mut R,
+    index: &FileIndex,
+    start_line: u64,
+) -> std::io::Result<u64> {
+    let (offset, lines_before) = index.seek_position(start_line);
+    reader.seek(SeekFrom::Start(offset))?;
+    Ok(lines_before)
+}
+
+/// 逐行宽松解码: 无效 UTF-8 替换为 U+FFFD 而不是中止扫描，与 LineReader 显示的内容一致
+fn lossy_lines<R: BufRead>(
+    reader: R,
+    encoding: Encoding,
+) -> impl Iterator<Item = std::io::Result<String>> {
+    decode_lines_lossy(reader, encoding).map(|line| line.map(|(line, _)| line))
+}
+
+/// 按排序方式重排搜索结果 (输入为行号升序)
+fn sort_results(mut results: Vec<SearchResult>, sort: ResultSort) -> Vec<SearchResult> {
+    match sort {
+        ResultSort::LineAsc => results,
+        ResultSort::LineDesc => {
+            // 稳定排序，同一行内的多个匹配保持从左到右
+            results.sort_by_key(|r| Reverse(r.line_number));
+            results
+        }
+        ResultSort::LevelThenLine => {
+            results.sort_by_key(|r| (Reverse(r.level.severity()), r.line_number));
+            results
+        }
+    }
+}
+
+/// 收集一次匹配中各命名捕获组的内容
+fn named_captures(regex: &Regex, caps: &regex::Captures) -> NamedCaptures {
+    regex
+        .capture_names()
+        .flatten()
+        .filter_map(|name| Some((name.to_string(), caps.name(name)?.as_str().to_string())))
+        .collect()
+}
+
+/// 字节位置 pos 是否落在某个词元内部 (不含词元的首尾)
+fn inside_token(tokens: &[Token], pos: usize) -> bool {
+    let preceding = tokens.partition_point(|token| token.start < pos);
+    preceding > 0 && tokens[preceding - 1].end > pos
+}
+
+/// 校验正则表达式，语法错误时给出出错位置
+pub fn validate_regex(pattern: &str) -> Result<(), RegexErrorInfo> {
+    match Regex::new(pattern) {
+        Ok(_) => Ok(()),
+        Err(regex::Error::Syntax(text)) => Err(RegexErrorInfo {
+            message: text
+                .lines()
+                .find_map(|line| line.strip_prefix("error: "))
+                .unwrap_or(&text)
+                .to_string(),
+            offset: syntax_error_offset(pattern, &text),
+        }),
+        Err(e) => Err(RegexErrorInfo {
+            message: e.to_string(),
+            offset: None,
+        }),
+    }
+}
+
+/// 从语法错误信息中的 ^ 标记推算字节偏移
+///
+/// 错误信息形如 "regex parse error:\n    a(b\n     ^\nerror: unclosed group"，
+/// 标记行缩进 4 列，列号按字符计。多行模式带行号前缀，不做解析
+fn syntax_error_offset(pattern: &str, text: &str) -> Option<usize> {
+    if pattern.contains('\n') {
+        return None;
+    }
+
+    let marker = text
+        .lines()
+        .find(|line| line.contains('^') && line.chars().all(|c| c == ' ' || c == '^'))?;
+    let column = marker.find('^')?.checked_sub(4)?;
+
+    Some(
+        pattern
+            .char_indices()
+            .nth(column)
+            .map_or(pattern.len(), |(offset, _)| offset),
+    )
+}
+
+/// 便捷搜索函数
+pub fn search<P: AsRef<Path>>(
+    path: P,
+    index: &FileIndex,
+    options: &SearchOptions,
+) -> Result<SearchResults, Box<dyn std::error::Error>> {
+    let engine = SearchEngine::new(options)?;
+    Ok(engine.search_in_file(path, index, options)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{CategoryLines, LogLevel};
+    use crate::streaming::{index_file, FileIndexer};
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn sorted_lines(sort: ResultSort) -> Vec<u64> {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "LogInit: Display: load ok").unwrap();
+        writeln!(temp_file, "LogNet: Error: load failed").unwrap();
+        writeln!(temp_file, "LogCore: Warning: load slow").unwrap();
+        writeln!(temp_file, "LogNet: Error: load failed again").unwrap();
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: "load".to_string(),
+            use_regex: false,
+            sort,
+            ..Default::default()
+        };
+
+        search(temp_file.path(), &index, &options)
+            .unwrap()
+            .results
+            .iter()
+            .map(|r| r.line_number)
+            .collect()
+    }
+
+    #[test]
+    fn test_fold_ranges_single_match() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=100 {
+            if i == 50 {
+                writeln!(temp_file, "LogNet: Error: Connection lost").unwrap();
+            } else {
+                writeln!(temp_file, "LogInit: Display: Line {}", i).unwrap();
+            }
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: "Connection lost".to_string(),
+            use_regex: false,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let folds = engine
+            .fold_ranges(temp_file.path(), &index, &options, 2)
+            .unwrap();
+        assert_eq!(folds, vec![(1, 47), (53, 100)]);
+
+        // 子范围: 折叠区间不超出请求的范围
+        let sub = SearchOptions {
+            start_line: Some(40),
+            end_line: Some(60),
+            ..options.clone()
+        };
+        let folds = engine
+            .fold_ranges(temp_file.path(), &index, &sub, 2)
+            .unwrap();
+        assert_eq!(folds, vec![(40, 47), (53, 60)]);
+
+        let no_match = SearchOptions {
+            start_line: Some(60),
+            end_line: Some(70),
+            ..options
+        };
+        let folds = engine
+            .fold_ranges(temp_file.path(), &index, &no_match, 2)
+            .unwrap();
+        assert_eq!(folds, vec![(60, 70)]);
+    }
+
+    #[test]
+    fn test_fold_ranges_merge_adjacent_context() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=20 {
+            let level = if i == 5 || i == 8 { "Error" } else { "Display" };
+            writeln!(temp_file, "LogInit: {}: Line {}", level, i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: "Error".to_string(),
+            use_regex: false,
+            case_insensitive: false,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let folds = engine
+            .fold_ranges(temp_file.path(), &index, &options, 2)
+            .unwrap();
+        assert_eq!(folds, vec![(1, 2), (11, 20)]);
+    }
+
+    #[test]
+    fn test_search_with_tiny_and_large_buffers() {
+        use crate::streaming::{set_io_buffer_size, DEFAULT_IO_BUFFER_SIZE, MIN_IO_BUFFER_SIZE};
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=3000 {
+            let level = if i % 7 == 0 { "Error" } else { "Display" };
+            writeln!(temp_file, "LogInit: {}: Line {}", level, i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: "Error".to_string(),
+            use_regex: false,
+            case_insensitive: false,
+            start_line: Some(500),
+            ..Default::default()
+        };
+        let lines = |results: Vec<SearchResult>| -> Vec<u64> {
+            results.into_iter().map(|r| r.line_number).collect()
+        };
+
+        assert_eq!(set_io_buffer_size(1), MIN_IO_BUFFER_SIZE);
+        let tiny = lines(search(temp_file.path(), &index, &options).unwrap().results);
+
+        set_io_buffer_size(4 * 1024 * 1024);
+        let large = lines(search(temp_file.path(), &index, &options).unwrap().results);
+
+        set_io_buffer_size(DEFAULT_IO_BUFFER_SIZE);
+
+        let expected: Vec<u64> = (500..=3000).filter(|i| i % 7 == 0).collect();
+        assert_eq!(tiny, expected);
+        assert_eq!(large, expected);
+    }
+
+    #[test]
+    fn test_search_custom_interval() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=3000 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i).unwrap();
+        }
+
+        let mut index = index_file(temp_file.path()).unwrap();
+        assert_ne!(index.index_interval, FileIndex::INDEX_INTERVAL);
+        index.dense_offsets = false;
+        index.dense_line_offsets.clear();
+
+        let options = SearchOptions {
+            pattern: "Line 12".to_string(),
+            use_regex: false,
+            case_insensitive: false,
+            start_line: Some(1230),
+            end_line: Some(1250),
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let results = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap()
+            .results;
+        let lines: Vec<u64> = results.iter().map(|r| r.line_number).collect();
+        assert_eq!(lines, (1230..=1250).collect::<Vec<_>>());
+
+        // 翻页同样遵循行范围，这里不限制范围
+        let whole_file = SearchOptions {
+            start_line: None,
+            end_line: None,
+            ..options
+        };
+        let page = engine
+            .search_prev_page(temp_file.path(), &index, 1202, 3, &whole_file)
+            .unwrap();
+        let lines: Vec<u64> = page.iter().map(|r| r.line_number).collect();
+        assert_eq!(lines, vec![1201, 1200, 129]);
+    }
+
+    #[test]
+    fn test_mapped_scan_matches_buffered() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=3000 {
+            match i % 5 {
+                0 => write!(temp_file, "LogNet: Error: 连接失败 Packet {}\r\n", i).unwrap(),
+                1 => writeln!(temp_file, "  continuation Packet {}", i).unwrap(),
+                2 => writeln!(temp_file).unwrap(),
+                _ => writeln!(
+                    temp_file,
+                    "[2026.02.14-03.33.56:070][{:3}]LogInit: Display: Packet {} id={}",
+                    i % 1000,
+                    i,
+                    i * 7
+                )
+                .unwrap(),
+            }
+        }
+        // 最后一行没有换行
+        write!(temp_file, "LogNet: Warning: Packet tail\r").unwrap();
+        temp_file.flush().unwrap();
+
+        let mut index = index_file(temp_file.path()).unwrap();
+        index.dense_offsets = false;
+        index.dense_line_offsets.clear();
+        let data = map_file(temp_file.path()).unwrap().unwrap();
+
+        let cases = [
+            SearchOptions {
+                pattern: "Packet".to_string(),
+                use_regex: false,
+                case_insensitive: false,
+                ..Default::default()
+            },
+            SearchOptions {
+                pattern: r"id=(?P<id>\d+)".to_string(),
+                use_regex: true,
+                start_line: Some(1234),
+                end_line: Some(2345),
+                ..Default::default()
+            },
+            SearchOptions {
+                pattern: "packet".to_string(),
+                case_insensitive: true,
+                level_filter: vec![LogLevel::Error, LogLevel::Warning],
+                ..Default::default()
+            },
+            SearchOptions {
+                pattern: r"\r$|^$".to_string(),
+                use_regex: true,
+                ..Default::default()
+            },
+        ];
+
+        for options in &cases {
+            let engine = SearchEngine::new(options).unwrap();
+            let start_line = options.start_line.unwrap_or(1);
+            let end_line = options.end_line.unwrap_or(index.total_lines);
+
+            let mut mapped = Vec::new();
+            engine
+                .scan_mapped(&data, &index, start_line, end_line, |text, line, n| {
+                    mapped.extend(engine.search_in_text(text, line, n));
+                    true
+                })
+                .unwrap();
+
+            let mut decoded = Vec::new();
+            let file = File::open(temp_file.path()).unwrap();
+            engine
+                .scan_buffered(
+                    file,
+                    &index,
+                    options,
+                    start_line,
+                    end_line,
+                    |text, line, n| {
+                        decoded.extend(engine.search_in_text(text, line, n));
+                        true
+                    },
+                )
+                .unwrap();
+
+            assert!(!mapped.is_empty(), "no matches for {}", options.pattern);
+            assert_eq!(
+                format!("{:?}", mapped),
+                format!("{:?}", decoded),
+                "pattern {}",
+                options.pattern
+            );
+        }
+    }
+
+    #[test]
+    fn test_search_invalid_utf8() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "LogNet: Error: Packet 1").unwrap();
+        temp_file
+            .write_all(b"LogNet: Error: Packet \xFF\xFE 2\n")
+            .unwrap();
+        writeln!(temp_file, "LogNet: Error: Packet 3").unwrap();
+        temp_file.flush().unwrap();
+
+        let index = index_file(temp_file.path()).unwrap();
+        // 内存映射路径、逐行解码路径 (合并续行搜索) 和计数都不因坏字节中止
+        for search_merged in [false, true] {
+            let options = SearchOptions {
+                pattern: "Packet".to_string(),
+                use_regex: false,
+                case_insensitive: false,
+                search_merged,
+                ..Default::default()
+            };
+            let engine = SearchEngine::new(&options).unwrap();
+
+            let results = engine
+                .search_in_file(temp_file.path(), &index, &options)
+                .unwrap()
+                .results;
+            let lines: Vec<u64> = results.iter().map(|r| r.line_number).collect();
+            assert_eq!(lines, vec![1, 2, 3]);
+            assert_eq!(
+                engine
+                    .count_matches(temp_file.path(), &index, &options)
+                    .unwrap(),
+                3
+            );
+        }
+
+        let options = SearchOptions {
+            pattern: "\u{FFFD}\u{FFFD} 2".to_string(),
+            use_regex: false,
+            case_insensitive: false,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+        let results = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap()
+            .results;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line_number, 2);
+    }
+
+    #[test]
+    fn test_search_crlf_line_endings() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=20 {
+            write!(temp_file, "LogInit: Display: Line {}\r\n", i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: r"Line 1\d$".to_string(),
+            use_regex: true,
+            start_line: Some(12),
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let results = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap()
+            .results;
+        assert_eq!(results.len(), 8);
+        assert_eq!(results[0].line_number, 12);
+        assert_eq!(results[0].matched_text, "Line 12");
+        assert_eq!(results[0].end, "LogInit: Display: Line 12".len());
+    }
+
+    #[test]
+    fn test_char_offsets_after_cjk() {
+        let options = SearchOptions {
+            pattern: "超时".to_string(),
+            use_regex: false,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let line = "LogNet: Warning: 连接超时，重试后再次超时";
+        let results = engine.search_in_string(line, 1);
+        assert_eq!(results.len(), 2);
+
+        let prefix = "LogNet: Warning: 连接";
+        assert_eq!(results[0].start, prefix.len());
+        assert_eq!(results[0].char_start, prefix.chars().count());
+        assert_eq!(results[0].char_end, results[0].char_start + 2);
+        assert_eq!(results[1].char_start, line.chars().count() - 2);
+        assert_eq!(results[1].char_end, line.chars().count());
+        assert_eq!(&line[results[1].start..results[1].end], "超时");
+    }
+
+    #[test]
+    fn test_named_captures() {
+        let options = SearchOptions {
+            pattern: r"Actor (?P<name>\w+) spawned(?: at (?P<pos>\S+))?".to_string(),
+            use_regex: true,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let results = engine.search_in_string("LogSpawn: Display: Actor BP_Enemy_C_3 spawned", 7);
+        assert_eq!(results.len(), 1);
+        let captures = results[0].captures.as_ref().unwrap();
+        assert_eq!(
+            captures.get("name").map(String::as_str),
+            Some("BP_Enemy_C_3")
+        );
+        // 未参与匹配的可选组不出现
+        assert!(!captures.contains_key("pos"));
+
+        // 没有命名组时不收集
+        let options = SearchOptions {
+            pattern: r"Actor (\w+) spawned".to_string(),
+            use_regex: true,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+        let results = engine.search_in_string("LogSpawn: Display: Actor BP_Enemy_C_3 spawned", 7);
+        assert_eq!(results[0].captures, None);
+    }
+
+    #[test]
+    fn test_search_result_limit() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=200 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let mut options = SearchOptions {
+            pattern: "Line".to_string(),
+            use_regex: false,
+            max_results: Some(50),
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let found = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap();
+        assert!(found.truncated);
+        assert_eq!(found.results.len(), 50);
+        assert_eq!(found.results.last().map(|r| r.line_number), Some(50));
+
+        // 结果数恰好等于上限时不算截断
+        options.max_results = Some(200);
+        let found = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap();
+        assert!(!found.truncated);
+        assert_eq!(found.results.len(), 200);
+    }
+
+    #[test]
+    fn test_search_in_lines_reads_only_category() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=20_000 {
+            if i % 2000 == 0 {
+                writeln!(temp_file, "LogRare: Error: Disk full {}", i).unwrap();
+            } else {
+                writeln!(temp_file, "LogInit: Display: Disk ok {}", i).unwrap();
+            }
+        }
+
+        let mut index = FileIndexer::open(temp_file.path())
+            .unwrap()
+            .build_index_with_category_lines(100_000);
+        let category_lines = CategoryLines::take_from(&mut index).unwrap();
+        let lines = category_lines.complete("LogRare").unwrap().to_vec();
+        assert_eq!(lines.len(), 10);
+
+        let options = SearchOptions {
+            pattern: "Disk".to_string(),
+            use_regex: false,
+            category_filter: vec!["LogRare".to_string()],
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+        let full = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap();
+        let spans = |results: &[SearchResult]| -> Vec<(u64, usize, usize)> {
+            results
+                .iter()
+                .map(|r| (r.line_number, r.start, r.end))
+                .collect()
+        };
+        let full_lines: Vec<u64> = full.results.iter().map(|r| r.line_number).collect();
+        assert_eq!(full_lines, lines);
+
+        // 完整行偏移: 每个目标行只读一行 (全文件扫描读 20000 行)
+        let (found, lines_read) = engine
+            .search_in_lines(temp_file.path(), &index, &lines, &options)
+            .unwrap();
+        assert_eq!(spans(&found.results), spans(&full.results));
+        assert_eq!(lines_read, 10);
+
+        // 稀疏索引: 每个目标行至多从前一个索引点读 index_interval 行
+        index.dense_offsets = false;
+        index.dense_line_offsets.clear();
+        let (found, lines_read) = engine
+            .search_in_lines(temp_file.path(), &index, &lines, &options)
+            .unwrap();
+        assert_eq!(spans(&found.results), spans(&full.results));
+        assert!(lines_read <= 10 * index.index_interval);
+        assert!(lines_read * 10 < index.total_lines);
+
+        // 行范围同样生效
+        let ranged = SearchOptions {
+            start_line: Some(5000),
+            end_line: Some(12_000),
+            ..options.clone()
+        };
+        let (found, _) = engine
+            .search_in_lines(temp_file.path(), &index, &lines, &ranged)
+            .unwrap();
+        let found_lines: Vec<u64> = found.results.iter().map(|r| r.line_number).collect();
+        assert_eq!(found_lines, vec![6000, 8000, 10_000, 12_000]);
+    }
+
+    #[test]
+    fn test_search_merged_continuations() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "LogInit: Display: Starting").unwrap();
+        writeln!(temp_file, "LogWindows: Error: === Critical error: ===").unwrap();
+        for frame in 0..5 {
+            writeln!(temp_file, "    UnrealEditor-Core.dll!Frame{}()", frame).unwrap();
+        }
+        writeln!(temp_file, "LogExit: Display: Exiting").unwrap();
+
+        let index = index_file(temp_file.path()).unwrap();
+        let mut options = SearchOptions {
+            pattern: r"Critical error.*Frame3".to_string(),
+            ..Default::default()
+        };
+
+        // 逐行匹配时模式跨越了多行，找不到
+        let engine = SearchEngine::new(&options).unwrap();
+        let results = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap();
+        assert!(results.results.is_empty());
+
+        options.search_merged = true;
+        let engine = SearchEngine::new(&options).unwrap();
+        let results = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap();
+        assert_eq!(results.results.len(), 1);
+
+        let result = &results.results[0];
+        assert_eq!(result.line_number, 2);
+        assert_eq!(result.level, LogLevel::Error);
+        assert_eq!(result.category.as_deref(), Some("LogWindows"));
+        assert!(result.matched_text.starts_with("Critical error: ===\n"));
+        assert!(result.matched_text.ends_with("Frame3"));
+
+        // 只作用于续行，不会跨越到下一条日志
+        options.pattern = r"Frame4.*Exiting".to_string();
+        let engine = SearchEngine::new(&options).unwrap();
+        let results = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap();
+        assert!(results.results.is_empty());
+    }
+
+    #[test]
+    fn test_merged_count_and_paging_match_search() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 0..3 {
+            writeln!(temp_file, "LogWindows: Error: Crash {}", i).unwrap();
+            writeln!(temp_file, "    UnrealEditor-Core.dll!Frame()").unwrap();
+            writeln!(temp_file, "LogTemp: Display: Tick {}", i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: r"Crash \d\n\s+Unreal".to_string(),
+            search_merged: true,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+        let path = temp_file.path();
+
+        let found: Vec<u64> = engine
+            .search_in_file(path, &index, &options)
+            .unwrap()
+            .results
+            .iter()
+            .map(|r| r.line_number)
+            .collect();
+        assert_eq!(found, vec![1, 4, 7]);
+
+        assert_eq!(engine.count_matches(path, &index, &options).unwrap(), 3);
+        let next: Vec<u64> = engine
+            .search_next_page(path, &index, 1, 10, &options)
+            .unwrap()
+            .iter()
+            .map(|r| r.line_number)
+            .collect();
+        assert_eq!(next, found);
+        let prev: Vec<u64> = engine
+            .search_prev_page(path, &index, 10, 10, &options)
+            .unwrap()
+            .iter()
+            .map(|r| r.line_number)
+            .collect();
+        assert_eq!(prev, vec![7, 4, 1]);
+        assert_eq!(
+            engine.fold_ranges(path, &index, &options, 0).unwrap(),
+            vec![(2, 3), (5, 6), (8, 9)]
+        );
+    }
+
+    #[test]
+    fn test_find_next_match() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=3000 {
+            let level = if i % 1000 == 0 { "Error" } else { "Display" };
+            writeln!(temp_file, "LogTemp: {}: Line {}", level, i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: ": Error:".to_string(),
+            use_regex: false,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+        let next = |from_line| {
+            engine
+                .find_next_match(temp_file.path(), &index, from_line, &options)
+                .unwrap()
+        };
+
+        assert_eq!(next(0), Some(1000));
+        // 严格在 from_line 之后
+        assert_eq!(next(1000), Some(2000));
+        assert_eq!(next(2999), Some(3000));
+        assert_eq!(next(3000), None);
+
+        let bounded = SearchOptions {
+            end_line: Some(2500),
+            ..options.clone()
+        };
+        assert_eq!(
+            engine
+                .find_next_match(temp_file.path(), &index, 1000, &bounded)
+                .unwrap(),
+            Some(2000)
+        );
+        assert_eq!(
+            engine
+                .find_next_match(temp_file.path(), &index, 2000, &bounded)
+                .unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_find_with_wrap() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=3000 {
+            let level = if i == 1200 || i == 2400 {
+                "Error"
+            } else {
+                "Display"
+            };
+            writeln!(temp_file, "LogTemp: {}: Line {}", level, i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: ": Error:".to_string(),
+            use_regex: false,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+        let path = temp_file.path();
+        let next = |from, wrap| {
+            engine
+                .find_next_wrapping(path, &index, from, &options, wrap)
+                .unwrap()
+        };
+        let prev = |from, wrap| {
+            engine
+                .find_prev_wrapping(path, &index, from, &options, wrap)
+                .unwrap()
+        };
+
+        assert_eq!(next(1200, true), NextMatch::Found(2400));
+        assert_eq!(next(2400, false), NextMatch::None);
+        assert_eq!(next(2400, true), NextMatch::Wrapped(1200));
+
+        assert_eq!(prev(2400, true), NextMatch::Found(1200));
+        assert_eq!(prev(1200, false), NextMatch::None);
+        assert_eq!(prev(1200, true), NextMatch::Wrapped(2400));
+        assert_eq!(prev(3001, false), NextMatch::Found(2400));
+
+        // 整个文件没有匹配时回绕一次后结束
+        let options = SearchOptions {
+            pattern: "no such text".to_string(),
+            ..options
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+        assert_eq!(
+            engine
+                .find_next_wrapping(path, &index, 1500, &options, true)
+                .unwrap(),
+            NextMatch::None
+        );
+        assert_eq!(
+            engine
+                .find_prev_wrapping(path, &index, 1500, &options, true)
+                .unwrap(),
+            NextMatch::None
+        );
+    }
+
+    #[test]
+    fn test_search_in_file_batched() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=120 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let mut options = SearchOptions {
+            pattern: "Line".to_string(),
+            use_regex: false,
+            sort: ResultSort::LineDesc,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let mut batches: Vec<Vec<SearchResult>> = Vec::new();
+        let complete = engine
+            .search_in_file_batched(temp_file.path(), &index, &options, 50, |batch| {
+                batches.push(batch)
+            })
+            .unwrap();
+        assert_eq!(
+            (complete.total, complete.truncated, complete.cancelled),
+            (120, false, false)
+        );
+        let sizes: Vec<usize> = batches.iter().map(Vec::len).collect();
+        assert_eq!(sizes, vec![50, 50, 20]);
+        // 忽略排序选项，始终按行号升序
+        let lines: Vec<u64> = batches.concat().iter().map(|r| r.line_number).collect();
+        assert_eq!(lines, (1..=120).collect::<Vec<u64>>());
+
+        options.max_results = Some(70);
+        let mut received = 0;
+        let complete = engine
+            .search_in_file_batched(temp_file.path(), &index, &options, 50, |batch| {
+                received += batch.len()
+            })
+            .unwrap();
+        assert_eq!((complete.total, complete.truncated), (70, true));
+        assert_eq!(received, 70);
+    }
+
+    /// ASCII 与 Unicode 大小写折叠的耗时对比 (较慢，需手动运行):
+    /// `cargo test --release -- --ignored bench_case_folding --nocapture`
+    #[test]
+    #[ignore]
+    fn bench_case_folding() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        {
+            let mut writer = std::io::BufWriter::new(temp_file.as_file_mut());
+            for i in 0..1_000_000u64 {
+                writeln!(
+                    writer,
+                    "[2026.02.14-03.33.56:070][{:3}]LogStreaming: Display: Loaded package /Game/Maps/Level_{} in {} ms",
+                    i % 1000,
+                    i,
+                    i % 97
+                )
+                .unwrap();
+            }
+        }
+        let index = index_file(temp_file.path()).unwrap();
+
+        for pattern in ["no such text here", "loaded package", "level_1"] {
+            for ascii in [false, true] {
+                let options = SearchOptions {
+                    pattern: pattern.to_string(),
+                    use_regex: false,
+                    case_insensitive: true,
+                    ascii_case_insensitive: ascii,
+                    ..Default::default()
+                };
+                let engine = SearchEngine::new(&options).unwrap();
+                let best = (0..5)
+                    .map(|_| {
+                        let started = std::time::Instant::now();
+                        engine
+                            .count_matches(temp_file.path(), &index, &options)
+                            .unwrap();
+                        started.elapsed()
+                    })
+                    .min()
+                    .unwrap();
+                println!("{:<20} ascii={:<5} {:?}", pattern, ascii, best);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ascii_case_insensitive() {
+        let options = |pattern: &str, ascii: bool| SearchOptions {
+            pattern: pattern.to_string(),
+            use_regex: false,
+            case_insensitive: true,
+            ascii_case_insensitive: ascii,
+            ..Default::default()
+        };
+
+        let ascii = SearchEngine::new(&options("i", true)).unwrap();
+        assert!(ascii.is_match("LogInit: Display: INIT"));
+
+        // Unicode 折叠把开尔文符号视为 k、长 s (ſ) 视为 s，ASCII 折叠不会
+        for (pattern, text) in [("k", "Temperature 300\u{212A}"), ("s", "\u{17F}")] {
+            assert!(SearchEngine::new(&options(pattern, false))
+                .unwrap()
+                .is_match(text));
+            assert!(!SearchEngine::new(&options(pattern, true))
+                .unwrap()
+                .is_match(text));
+        }
+
+        // 非 ASCII 的字面量仍按原样匹配
+        let cjk = SearchEngine::new(&options("超时", true)).unwrap();
+        assert_eq!(cjk.search_in_string("连接超时", 1)[0].char_start, 2);
+    }
+
+    #[test]
+    fn test_search_in_files() {
+        let mut first = NamedTempFile::new().unwrap();
+        writeln!(first, "LogNet: Error: Timeout A").unwrap();
+        writeln!(first, "LogInit: Display: Ok").unwrap();
+        writeln!(first, "LogNet: Error: Timeout B").unwrap();
+        let mut second = NamedTempFile::new().unwrap();
+        writeln!(second, "LogNet: Error: Timeout C").unwrap();
+        writeln!(second, "LogNet: Error: Timeout D").unwrap();
+
+        let files = [
+            (first.path(), index_file(first.path()).unwrap()),
+            (second.path(), index_file(second.path()).unwrap()),
+        ];
+        let mut options = SearchOptions {
+            pattern: r"Timeout (\w)".to_string(),
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let found = engine.search_in_files(&files, &options).unwrap();
+        let summary: Vec<(bool, u64, &str)> = found
+            .results
+            .iter()
+            .map(|r| {
+                let source = r.source_file.as_deref().unwrap();
+                let from_first = source == first.path().to_string_lossy();
+                (from_first, r.line_number, r.matched_text.as_str())
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (true, 1, "Timeout A"),
+                (true, 3, "Timeout B"),
+                (false, 1, "Timeout C"),
+                (false, 2, "Timeout D"),
+            ]
+        );
+        assert!(!found.truncated);
+
+        // 上限由所有文件共享
+        options.max_results = Some(3);
+        let found = engine.search_in_files(&files, &options).unwrap();
+        assert_eq!(found.results.len(), 3);
+        assert_eq!(found.results[2].matched_text, "Timeout C");
+        assert!(found.truncated);
+
+        // 恰好在第一个文件用完上限时，仍能发现后面的文件还有结果
+        options.max_results = Some(2);
+        let found = engine.search_in_files(&files, &options).unwrap();
+        assert_eq!(found.results.len(), 2);
+        assert!(found.truncated);
+    }
+
+    #[test]
+    fn test_cancel_search() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=20000 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: "Line".to_string(),
+            use_regex: false,
+            max_results: Some(usize::MAX),
+            ..Default::default()
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        let engine = SearchEngine::new(&options)
+            .unwrap()
+            .with_cancel(cancel.clone());
+
+        // 扫描过程中由另一个线程置位
+        let found = std::thread::scope(|scope| {
+            let search = scope.spawn(|| engine.search_in_file(temp_file.path(), &index, &options));
+            cancel.store(true, Ordering::Relaxed);
+            search.join().unwrap().unwrap()
+        });
+        assert!(found.cancelled);
+        assert!(found.results.len() < 20000);
+        assert!(engine
+            .search_next_page(temp_file.path(), &index, 1, 100, &options)
+            .unwrap()
+            .is_empty());
+
+        cancel.store(false, Ordering::Relaxed);
+        let found = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap();
+        assert!(!found.cancelled);
+        assert_eq!(found.results.len(), 20000);
+    }
+
+    #[test]
+    fn test_search_extreme_lines() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=1500 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: "Line 15".to_string(),
+            use_regex: false,
+            start_line: Some(0),
+            end_line: Some(u64::MAX),
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let results = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap()
+            .results;
+        assert_eq!(results.first().map(|r| r.line_number), Some(15));
+
+        let page = engine
+            .search_next_page(temp_file.path(), &index, 0, 1, &options)
+            .unwrap();
+        assert_eq!(page[0].line_number, 15);
+
+        let page = engine
+            .search_next_page(temp_file.path(), &index, u64::MAX, 10, &options)
+            .unwrap();
+        assert!(page.is_empty());
+
+        let beyond = SearchOptions {
+            start_line: Some(u64::MAX),
+            ..options.clone()
+        };
+        assert!(engine
+            .search_in_file(temp_file.path(), &index, &beyond)
+            .unwrap()
+            .results
+            .is_empty());
+        // 范围在文件之外，没有可折叠的行
+        assert!(engine
+            .fold_ranges(temp_file.path(), &index, &beyond, 2)
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            engine
+                .fold_ranges(temp_file.path(), &index, &options, 2)
+                .unwrap(),
+            vec![(1, 12), (18, 147), (162, 1497)]
+        );
+    }
+
+    #[test]
+    fn test_search_prev_page() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=2500 {
+            if i % 400 == 0 {
+                writeln!(temp_file, "LogNet: Error: timeout {} timeout", i).unwrap();
+            } else {
+                writeln!(temp_file, "LogInit: Display: Line {}", i).unwrap();
+            }
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: "timeout".to_string(),
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        // 跨越多个索引块，离 from_line 最近的在前，同一行内从右到左
+        let page = engine
+            .search_prev_page(temp_file.path(), &index, 2000, 5, &options)
+            .unwrap();
+        let lines: Vec<u64> = page.iter().map(|r| r.line_number).collect();
+        assert_eq!(lines, vec![1600, 1600, 1200, 1200, 800]);
+        assert!(page[0].start > page[1].start);
+
+        // from_line 本身不计入
+        let page = engine
+            .search_prev_page(temp_file.path(), &index, 400, 10, &options)
+            .unwrap();
+        assert!(page.is_empty());
+
+        let page = engine
+            .search_prev_page(temp_file.path(), &index, u64::MAX, 1, &options)
+            .unwrap();
+        assert_eq!(page[0].line_number, 2400);
+    }
+
+    #[test]
+    fn test_matching_lines() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "LogNet: Error: retry retry retry").unwrap();
+        writeln!(temp_file, "LogNet: Display: Connected").unwrap();
+        writeln!(temp_file, "LogNet: Warning: retry later").unwrap();
+        writeln!(temp_file, "LogInit: Display: Retry").unwrap();
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: "retry".to_string(),
+            use_regex: false,
+            case_insensitive: false,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        // 第 1 行有三个匹配，只出现一次
+        let lines = engine
+            .matching_lines(temp_file.path(), &index, &options)
+            .unwrap();
+        assert_eq!(lines, vec![1, 3]);
+
+        // 不受结果数上限影响，遵循行范围
+        let options = SearchOptions {
+            case_insensitive: true,
+            max_results: Some(1),
+            start_line: Some(2),
+            ..options
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+        let lines = engine
+            .matching_lines(temp_file.path(), &index, &options)
+            .unwrap();
+        assert_eq!(lines, vec![3, 4]);
+    }
+
+    #[test]
+    fn test_count_matches_consistent_with_search() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=1200 {
+            if i % 3 == 0 {
+                writeln!(temp_file, "LogNet: Error: retry retry {}", i).unwrap();
+            } else {
+                writeln!(temp_file, "LogNet: Display: Retry {}", i).unwrap();
+            }
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        for case_insensitive in [false, true] {
+            let options = SearchOptions {
+                pattern: "retry".to_string(),
+                use_regex: false,
+                case_insensitive,
+                start_line: Some(10),
+                end_line: Some(1100),
+                ..Default::default()
+            };
+            let engine = SearchEngine::new(&options).unwrap();
+
+            let results = engine
+                .search_in_file(temp_file.path(), &index, &options)
+                .unwrap()
+                .results;
+            let count = engine
+                .count_matches(temp_file.path(), &index, &options)
+                .unwrap();
+            assert_eq!(count, results.len() as u64);
+        }
+
+        // 每行两个匹配，第 12..=1098 行中每三行一个
+        let options = SearchOptions {
+            pattern: "retry".to_string(),
+            use_regex: false,
+            case_insensitive: false,
+            start_line: Some(10),
+            end_line: Some(1100),
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+        assert_eq!(
+            engine
+                .count_matches(temp_file.path(), &index, &options)
+                .unwrap(),
+            2 * 363
+        );
+    }
+
+    #[test]
+    fn test_search_with_context() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "LogNet: Error: failed failed").unwrap();
+        for i in 2..=9 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i).unwrap();
+        }
+        writeln!(temp_file, "LogNet: Error: failed at end").unwrap();
+
+        let index = index_file(temp_file.path()).unwrap();
+        let reader = Mutex::new(LineReader::from_index(temp_file.path(), index.clone()).unwrap());
+        let options = SearchOptions {
+            pattern: "failed".to_string(),
+            context_lines: 3,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let hits = engine
+            .search_with_context(temp_file.path(), &index, &options, &reader)
+            .unwrap();
+        assert_eq!(hits.len(), 3);
+
+        // 文件开头: 没有前文，同一行的两个命中上下文相同
+        let lines = |entries: &[crate::parser::LogEntry]| -> Vec<u64> {
+            entries.iter().map(|e| e.line_number).collect()
+        };
+        assert!(hits[0].before.is_empty());
+        assert_eq!(lines(&hits[0].after), vec![2, 3, 4]);
+        assert_eq!(lines(&hits[1].after), vec![2, 3, 4]);
+
+        // 文件末尾: 没有后文
+        assert_eq!(lines(&hits[2].before), vec![7, 8, 9]);
+        assert!(hits[2].after.is_empty());
+
+        // 过大的上下文被截断在文件边界内
+        let huge = SearchOptions {
+            context_lines: u64::MAX,
+            ..options
+        };
+        let hits = engine
+            .search_with_context(temp_file.path(), &index, &huge, &reader)
+            .unwrap();
+        assert_eq!(hits[2].before.len(), 9);
+        assert_eq!(hits[0].after.len(), 9);
+    }
+
+    #[test]
+    fn test_search_with_context_total_limit() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        for i in 1..=3000 {
+            writeln!(temp_file, "LogNet: Error: failed {}", i).unwrap();
+        }
+
+        let index = index_file(temp_file.path()).unwrap();
+        let reader = Mutex::new(LineReader::from_index(temp_file.path(), index.clone()).unwrap());
+        let options = SearchOptions {
+            pattern: "failed".to_string(),
+            context_lines: SearchOptions::MAX_CONTEXT_LINES,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options).unwrap();
+
+        let hits = engine
+            .search_with_context(temp_file.path(), &index, &options, &reader)
+            .unwrap();
+        let total: usize = hits.iter().map(|h| h.before.len() + h.after.len()).sum();
+        assert!(total <= SearchOptions::MAX_CONTEXT_TOTAL_LINES);
+        assert!(hits.len() < 3000);
+        // 靠前的命中完整返回
+        assert_eq!(hits[0].result.line_number, 1);
+        assert_eq!(hits[200].before.len(), 100);
+    }
+
+    #[test]
+    fn test_whole_word_literal() {
+        for case_insensitive in [false, true] {
+            let engine = SearchEngine::new(&SearchOptions {
+                pattern: "Init".to_string(),
+                use_regex: false,
+                case_insensitive,
+                whole_word: true,
+                ..Default::default()
+            })
+            .unwrap();
+
+            // "LogInit" 中的 Init 前面是单词字符，不算全词
+            assert!(!engine.is_match("LogInit: Display: Initialization done"));
+            assert!(engine.is_match("LogCore: Display: Init complete"));
+            assert!(engine.is_match("LogCore: Display: step=Init."));
+
+            let results = engine.search_in_string("Init Initialize (Init)", 1);
+            let starts: Vec<usize> = results.iter().map(|r| r.start).collect();
+            assert_eq!(starts, vec![0, 17]);
+            assert_eq!(engine.count_in_string("Init Initialize (Init)"), 2);
+
+            // 词边界按 Unicode 分词判断
+            assert!(!engine.is_match("ÉInit"));
+            assert!(engine.is_match("к Init"));
+        }
+
+        // 区分大小写时仍走子串查找
+        let options = |pattern: &str| SearchOptions {
+            pattern: pattern.to_string(),
+            use_regex: false,
+            case_insensitive: false,
+            whole_word: true,
+            ..Default::default()
+        };
+        let engine = SearchEngine::new(&options("a a")).unwrap();
+        assert!(matches!(engine.matcher, Matcher::Literal(_)));
+        // 第一个候选 (ba 中的 a) 被丢弃后，不漏掉与其重叠的全词匹配
+        let starts: Vec<usize> = engine
+            .search_in_string("ba a a", 1)
+            .iter()
+            .map(|r| r.start)
+            .collect();
+        assert_eq!(starts, vec![3]);
+
+        // 以标点开头的模式只要求不切开词元
+        let engine = SearchEngine::new(&options("-log")).unwrap();
+        assert!(engine.is_match("run -log now"));
+        assert!(engine.is_match("run x-log"));
+        assert!(!engine.is_match("run -logs"));
+
+        // 正则模式下忽略 whole_word
+        let engine = SearchEngine::new(&SearchOptions {
+            pattern: "Init".to_string(),
+            whole_word: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(engine.is_match("Initialization"));
+    }
+
+    #[test]
+    fn test_level_and_category_filter() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "LogPhysics: Display: load failed, retrying").unwrap();
+        writeln!(temp_file, "LogPhysics: Error: load failed").unwrap();
+        writeln!(temp_file, "LogNet: Error: connect failed").unwrap();
+        writeln!(temp_file, "LogPhysics: Warning: step failed").unwrap();
+        writeln!(temp_file, "    failed inside callstack").unwrap();
+
+        let index = index_file(temp_file.path()).unwrap();
+        let lines = |options: &SearchOptions| -> Vec<u64> {
+            let engine = SearchEngine::new(options).unwrap();
+            engine
+                .search_in_file(temp_file.path(), &index, options)
+                .unwrap()
+                .results
+                .iter()
+                .map(|r| r.line_number)
+                .collect()
+        };
+
+        let options = SearchOptions {
+            pattern: "failed".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(lines(&options), vec![1, 2, 3, 4, 5]);
+
+        let options = SearchOptions {
+            level_filter: vec![LogLevel::Error, LogLevel::Warning],
+            ..options
+        };
+        assert_eq!(lines(&options), vec![2, 3, 4]);
+
+        let options = SearchOptions {
+            category_filter: vec!["LogPhysics".to_string()],
+            ..options
+        };
+        assert_eq!(lines(&options), vec![2, 4]);
+
+        let engine = SearchEngine::new(&options).unwrap();
+        let page = engine
+            .search_next_page(temp_file.path(), &index, 1, 10, &options)
+            .unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(
+            engine
+                .count_matches(temp_file.path(), &index, &options)
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_filter_with_parser_config() {
+        let mut temp_file = NamedTempFile::new().unwrap();
+        writeln!(temp_file, "LogCore: Critical: out of memory").unwrap();
+        writeln!(temp_file, "LogCore: Display: memory ok").unwrap();
+
+        let index = index_file(temp_file.path()).unwrap();
+        let options = SearchOptions {
+            pattern: "memory".to_string(),
+            level_filter: vec![LogLevel::Error],
+            ..Default::default()
+        };
+
+        // 默认配置下 Critical 不是已知级别
+        let engine = SearchEngine::new(&options).unwrap();
+        assert!(engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap()
+            .results
+            .is_empty());
+
+        let mut config = ParserConfig::default();
+        config
+            .level_aliases
+            .insert("Critical".to_string(), LogLevel::Error);
+        let engine = SearchEngine::new(&options)
+            .unwrap()
+            .with_parser_config(config);
+        let results = engine
+            .search_in_file(temp_file.path(), &index, &options)
+            .unwrap()
+            .results;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].line_number, 1);
+        assert_eq!(results[0].level, LogLevel::Error);
+    }
+
+    #[test]
+    fn test_validate_regex_offset() {
+        assert_eq!(validate_regex(r"Log\w+: Error"), Ok(()));
+
+        let err = validate_regex("Log(Init").unwrap_err();
+        assert_eq!(err.message, "unclosed group");
+        assert_eq!(err.offset, Some(3));
+
+        // 偏移按字节计，前面的中文字符各占 3 字节
+        let err = validate_regex(r"日志[a-").unwrap_err();
+        assert_eq!(err.offset, Some(6));
+
+        let err = validate_regex(r"ok\q").unwrap_err();
+        assert_eq!(err.offset, Some(2));
+
+        assert_eq!(validate_regex("a\n(").unwrap_err().offset, None);
+    }
+
+    #[test]
+    fn test_sort_line_asc() {
+        assert_eq!(sorted_lines(ResultSort::LineAsc), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_sort_line_desc() {
+        assert_eq!(sorted_lines(ResultSort::LineDesc), vec![4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_sort_level_then_line() {
+        assert_eq!(sorted_lines(ResultSort::LevelThenLine), vec![2, 4, 3, 1]);
+    }
+
+    #[test]
+    fn test_regex_search() {
+        let options = SearchOptions {
+            pattern: r"Error:\s*(\w+)".to_string(),
+            use_regex: true,
+            case_insensitive: true,
+            ..Default::default()
+        };
+
+        let engine = SearchEngine::new(&options).unwrap();
+        let results = engine.search_in_string("LogWindows: Error: TestMessage", 1);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].matched_text, "Error: TestMessage");
+    }
+
+    #[test]
+    fn test_result_reports_line_level() {
+        let options = SearchOptions {
+            pattern: "timeout".to_string(),
+            ..Default::default()
+        };
+
+        let engine = SearchEngine::new(&options).unwrap();
+        let results = engine.search_in_string("LogNet: Error: Connection timeout", 7);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].level, LogLevel::Error);
+        assert_eq!(results[0].category.as_deref(), Some("LogNet"));
+    }
+
+    #[test]
+    fn test_literal_fast_path_matches_regex() {
+        let text = "aaaa LogNet: aa timeout aaa; retry aa";
+        let literal = SearchEngine::new(&SearchOptions {
+            pattern: "aa".to_string(),
+            use_regex: false,
+            case_insensitive: false,
+            ..Default::default()
+        })
+        .unwrap();
+        let regex = SearchEngine::new(&SearchOptions {
+            pattern: "aa".to_string(),
+            use_regex: true,
+            case_insensitive: false,
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(matches!(literal.matcher, Matcher::Literal(_)));
+        assert!(matches!(regex.matcher, Matcher::Regex(_)));
+
+        let fast: Vec<_> = literal
+            .search_in_string(text, 1)
+            .into_iter()
+            .map(|r| (r.start, r.end, r.matched_text))
+            .collect();
+        let slow: Vec<_> = regex
+            .search_in_string(text, 1)
+            .into_iter()
+            .map(|r| (r.start, r.end, r.matched_text))
+            .collect();
+        assert_eq!(fast, slow);
+        assert_eq!(fast.len(), 5);
     }
 
     #[test]
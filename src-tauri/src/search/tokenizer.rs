@@ -0,0 +1,98 @@
+//! 分词器 - 将日志行切分为带位置的词元
+//!
+//! 供全词匹配、消息聚合等功能共用，避免各处各自依赖正则单词边界
+
+/// 词元
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    /// 词元文本
+    pub text: &'a str,
+    /// 起始位置 (字节偏移)
+    pub start: usize,
+    /// 结束位置 (字节偏移，不含)
+    pub end: usize,
+}
+
+/// 分词器
+pub trait Tokenizer: Send + Sync {
+    /// 将文本切分为词元，按出现顺序返回
+    fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>>;
+}
+
+/// 默认分词器: 以空白和标点分隔，字母、数字和下划线组成词元
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DefaultTokenizer;
+
+impl DefaultTokenizer {
+    fn is_token_char(c: char) -> bool {
+        c.is_alphanumeric() || c == '_'
+    }
+}
+
+impl Tokenizer for DefaultTokenizer {
+    fn tokenize<'a>(&self, text: &'a str) -> Vec<Token<'a>> {
+        let mut tokens = Vec::new();
+        let mut start: Option<usize> = None;
+
+        for (i, c) in text.char_indices() {
+            match (Self::is_token_char(c), start) {
+                (true, None) => start = Some(i),
+                (false, Some(s)) => {
+                    tokens.push(Token {
+                        text: &text[s..i],
+                        start: s,
+                        end: i,
+                    });
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(s) = start {
+            tokens.push(Token {
+                text: &text[s..],
+                start: s,
+                end: text.len(),
+            });
+        }
+
+        tokens
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_tokenizer_spans() {
+        let line = "LogNet: Error: Connection_lost (timeout=30s) к серверу";
+        let tokens = DefaultTokenizer.tokenize(line);
+
+        let texts: Vec<&str> = tokens.iter().map(|t| t.text).collect();
+        assert_eq!(
+            texts,
+            vec![
+                "LogNet",
+                "Error",
+                "Connection_lost",
+                "timeout",
+                "30s",
+                "к",
+                "серверу"
+            ]
+        );
+
+        for token in &tokens {
+            assert_eq!(&line[token.start..token.end], token.text);
+        }
+        assert_eq!((tokens[2].start, tokens[2].end), (15, 30));
+    }
+
+    #[test]
+    fn test_default_tokenizer_empty_and_punctuation() {
+        assert!(DefaultTokenizer.tokenize("").is_empty());
+        assert!(DefaultTokenizer.tokenize("  ... --> ]").is_empty());
+    }
+}
@@ -0,0 +1,245 @@
+//! 编码处理 - 按文件编码切分和解码行
+//!
+//! UTF-16 文件的换行是两字节码元，不能直接按 b'\n' 切分
+
+use std::borrow::Cow;
+use std::io::BufRead;
+
+use crate::parser::Encoding;
+
+/// 换行码元
+fn newline_unit(encoding: Encoding) -> [u8; 2] {
+    match encoding {
+        Encoding::Utf16Be => [0x00, b'\n'],
+        _ => [b'\n', 0x00],
+    }
+}
+
+/// 换行的字节序列 (按源文件编码写出行时使用)
+pub fn newline_bytes(encoding: Encoding) -> &'static [u8] {
+    match encoding {
+        Encoding::Utf16Le => &[b'\n', 0x00],
+        Encoding::Utf16Be => &[0x00, b'\n'],
+        Encoding::Utf8 | Encoding::Utf8Bom => b"\n",
+    }
+}
+
+/// 查找 data[from..to] 中的换行，返回 (换行位置, 下一行起始位置)
+///
+/// UTF-16 时 from 必须与码元对齐
+pub fn newline_spans(
+    data: &[u8],
+    from: usize,
+    to: usize,
+    encoding: Encoding,
+) -> Box<dyn Iterator<Item = (usize, usize)> + '_> {
+    if encoding.is_utf16() {
+        let unit = newline_unit(encoding);
+        Box::new(
+            data[from..to]
+                .chunks_exact(2)
+                .enumerate()
+                .filter(move |(_, u)| *u == unit)
+                .map(move |(k, _)| (from + 2 * k, from + 2 * k + 2)),
+        )
+    } else {
+        Box::new(memchr::memchr_iter(b'\n', &data[from..to]).map(move |p| (from + p, from + p + 1)))
+    }
+}
+
+/// data[..end] 中最后一行的起始位置 (没有换行时为 BOM 之后)
+pub fn last_line_start(data: &[u8], end: usize, encoding: Encoding) -> usize {
+    let bom = encoding.bom_len().min(end);
+    if encoding.is_utf16() {
+        newline_spans(data, bom, end, encoding)
+            .last()
+            .map_or(bom, |(_, next)| next)
+    } else {
+        memchr::memrchr(b'\n', &data[bom..end]).map_or(bom, |p| bom + p + 1)
+    }
+}
+
+/// 解码一行 (不含换行符)，UTF-8 无效时返回 None，UTF-16 有损解码
+pub fn decode_line(bytes: &[u8], encoding: Encoding) -> Option<Cow<'_, str>> {
+    match encoding {
+        Encoding::Utf8 | Encoding::Utf8Bom => std::str::from_utf8(bytes).ok().map(Cow::Borrowed),
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|u| match encoding {
+                    Encoding::Utf16Be => u16::from_be_bytes([u[0], u[1]]),
+                    _ => u16::from_le_bytes([u[0], u[1]]),
+                })
+                .collect();
+            Some(Cow::Owned(String::from_utf16_lossy(&units)))
+        }
+    }
+}
+
+/// 从文件开头检测编码并跳过 BOM，用于没有索引时直接读取整个文件
+pub fn skip_bom<R: BufRead>(reader: &mut R) -> std::io::Result<Encoding> {
+    let encoding = Encoding::detect(reader.fill_buf()?);
+    reader.consume(encoding.bom_len());
+    Ok(encoding)
+}
+
+/// 按编码逐行读取 (行为与 BufRead::lines 一致: 去掉行尾的 \n 或 \r\n)
+pub struct DecodedLines<R> {
+    reader: R,
+    encoding: Encoding,
+    buf: Vec<u8>,
+}
+
+/// 以指定编码逐行读取，读取位置需位于行首
+pub fn decode_lines<R: BufRead>(reader: R, encoding: Encoding) -> DecodedLines<R> {
+    DecodedLines {
+        reader,
+        encoding,
+        buf: Vec::new(),
+    }
+}
+
+impl<R: BufRead> DecodedLines<R> {
+    /// 读取一行 UTF-16 的原始字节 (含换行码元)，返回是否读到了内容
+    fn read_utf16_line(&mut self) -> std::io::Result<bool> {
+        loop {
+            if self.reader.read_until(b'\n', &mut self.buf)? == 0 {
+                return Ok(!self.buf.is_empty());
+            }
+
+            let len = self.buf.len();
+            match self.encoding {
+                // 小端: 0x0A 位于码元低字节 (偶数位置)，还需确认下一个字节为 0x00
+                Encoding::Utf16Le if len % 2 == 1 => {
+                    let next = self.reader.fill_buf()?.first().copied();
+                    match next {
+                        Some(0x00) => {
+                            self.reader.consume(1);
+                            self.buf.push(0x00);
+                            return Ok(true);
+                        }
+                        None => return Ok(true),
+                        Some(_) => {}
+                    }
+                }
+                // 大端: 0x0A 位于码元高位之后 (奇数位置)，前一个字节为 0x00
+                Encoding::Utf16Be if len % 2 == 0 && self.buf[len - 2] == 0x00 => {
+                    return Ok(true);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// 以指定编码逐行读取，无效的 UTF-8 替换为 U+FFFD 而不是报错
+///
+/// 每行附带是否含无效 UTF-8 (UTF-16 始终按有损解码，不标记)
+pub fn decode_lines_lossy<R: BufRead>(reader: R, encoding: Encoding) -> LossyLines<R> {
+    LossyLines(decode_lines(reader, encoding))
+}
+
+/// 宽松解码的逐行读取，见 decode_lines_lossy
+pub struct LossyLines<R>(DecodedLines<R>);
+
+impl<R: BufRead> Iterator for LossyLines<R> {
+    type Item = std::io::Result<(String, bool)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next_line(true)
+    }
+}
+
+impl<R: BufRead> Iterator for DecodedLines<R> {
+    type Item = std::io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_line(false).map(|line| line.map(|(line, _)| line))
+    }
+}
+
+impl<R: BufRead> DecodedLines<R> {
+    /// 读取下一行，返回内容和是否含无效 UTF-8；lossy 为 false 时无效 UTF-8 报错
+    fn next_line(&mut self, lossy: bool) -> Option<std::io::Result<(String, bool)>> {
+        self.buf.clear();
+
+        if self.encoding.is_utf16() {
+            match self.read_utf16_line() {
+                Ok(false) => return None,
+                Ok(true) => {}
+                Err(e) => return Some(Err(e)),
+            }
+
+            let mut line = decode_line(&self.buf, self.encoding)?.into_owned();
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+            return Some(Ok((line, false)));
+        }
+
+        match self.reader.read_until(b'\n', &mut self.buf) {
+            Ok(0) => None,
+            Ok(_) => {
+                if self.buf.ends_with(b"\n") {
+                    self.buf.pop();
+                    if self.buf.ends_with(b"\r") {
+                        self.buf.pop();
+                    }
+                }
+                Some(match String::from_utf8(std::mem::take(&mut self.buf)) {
+                    Ok(line) => Ok((line, false)),
+                    Err(e) if lossy => {
+                        Ok((String::from_utf8_lossy(e.as_bytes()).into_owned(), true))
+                    }
+                    Err(e) => Err(std::io::Error::new(std::io::ErrorKind::InvalidData, e)),
+                })
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        let mut bytes = vec![0xFF, 0xFE];
+        bytes.extend(text.encode_utf16().flat_map(|u| u.to_le_bytes()));
+        bytes
+    }
+
+    #[test]
+    fn test_decode_utf16le_lines() {
+        // U+010A 的低字节是 0x0A，不能被当作换行
+        let bytes = utf16le("LogInit: Display: Ċ ok\r\nLogNet: Error: 失败\n");
+        let lines: Vec<String> = decode_lines(&bytes[2..], Encoding::Utf16Le)
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(lines, vec!["LogInit: Display: Ċ ok", "LogNet: Error: 失败"]);
+
+        let spans: Vec<_> = newline_spans(&bytes, 2, bytes.len(), Encoding::Utf16Le).collect();
+        assert_eq!(spans.len(), 2);
+        assert_eq!(
+            last_line_start(&bytes, bytes.len(), Encoding::Utf16Le),
+            bytes.len()
+        );
+    }
+
+    #[test]
+    fn test_decode_utf8_lines_like_std() {
+        let text = "a\r\nb\n\nc";
+        let ours: Vec<String> = decode_lines(text.as_bytes(), Encoding::Utf8)
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        let std: Vec<String> = text
+            .as_bytes()
+            .lines()
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(ours, std);
+    }
+}
@@ -3,17 +3,32 @@
 //! 使用内存映射提高大文件的读取性能
 
 use memmap2::Mmap;
-use std::collections::HashMap;
+use parking_lot::Mutex;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
 use std::path::Path;
+use std::time::{Instant, UNIX_EPOCH};
 
-use crate::parser::{FileIndex, LogLevel, LogParser};
+use crate::parser::{
+    CategorySample, ContentSignature, Encoding, FileIndex, IndexStats, LogFileInfo, LogLevel,
+    LogParser,
+};
+use crate::streaming::buffered;
+use crate::streaming::encoding::{
+    decode_line, decode_lines, last_line_start, newline_spans, skip_bom,
+};
 
 /// 文件索引器
 pub struct FileIndexer {
     file_path: String,
-    file: File,
-    mmap: Mmap,
+    modified_time: u64,
+    /// 打开时文件首尾采样的内容哈希
+    content_hash: u64,
+    encoding: Encoding,
+    /// 空文件不做映射
+    mmap: Option<Mmap>,
 }
 
 impl FileIndexer {
@@ -22,80 +37,425 @@ impl FileIndexer {
         let file_path = path.as_ref().to_string_lossy().to_string();
         let file = File::open(&path)?;
         let metadata = file.metadata()?;
-        let mmap = unsafe { Mmap::map(&file)? };
+        let modified_time = modified_millis(&metadata);
+        let mmap = map_nonempty(&file, metadata.len())?;
+        let encoding = Encoding::detect(mmap.as_deref().unwrap_or_default());
+        let content_hash = sample_hash(mmap.as_deref().unwrap_or_default());
 
         Ok(Self {
             file_path,
-            file,
+            modified_time,
+            content_hash,
+            encoding,
             mmap,
         })
     }
 
     /// 构建文件索引
     pub fn build_index(&self) -> FileIndex {
-        let mut index = FileIndex::new(self.file_path.clone(), self.mmap.len() as u64);
-
-        let mut line_offsets: Vec<u64> = vec![0]; // 第一行从 0 开始
-        let mut current_offset: u64 = 0;
-        let mut line_count: u64 = 0;
-        let mut categories: HashMap<String, u64> = HashMap::new();
-        let mut level_counts: HashMap<String, u64> = HashMap::new();
-
-        let data = &self.mmap;
-
-        // 遍历文件，记录行偏移和统计信息
-        for (i, &byte) in data.iter().enumerate() {
-            if byte == b'\n' {
-                line_count += 1;
-
-                // 提取当前行内容
-                let start = current_offset as usize;
-                let end = i;
-                if start < end {
-                    if let Ok(line) = std::str::from_utf8(&data[start..end]) {
-                        // 提取类别
-                        if let Some(category) = LogParser::extract_category(line) {
-                            *categories.entry(category).or_insert(0) += 1;
-                        }
-                        // 提取级别 (未匹配的计入 unknown)
-                        let level = LogParser::extract_level(line)
-                            .unwrap_or(LogLevel::Unknown);
-                        *level_counts
-                            .entry(level.display_name().to_lowercase())
-                            .or_insert(0) += 1;
-                    }
-                }
+        self.build(None)
+    }
 
-                // 每隔 INDEX_INTERVAL 行记录一次偏移
-                if line_count % FileIndex::INDEX_INTERVAL == 0 {
-                    line_offsets.push((i + 1) as u64);
-                }
+    /// 构建文件索引并统计耗时和吞吐量 (性能诊断用，常规打开文件走 build_index)
+    pub fn build_index_timed(&self) -> (FileIndex, IndexStats) {
+        let started = Instant::now();
+        let index = self.build_index();
+        let stats = IndexStats::new(
+            self.data().len() as u64,
+            index.total_lines,
+            started.elapsed(),
+        );
+        (index, stats)
+    }
 
-                current_offset = (i + 1) as u64;
-            }
+    /// 构建文件索引，同时记录每个类别出现的行号 (每个类别最多 max_per_category 行)
+    pub fn build_index_with_category_lines(&self, max_per_category: usize) -> FileIndex {
+        self.build(Some(max_per_category))
+    }
+
+    /// 构建文件索引，期间以 (已扫描字节数, 总字节数) 回调进度
+    ///
+    /// 每完成约 5% 回调一次；UTF-16 文件不切块，只在扫描结束时回调
+    pub fn build_index_with_progress(&self, on_progress: &(dyn Fn(u64, u64) + Sync)) -> FileIndex {
+        // 切成更多块，块的完成即为进度
+        let chunks = if self.encoding.is_utf16() {
+            1
+        } else {
+            self.chunk_count().max(PROGRESS_STEPS as usize)
+        };
+        let progress = Progress::new(self.data().len() as u64, on_progress);
+        self.build_chunked(None, chunks, Some(&progress))
+    }
+
+    fn build(&self, category_lines_cap: Option<usize>) -> FileIndex {
+        self.build_chunked(category_lines_cap, self.chunk_count(), None)
+    }
+
+    /// 并行扫描的块数
+    fn chunk_count(&self) -> usize {
+        // UTF-16 的换行需按码元对齐，不切块
+        if self.encoding.is_utf16() {
+            1
+        } else {
+            (self.data().len() / PARALLEL_CHUNK_SIZE).clamp(1, rayon::current_num_threads().max(1))
+        }
+    }
+
+    /// 将文件按行边界切成 chunks 块并行扫描，再按顺序合并
+    ///
+    /// 先并行统计每块的行数得到各块的起始行号，
+    /// 再并行扫描各块，保证稀疏偏移与单线程扫描完全一致
+    fn build_chunked(
+        &self,
+        category_lines_cap: Option<usize>,
+        chunks: usize,
+        progress: Option<&Progress>,
+    ) -> FileIndex {
+        let data = self.data();
+
+        let mut index = FileIndex::new(self.file_path.clone(), data.len() as u64);
+        index.modified_time = self.modified_time;
+        index.content_hash = Some(self.content_hash);
+        index.encoding = self.encoding;
+        index.index_interval = FileIndex::interval_for_size(index.file_size);
+
+        // 第一行从 BOM 之后开始
+        let first_line = self.encoding.bom_len().min(data.len());
+        index.line_offsets = vec![first_line as u64];
+        index.last_indexed_offset = first_line as u64;
+
+        // 小文件记录每一行的偏移，读取时可直接定位
+        index.dense_offsets = index.file_size < FileIndex::DENSE_OFFSETS_THRESHOLD;
+        if index.dense_offsets && first_line < data.len() {
+            index.dense_line_offsets.push(first_line as u64);
         }
 
-        // 处理最后一行 (如果没有以换行符结尾)
-        if current_offset < data.len() as u64 {
-            line_count += 1;
+        if let Some(cap) = category_lines_cap {
+            index.category_lines = Some(HashMap::new());
+            index.category_lines_cap = cap;
         }
 
-        index.total_lines = line_count;
-        index.line_offsets = line_offsets;
-        index.categories = categories;
-        index.level_counts = level_counts;
+        let bounds = chunk_bounds(data, first_line, chunks);
+        if let Some(progress) = progress {
+            progress.advance(first_line as u64);
+        }
+
+        // 各块之前的行数
+        let newlines: Vec<u64> = bounds
+            .par_iter()
+            .map(|&(start, end)| newline_spans(data, start, end, self.encoding).count() as u64)
+            .collect();
+        let bases = newlines.iter().scan(0, |total, &count| {
+            let base = *total;
+            *total += count;
+            Some(base)
+        });
+        let jobs: Vec<((usize, usize), u64)> = bounds.into_iter().zip(bases).collect();
+
+        let parts: Vec<FileIndex> = jobs
+            .into_par_iter()
+            .map(|((start, end), base)| {
+                let mut part = FileIndex::new(String::new(), 0);
+                part.total_lines = base;
+                part.encoding = index.encoding;
+                part.index_interval = index.index_interval;
+                part.dense_offsets = index.dense_offsets;
+                part.category_lines = index.category_lines.clone();
+                part.category_lines_cap = index.category_lines_cap;
+                scan_lines(data, start, end, &mut part);
+                if let Some(progress) = progress {
+                    progress.advance((end - start) as u64);
+                }
+                part
+            })
+            .collect();
+
+        for part in parts {
+            merge_part(&mut index, part);
+        }
 
         index
     }
 
+    /// 文件增长后增量更新索引，只扫描新增的字节，返回新增的行数
+    ///
+    /// 会重新映射文件 (不使用打开时的映射)。从 last_indexed_offset 继续扫描，
+    /// 上次末尾未以换行结束的行会重新扫描。
+    /// 文件变小 (被截断或轮转)，或已索引部分的内容哈希与记录不符
+    /// (轮转后的新文件已比旧文件大) 时返回以 FILE_TRUNCATED 开头的错误
+    pub fn update_index(&self, existing: &mut FileIndex) -> std::io::Result<u64> {
+        let file = File::open(&self.file_path)?;
+        let metadata = file.metadata()?;
+        let modified_time = modified_millis(&metadata);
+        let new_size = metadata.len();
+
+        if new_size < existing.file_size {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{}: file shrank from {} to {} bytes",
+                    FILE_TRUNCATED, existing.file_size, new_size
+                ),
+            ));
+        }
+
+        existing.modified_time = modified_time;
+        if new_size == existing.file_size {
+            return Ok(0);
+        }
+
+        let Some(mmap) = map_nonempty(&file, new_size)? else {
+            return Ok(0);
+        };
+
+        let old_size = existing.file_size as usize;
+        if existing
+            .content_hash
+            .is_some_and(|hash| hash != sample_hash(&mmap[..old_size]))
+        {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "{}: content of the first {} bytes changed",
+                    FILE_TRUNCATED, old_size
+                ),
+            ));
+        }
+        existing.content_hash = Some(sample_hash(&mmap));
+
+        let old_lines = existing.total_lines;
+
+        // 末尾不完整的行已计入 total_lines 和统计，撤销后从它的行首重新扫描；
+        // 旧版本的 sidecar 没有记录 last_indexed_offset，向前查找行首
+        let resume = match existing.last_indexed_offset as usize {
+            0 => last_line_start(&mmap, old_size, existing.encoding),
+            offset => offset.min(old_size),
+        };
+        if resume < old_size {
+            forget_line(existing, &mmap[resume..old_size], existing.total_lines);
+            existing.total_lines -= 1;
+        } else if existing.dense_offsets {
+            // 原文件以换行结尾，新增的第一行偏移尚未记录
+            existing.dense_line_offsets.push(resume as u64);
+        }
+
+        scan_lines(&mmap, resume, mmap.len(), existing);
+
+        Ok(existing.total_lines - old_lines)
+    }
+
     /// 获取文件大小
     pub fn file_size(&self) -> u64 {
-        self.mmap.len() as u64
+        self.data().len() as u64
+    }
+
+    /// 获取内存映射引用 (空文件为 None)
+    pub fn mmap(&self) -> Option<&Mmap> {
+        self.mmap.as_ref()
+    }
+
+    /// 文件内容 (空文件为空切片)
+    fn data(&self) -> &[u8] {
+        self.mmap.as_deref().unwrap_or_default()
+    }
+}
+
+/// 只读映射文件 (不计算签名，供搜索等只需读取内容的场景使用)；空文件返回 None
+pub fn map_file<P: AsRef<Path>>(path: P) -> std::io::Result<Option<Mmap>> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+    map_nonempty(&file, len)
+}
+
+/// 映射文件；长度为 0 时不映射 (部分平台不支持映射空文件)
+fn map_nonempty(file: &File, len: u64) -> std::io::Result<Option<Mmap>> {
+    if len == 0 {
+        return Ok(None);
+    }
+    Ok(Some(unsafe { Mmap::map(file)? }))
+}
+
+/// 文件被截断或轮转时 update_index 返回的错误码
+pub const FILE_TRUNCATED: &str = "FILE_TRUNCATED";
+
+/// 单线程扫描即可的文件大小，超过后按此大小切块并行索引
+const PARALLEL_CHUNK_SIZE: usize = 16 * 1024 * 1024;
+
+/// 进度回调的次数上限 (每 5% 一次)
+const PROGRESS_STEPS: u64 = 20;
+
+/// 按块累计扫描进度，每跨过 1/PROGRESS_STEPS 才回调一次
+///
+/// 各块在不同线程完成，加锁保证回调的进度单调递增
+struct Progress<'a> {
+    total: u64,
+    /// (已扫描字节数, 已回调到的档位)
+    state: Mutex<(u64, u64)>,
+    on_progress: &'a (dyn Fn(u64, u64) + Sync),
+}
+
+impl<'a> Progress<'a> {
+    fn new(total: u64, on_progress: &'a (dyn Fn(u64, u64) + Sync)) -> Self {
+        Self {
+            total,
+            state: Mutex::new((0, 0)),
+            on_progress,
+        }
+    }
+
+    fn advance(&self, bytes: u64) {
+        let mut state = self.state.lock();
+        state.0 += bytes;
+        let step = state.0 * PROGRESS_STEPS / self.total.max(1);
+        if step > state.1 {
+            state.1 = step;
+            (self.on_progress)(state.0, self.total);
+        }
+    }
+}
+
+/// 将 data[from..] 切成至多 chunks 个区间，每个区间 (最后一个除外) 都在换行符之后结束
+///
+/// 只按单字节 b'\n' 切分，UTF-16 文件应只用一个区间
+fn chunk_bounds(data: &[u8], from: usize, chunks: usize) -> Vec<(usize, usize)> {
+    let target = (data.len() - from).div_ceil(chunks.max(1)).max(1);
+    let mut bounds = Vec::new();
+    let mut start = from;
+
+    while start < data.len() {
+        let end = (start + target).min(data.len());
+        let end = memchr::memchr(b'\n', &data[end - 1..]).map_or(data.len(), |pos| end + pos);
+        bounds.push((start, end));
+        start = end;
+    }
+
+    bounds
+}
+
+/// 按顺序合并一块的扫描结果 (part.total_lines 为累计到该块末尾的行数)
+fn merge_part(index: &mut FileIndex, part: FileIndex) {
+    index.total_lines = part.total_lines;
+    index.last_indexed_offset = part.last_indexed_offset;
+    index.line_offsets.extend(part.line_offsets);
+    index.dense_line_offsets.extend(part.dense_line_offsets);
+
+    for (category, count) in part.categories {
+        *index.categories.entry(category).or_insert(0) += count;
+    }
+    for (level, count) in part.level_counts {
+        *index.level_counts.entry(level).or_insert(0) += count;
+    }
+
+    if let (Some(merged), Some(lines)) = (index.category_lines.as_mut(), part.category_lines) {
+        for (category, part_lines) in lines {
+            let merged_lines = merged.entry(category).or_default();
+            let room = index.category_lines_cap.saturating_sub(merged_lines.len());
+            merged_lines.extend(part_lines.into_iter().take(room));
+        }
+    }
+}
+
+/// 扫描 data[from..to] (from 为行首)，追加行偏移并累计类别/级别统计
+///
+/// index 中已有的 total_lines、line_offsets 等视为 from 之前的结果
+fn scan_lines(data: &[u8], from: usize, to: usize, index: &mut FileIndex) {
+    let mut current_offset = from;
+    let mut line_count = index.total_lines;
+    let dense = index.dense_offsets;
+    let encoding = index.encoding;
+    let interval = index.index_interval;
+
+    // 遍历文件中的换行，记录行偏移和统计信息
+    for (newline, next) in newline_spans(data, from, to, encoding) {
+        line_count += 1;
+
+        record_line(index, &data[current_offset..newline], line_count);
+
+        // 每隔 index_interval 行记录一次偏移
+        if line_count % interval == 0 {
+            index.line_offsets.push(next as u64);
+        }
+
+        if dense && next < data.len() {
+            index.dense_line_offsets.push(next as u64);
+        }
+
+        current_offset = next;
     }
+    index.last_indexed_offset = current_offset as u64;
 
-    /// 获取内存映射引用
-    pub fn mmap(&self) -> &Mmap {
-        &self.mmap
+    // 处理最后一行 (如果没有以换行符结尾)
+    if to == data.len() && current_offset < data.len() {
+        line_count += 1;
+        record_line(index, &data[current_offset..], line_count);
+    }
+
+    index.total_lines = line_count;
+    index.file_size = data.len() as u64;
+}
+
+/// 提取一行的类别和级别；CRLF 行尾的 \r 不属于行内容 (与读取时一致)，空行返回 None
+fn line_stats(bytes: &[u8], encoding: Encoding) -> Option<(Option<String>, LogLevel)> {
+    let line = decode_line(bytes, encoding)?;
+    let line = line.strip_suffix('\r').unwrap_or(&line);
+    if line.is_empty() {
+        return None;
+    }
+
+    // 未匹配级别的行计入 unknown
+    let level = LogParser::extract_level(line).unwrap_or(LogLevel::Unknown);
+    Some((LogParser::extract_category(line), level))
+}
+
+/// 将第 line_number 行计入类别/级别统计
+fn record_line(index: &mut FileIndex, bytes: &[u8], line_number: u64) {
+    let Some((category, level)) = line_stats(bytes, index.encoding) else {
+        return;
+    };
+
+    if let Some(category) = category {
+        if let Some(category_lines) = index.category_lines.as_mut() {
+            let lines = category_lines.entry(category.clone()).or_default();
+            if lines.len() < index.category_lines_cap {
+                lines.push(line_number);
+            }
+        }
+        *index.categories.entry(category).or_insert(0) += 1;
+    }
+    *index
+        .level_counts
+        .entry(level.display_name().to_lowercase())
+        .or_insert(0) += 1;
+}
+
+/// 撤销 record_line 对第 line_number 行的统计 (该行需重新扫描时使用)
+fn forget_line(index: &mut FileIndex, bytes: &[u8], line_number: u64) {
+    let Some((category, level)) = line_stats(bytes, index.encoding) else {
+        return;
+    };
+
+    if let Some(category) = category {
+        if let Some(lines) = index
+            .category_lines
+            .as_mut()
+            .and_then(|category_lines| category_lines.get_mut(&category))
+        {
+            if lines.last() == Some(&line_number) {
+                lines.pop();
+            }
+        }
+        decrement(&mut index.categories, category);
+    }
+    decrement(&mut index.level_counts, level.display_name().to_lowercase());
+}
+
+/// 计数减一，减到 0 时移除
+fn decrement(counts: &mut HashMap<String, u64>, key: String) {
+    if let Some(count) = counts.get_mut(&key) {
+        *count = count.saturating_sub(1);
+        if *count == 0 {
+            counts.remove(&key);
+        }
     }
 }
 
@@ -105,6 +465,212 @@ pub fn index_file<P: AsRef<Path>>(path: P) -> std::io::Result<FileIndex> {
     Ok(indexer.build_index())
 }
 
+/// 优先复用持久化的索引，否则重新构建并写入 sidecar
+///
+/// 文件在建索引之后只是追加了内容 (如重启前正在跟踪的日志) 时，
+/// 从持久化索引的 last_indexed_offset 继续增量索引。返回的 bool 表示是否复用了 sidecar
+pub fn load_or_index_file<P: AsRef<Path>>(path: P) -> std::io::Result<(FileIndex, bool)> {
+    load_or_build(path.as_ref(), None)
+}
+
+/// 同 load_or_index_file，重新构建时以 (已扫描字节数, 总字节数) 回调进度
+pub fn load_or_index_file_with_progress<P: AsRef<Path>>(
+    path: P,
+    on_progress: &(dyn Fn(u64, u64) + Sync),
+) -> std::io::Result<(FileIndex, bool)> {
+    load_or_build(path.as_ref(), Some(on_progress))
+}
+
+fn load_or_build(
+    path: &Path,
+    on_progress: Option<&(dyn Fn(u64, u64) + Sync)>,
+) -> std::io::Result<(FileIndex, bool)> {
+    let sidecar = FileIndex::sidecar_path(path);
+
+    if let Ok(mut index) = FileIndex::load_from(&sidecar) {
+        let signature = file_signature(path)?;
+        // 旧版本的 sidecar 没有记录编码，按字节切分的 UTF-16 索引不可复用
+        let same_encoding = || -> std::io::Result<bool> {
+            Ok(index.encoding == skip_bom(&mut buffered(File::open(path)?))?)
+        };
+
+        if index.content_signature() == signature && same_encoding()? {
+            return Ok((index, true));
+        }
+
+        if signature.file_size > index.file_size
+            && index.content_hash == Some(prefix_hash(&mut File::open(path)?, index.file_size)?)
+            && same_encoding()?
+        {
+            FileIndexer::open(path)?.update_index(&mut index)?;
+            if let Err(e) = index.save_to(&sidecar) {
+                log::warn!("Failed to write index sidecar {}: {}", sidecar.display(), e);
+            }
+            return Ok((index, true));
+        }
+    }
+
+    let indexer = FileIndexer::open(path)?;
+    let index = match on_progress {
+        Some(on_progress) => indexer.build_index_with_progress(on_progress),
+        None => indexer.build_index(),
+    };
+    if let Err(e) = index.save_to(&sidecar) {
+        log::warn!("Failed to write index sidecar {}: {}", sidecar.display(), e);
+    }
+
+    Ok((index, false))
+}
+
+/// 内容哈希的采样长度: 文件首尾各取这么多字节
+const SIGNATURE_SAMPLE_BYTES: usize = 1024 * 1024;
+
+/// 读取文件当前的内容签名 (只读取首尾采样，不做内存映射)
+pub fn file_signature(path: &Path) -> std::io::Result<ContentSignature> {
+    let mut file = File::open(path)?;
+    let metadata = file.metadata()?;
+    let file_size = metadata.len();
+
+    Ok(ContentSignature {
+        file_size,
+        modified_time: modified_millis(&metadata),
+        content_hash: Some(prefix_hash(&mut file, file_size)?),
+    })
+}
+
+/// 文件前 len 字节的采样哈希 (即文件长度为 len 时的内容哈希)
+fn prefix_hash(file: &mut File, len: u64) -> std::io::Result<u64> {
+    let sample_len = SIGNATURE_SAMPLE_BYTES.min(len as usize);
+
+    let mut head = vec![0; sample_len];
+    file.seek(SeekFrom::Start(0))?;
+    file.read_exact(&mut head)?;
+    let mut tail = vec![0; sample_len];
+    file.seek(SeekFrom::Start(len - sample_len as u64))?;
+    file.read_exact(&mut tail)?;
+
+    Ok(hash_samples(len, &head, &tail))
+}
+
+/// 对内存中的完整文件内容计算采样哈希 (与 file_signature 的结果一致)
+fn sample_hash(data: &[u8]) -> u64 {
+    let sample_len = SIGNATURE_SAMPLE_BYTES.min(data.len());
+    hash_samples(
+        data.len() as u64,
+        &data[..sample_len],
+        &data[data.len() - sample_len..],
+    )
+}
+
+/// 文件大小与首尾采样的 64 位 FNV-1a 哈希
+fn hash_samples(file_size: u64, head: &[u8], tail: &[u8]) -> u64 {
+    const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    file_size
+        .to_le_bytes()
+        .iter()
+        .chain(head)
+        .chain(tail)
+        .fold(FNV_OFFSET, |hash, &byte| {
+            (hash ^ byte as u64).wrapping_mul(FNV_PRIME)
+        })
+}
+
+/// 文件修改时间 (Unix 毫秒)，无法获取时为 0
+fn modified_millis(metadata: &std::fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_millis() as u64)
+}
+
+/// 采样发现日志类别 (不建立完整索引)
+///
+/// 最多扫描 max_lines 行；连续 stable_lines 行没有出现新类别时提前结束
+pub fn sample_categories<P: AsRef<Path>>(
+    path: P,
+    max_lines: u64,
+    stable_lines: u64,
+) -> std::io::Result<CategorySample> {
+    let mut reader = buffered(File::open(path)?);
+    let encoding = skip_bom(&mut reader)?;
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut categories = Vec::new();
+    let mut lines_scanned: u64 = 0;
+    let mut since_new: u64 = 0;
+    let mut complete = true;
+
+    for line_result in decode_lines(reader, encoding) {
+        if lines_scanned >= max_lines || since_new >= stable_lines {
+            complete = false;
+            break;
+        }
+
+        let line = line_result?;
+        lines_scanned += 1;
+        since_new += 1;
+
+        if let Some(category) = LogParser::extract_category(&line) {
+            if seen.insert(category.clone()) {
+                categories.push(category);
+                since_new = 0;
+            }
+        }
+    }
+
+    Ok(CategorySample {
+        categories,
+        complete,
+        lines_scanned,
+    })
+}
+
+/// 估算行数时采样的文件开头长度
+const LINE_ESTIMATE_SAMPLE_BYTES: usize = 64 * 1024;
+
+/// 读取文件大小、修改时间和估算的行数 (只读取开头 LINE_ESTIMATE_SAMPLE_BYTES 字节)
+pub fn log_file_info<P: AsRef<Path>>(path: P) -> std::io::Result<LogFileInfo> {
+    let file = File::open(&path)?;
+    let metadata = file.metadata()?;
+
+    let mut sample = Vec::with_capacity(LINE_ESTIMATE_SAMPLE_BYTES.min(metadata.len() as usize));
+    file.take(LINE_ESTIMATE_SAMPLE_BYTES as u64)
+        .read_to_end(&mut sample)?;
+
+    Ok(LogFileInfo {
+        path: path.as_ref().to_string_lossy().to_string(),
+        size: metadata.len(),
+        modified: modified_millis(&metadata),
+        line_count_estimate: estimate_lines(&sample, metadata.len()),
+    })
+}
+
+/// 按采样中的平均行长估算 file_size 字节的文件的行数；采样即整个文件时为精确值
+fn estimate_lines(sample: &[u8], file_size: u64) -> u64 {
+    let encoding = Encoding::detect(sample);
+    let start = encoding.bom_len().min(sample.len());
+    let mut newlines = 0;
+    let mut last_line_end = start;
+    for (_, next) in newline_spans(sample, start, sample.len(), encoding) {
+        newlines += 1;
+        last_line_end = next;
+    }
+
+    if sample.len() as u64 >= file_size {
+        // 最后一行没有以换行结束
+        return newlines + u64::from(last_line_end < sample.len());
+    }
+    if newlines == 0 {
+        return 1;
+    }
+
+    let average = (last_line_end - start) as u64 / newlines;
+    file_size / average.max(1)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -129,4 +695,545 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_build_index_timed() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        let content: String = (0..20_000)
+            .map(|i| {
+                format!(
+                    "[2026.02.14-03.33.56:070][{:3}]LogTemp: Display: Benchmark line {}\n",
+                    i % 1000,
+                    i
+                )
+            })
+            .collect();
+        temp_file.write_all(content.as_bytes())?;
+        temp_file.flush()?;
+
+        let indexer = FileIndexer::open(temp_file.path())?;
+        let (index, stats) = indexer.build_index_timed();
+
+        assert_eq!(index.total_lines, 20_000);
+        assert_eq!(stats.lines, 20_000);
+        assert_eq!(stats.bytes, temp_file.as_file().metadata()?.len());
+        assert!(stats.elapsed_ms > 0.0);
+        assert!(stats.mb_per_sec > 0.0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_index_matches_serial() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "Log file open, 02/14/26 11:33:35")?;
+        for i in 0..5000 {
+            match i % 7 {
+                0 => writeln!(temp_file, "LogNet: Error: Failure {}", i)?,
+                1 => writeln!(temp_file, "  continuation {}", i)?,
+                2 => writeln!(temp_file)?,
+                3 => writeln!(
+                    temp_file,
+                    "[2026.02.14-03.33.56:070][{:3}]LogCore: Warning: Slow",
+                    i
+                )?,
+                _ => writeln!(temp_file, "LogInit: Display: Line {}", i)?,
+            }
+        }
+        write!(temp_file, "LogTail: Display: no trailing newline")?;
+        temp_file.flush()?;
+
+        let indexer = FileIndexer::open(temp_file.path())?;
+        let serial = indexer.build_chunked(Some(50), 1, None);
+
+        for chunks in [2, 3, 7, 64] {
+            let parallel = indexer.build_chunked(Some(50), chunks, None);
+            assert_eq!(
+                serde_json::to_value(&parallel)?,
+                serde_json::to_value(&serial)?
+            );
+            assert_eq!(parallel.dense_line_offsets, serial.dense_line_offsets);
+            assert_eq!(parallel.category_lines, serial.category_lines);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_index_with_progress() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 0..5000 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+        temp_file.flush()?;
+
+        let indexer = FileIndexer::open(temp_file.path())?;
+        let reports = std::sync::Mutex::new(Vec::new());
+        let index = indexer.build_index_with_progress(&|done, total| {
+            reports.lock().unwrap().push((done, total));
+        });
+        let reports = reports.into_inner().unwrap();
+
+        let total = index.file_size;
+        assert!(!reports.is_empty() && reports.len() <= PROGRESS_STEPS as usize);
+        assert!(reports.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert!(reports
+            .iter()
+            .all(|&(_, reported_total)| reported_total == total));
+        assert_eq!(reports.last(), Some(&(total, total)));
+        assert_eq!(
+            serde_json::to_value(&index)?,
+            serde_json::to_value(indexer.build_index())?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_category_lines() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Line 1")?;
+        writeln!(temp_file, "LogNet: Warning: Line 2")?;
+        writeln!(temp_file, "  continuation")?;
+        writeln!(temp_file, "LogNet: Error: Line 4")?;
+        writeln!(temp_file, "LogInit: Display: Line 5")?;
+        writeln!(temp_file, "LogNet: Display: Line 6")?;
+
+        let indexer = FileIndexer::open(temp_file.path())?;
+        assert!(indexer.build_index().category_lines.is_none());
+
+        let index = indexer.build_index_with_category_lines(2);
+        let category_lines = index.category_lines.expect("category lines requested");
+        assert_eq!(category_lines["LogInit"], vec![1, 5]);
+        // 每个类别最多记录 2 行，计数不受影响
+        assert_eq!(category_lines["LogNet"], vec![2, 4]);
+        assert_eq!(index.categories["LogNet"], 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_index_after_append() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=1500 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+        // 末尾的行还没写完
+        write!(temp_file, "LogNet: Warn")?;
+        temp_file.flush()?;
+
+        let indexer = FileIndexer::open(temp_file.path())?;
+        let mut index = indexer.build_index();
+        assert_eq!(index.total_lines, 1501);
+
+        writeln!(temp_file, "ing: Slow packet")?;
+        for i in 1..=700 {
+            writeln!(temp_file, "LogCore: Error: Failure {}", i)?;
+        }
+        temp_file.flush()?;
+
+        let new_lines = indexer.update_index(&mut index)?;
+        let rebuilt = FileIndexer::open(temp_file.path())?.build_index();
+
+        assert_eq!(new_lines, 700);
+        assert_eq!(index.total_lines, 2201);
+        assert_eq!(index.total_lines, rebuilt.total_lines);
+        assert_eq!(index.file_size, rebuilt.file_size);
+        assert_eq!(index.line_offsets, rebuilt.line_offsets);
+        assert_eq!(index.dense_line_offsets, rebuilt.dense_line_offsets);
+        assert_eq!(index.categories, rebuilt.categories);
+        assert_eq!(index.level_counts, rebuilt.level_counts);
+        assert_eq!(index.categories.get("LogNet"), Some(&1));
+
+        // 没有变化时不产生新行
+        assert_eq!(indexer.update_index(&mut index)?, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_file_info_estimate() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Line 1")?;
+        write!(temp_file, "LogInit: Display: Line 2")?;
+        temp_file.flush()?;
+
+        let info = log_file_info(temp_file.path())?;
+        assert_eq!(info.size, 49);
+        assert_eq!(info.line_count_estimate, 2);
+        assert!(info.modified > 0);
+
+        // 大文件按开头的平均行长估算
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 0..100_000 {
+            writeln!(temp_file, "LogStreaming: Display: Loaded asset {:06}", i)?;
+        }
+        temp_file.flush()?;
+        let estimate = log_file_info(temp_file.path())?.line_count_estimate;
+        assert!((99_000..=101_000).contains(&estimate), "{}", estimate);
+
+        assert_eq!(estimate_lines(b"", 0), 0);
+        assert_eq!(estimate_lines(b"no newline yet", 1 << 20), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_resumes_from_last_indexed_offset() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Line 1")?;
+        writeln!(temp_file, "LogInit: Display: Line 2")?;
+        write!(temp_file, "LogNet: Warn")?;
+        temp_file.flush()?;
+
+        let indexer = FileIndexer::open(temp_file.path())?;
+        let mut index = indexer.build_index();
+        let complete = "LogInit: Display: Line 1\n".len() as u64 * 2;
+        assert_eq!(index.last_indexed_offset, complete);
+        assert_eq!(index.total_lines, 3);
+
+        // 末尾不完整的行从它的行首重新扫描，撤销按半行统计的结果
+        writeln!(temp_file, "ing: Slow packet")?;
+        writeln!(temp_file, "LogCore: Error: Failure")?;
+        temp_file.flush()?;
+
+        assert_eq!(indexer.update_index(&mut index)?, 1);
+        assert_eq!(index.total_lines, 4);
+        assert_eq!(index.last_indexed_offset, index.file_size);
+        assert_eq!(index.categories.get("LogInit"), Some(&2));
+        assert_eq!(index.categories.get("LogNet"), Some(&1));
+        assert_eq!(index.level_counts.get("warning"), Some(&1));
+        assert_eq!(index.categories.get("LogCore"), Some(&1));
+
+        // 持久化后仍可继续增量更新
+        let sidecar = FileIndex::sidecar_path(temp_file.path());
+        index.save_to(&sidecar)?;
+        let mut loaded = FileIndex::load_from(&sidecar)?;
+        std::fs::remove_file(&sidecar)?;
+        assert_eq!(loaded.last_indexed_offset, index.last_indexed_offset);
+
+        writeln!(temp_file, "LogCore: Error: Failure")?;
+        temp_file.flush()?;
+        assert_eq!(indexer.update_index(&mut loaded)?, 1);
+        assert_eq!(loaded.categories.get("LogCore"), Some(&2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_final_line_without_newline() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=250 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+        write!(temp_file, "LogCrash: Error: Unhandled exception")?;
+        temp_file.flush()?;
+
+        let mut index = index_file(temp_file.path())?;
+        assert_eq!(index.total_lines, 251);
+        assert_eq!(index.level_counts.get("error"), Some(&1));
+        assert_eq!(index.categories.get("LogCrash"), Some(&1));
+
+        // 只用稀疏偏移也能读到最后一行
+        index.dense_offsets = false;
+        index.dense_line_offsets.clear();
+        let options = crate::parser::SearchOptions {
+            pattern: "Unhandled".to_string(),
+            ..Default::default()
+        };
+        let results = crate::search::search(temp_file.path(), &index, &options)
+            .unwrap()
+            .results;
+        assert_eq!(results.first().map(|r| r.line_number), Some(251));
+
+        let mut reader = crate::streaming::LineReader::from_index(temp_file.path(), index)?;
+        let chunk = reader.read_range(250, 251)?;
+        assert_eq!(chunk.end_line, 251);
+        assert_eq!(
+            &*chunk.entries[1].raw,
+            "LogCrash: Error: Unhandled exception"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_file() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log_path = dir.path().join("Empty.log");
+        File::create(&log_path)?;
+
+        let (index, _) = load_or_index_file(&log_path)?;
+        assert_eq!(index.total_lines, 0);
+        assert_eq!(index.file_size, 0);
+        assert!(index.categories.is_empty());
+
+        let mut reader = crate::streaming::LineReader::from_index(&log_path, index)?;
+        assert!(reader.read_preview(100)?.is_empty());
+
+        // 空文件之后写入的内容可增量索引
+        let indexer = FileIndexer::open(&log_path)?;
+        let mut index = indexer.build_index();
+        assert_eq!(indexer.update_index(&mut index)?, 0);
+        std::fs::write(&log_path, "LogInit: Display: Ready\n")?;
+        assert_eq!(indexer.update_index(&mut index)?, 1);
+        assert_eq!(index.level_counts.get("display"), Some(&1));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_index_after_truncate() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Line 1")?;
+        writeln!(temp_file, "LogInit: Display: Line 2")?;
+        temp_file.flush()?;
+
+        let indexer = FileIndexer::open(temp_file.path())?;
+        let mut index = indexer.build_index();
+
+        temp_file.as_file().set_len(10)?;
+
+        let err = indexer.update_index(&mut index).unwrap_err();
+        assert!(err.to_string().starts_with(FILE_TRUNCATED));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_update_index_after_rotation() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Line 1")?;
+        writeln!(temp_file, "LogInit: Display: Line 2")?;
+        temp_file.flush()?;
+
+        let indexer = FileIndexer::open(temp_file.path())?;
+        let mut index = indexer.build_index();
+
+        // 轮转: 同一路径换成更大的新文件
+        let mut rotated = temp_file.reopen()?;
+        rotated.set_len(0)?;
+        for i in 1..=5 {
+            writeln!(rotated, "LogNet: Error: Rotated {}", i)?;
+        }
+        rotated.flush()?;
+
+        let err = indexer.update_index(&mut index).unwrap_err();
+        assert!(err.to_string().starts_with(FILE_TRUNCATED));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reuse_persisted_index() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log_path = dir.path().join("Game.log");
+        let mut file = File::create(&log_path)?;
+        writeln!(file, "LogInit: Display: Line 1")?;
+        writeln!(file, "LogWindows: Error: Line 2")?;
+        drop(file);
+
+        let (built, reused) = load_or_index_file(&log_path)?;
+        assert!(!reused);
+        assert!(FileIndex::sidecar_path(&log_path).exists());
+
+        let (loaded, reused) = load_or_index_file(&log_path)?;
+        assert!(reused);
+        assert_eq!(loaded.total_lines, built.total_lines);
+        assert_eq!(loaded.dense_line_offsets, built.dense_line_offsets);
+        assert_eq!(loaded.categories, built.categories);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resume_persisted_index_after_append() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log_path = dir.path().join("Game.log");
+        let mut file = File::create(&log_path)?;
+        writeln!(file, "LogInit: Display: Line 1")?;
+        write!(file, "LogNet: Warn")?;
+        drop(file);
+        load_or_index_file(&log_path)?;
+
+        let mut file = std::fs::OpenOptions::new().append(true).open(&log_path)?;
+        writeln!(file, "ing: Slow packet")?;
+        writeln!(file, "LogCore: Error: Failure")?;
+        drop(file);
+
+        let (resumed, reused) = load_or_index_file(&log_path)?;
+        let rebuilt = index_file(&log_path)?;
+        assert!(reused);
+        assert_eq!(resumed.total_lines, 3);
+        assert_eq!(resumed.last_indexed_offset, rebuilt.last_indexed_offset);
+        assert_eq!(resumed.dense_line_offsets, rebuilt.dense_line_offsets);
+        assert_eq!(resumed.level_counts, rebuilt.level_counts);
+        assert_eq!(resumed.content_hash, rebuilt.content_hash);
+
+        // 续写后的 sidecar 可直接复用
+        assert!(load_or_index_file(&log_path)?.1);
+
+        // 已索引的内容被改写后不能续用
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(&log_path)?
+            .write_all(b"LogEdit")?;
+        let mut file = std::fs::OpenOptions::new().append(true).open(&log_path)?;
+        writeln!(file, "LogCore: Error: Failure")?;
+        drop(file);
+        let (_, reused) = load_or_index_file(&log_path)?;
+        assert!(!reused);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reindex_on_stale_or_corrupt_sidecar() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log_path = dir.path().join("Game.log");
+        std::fs::write(&log_path, "LogInit: Display: Line 1\n")?;
+        let sidecar = FileIndex::sidecar_path(&log_path);
+
+        // 无法解析的 sidecar 被忽略并重写
+        std::fs::write(&sidecar, b"not an index")?;
+        let (index, reused) = load_or_index_file(&log_path)?;
+        assert!(!reused);
+        assert_eq!(index.total_lines, 1);
+        assert!(FileIndex::load_from(&sidecar).is_ok());
+
+        // 文件被改写后 sidecar 过期 (只是追加时会续用，见 test_resume_persisted_index_after_append)
+        std::fs::write(&log_path, "LogNet: Error: Line 1\nLogNet: Error: Line 2\n")?;
+
+        let (index, reused) = load_or_index_file(&log_path)?;
+        assert!(!reused);
+        assert_eq!(index.total_lines, 2);
+        assert!(load_or_index_file(&log_path)?.1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reindex_on_same_size_edit() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let log_path = dir.path().join("Game.log");
+        std::fs::write(
+            &log_path,
+            "LogInit: Display: Line 1\nLogNet: Error: Line 2\n",
+        )?;
+
+        let (built, _) = load_or_index_file(&log_path)?;
+        assert_eq!(
+            built.content_signature(),
+            file_signature(&log_path)?,
+            "mmap and sampled reads must hash identically"
+        );
+        assert!(load_or_index_file(&log_path)?.1);
+
+        // 原地改写为同样大小的内容，并还原修改时间
+        let modified = std::fs::metadata(&log_path)?.modified()?;
+        std::fs::write(
+            &log_path,
+            "LogInit: Display: Line 1\nLogNet: Error: Line 3\n",
+        )?;
+        File::options()
+            .write(true)
+            .open(&log_path)?
+            .set_modified(modified)?;
+
+        let (index, reused) = load_or_index_file(&log_path)?;
+        assert!(!reused);
+        assert_eq!(index.file_size, built.file_size);
+        assert_ne!(index.content_hash, built.content_hash);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sample_hash_large_file() -> std::io::Result<()> {
+        // 超过两倍采样长度时只哈希首尾
+        let mut data = vec![b'a'; SIGNATURE_SAMPLE_BYTES * 3];
+        let mut temp_file = tempfile::NamedTempFile::new()?;
+        temp_file.write_all(&data)?;
+        temp_file.flush()?;
+        let hash = sample_hash(&data);
+        assert_eq!(file_signature(temp_file.path())?.content_hash, Some(hash));
+
+        data[SIGNATURE_SAMPLE_BYTES * 3 / 2] = b'b';
+        assert_eq!(sample_hash(&data), hash);
+        data[0] = b'b';
+        assert_ne!(sample_hash(&data), hash);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_adaptive_index_interval() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=2600 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+
+        let mut index = FileIndexer::open(temp_file.path())?.build_index();
+        assert_eq!(index.index_interval, FileIndex::MIN_INDEX_INTERVAL);
+        assert_eq!(index.line_offsets.len(), 27);
+
+        // 只用稀疏偏移定位: 回退到第 2501 行的行首
+        index.dense_offsets = false;
+        let (offset, lines_before) = index.seek_position(2550);
+        assert_eq!(lines_before, 2500);
+        let data = std::fs::read(temp_file.path())?;
+        assert!(data[offset as usize..].starts_with(b"LogInit: Display: Line 2501\n"));
+
+        assert_eq!(FileIndex::interval_for_size(1 << 30), 1073);
+        assert_eq!(
+            FileIndex::interval_for_size(u64::MAX),
+            FileIndex::MAX_INDEX_INTERVAL
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_small_file_dense_offsets() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Line 1")?;
+        writeln!(temp_file, "LogWindows: Error: Line 2")?;
+        write!(temp_file, "LogCore: Warning: Line 3")?;
+
+        let index = FileIndexer::open(temp_file.path())?.build_index();
+
+        assert!(index.dense_offsets);
+        assert_eq!(index.dense_line_offsets, vec![0, 25, 51]);
+        assert_eq!(index.seek_position(3), (51, 2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sample_categories() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 0..50 {
+            let category = ["LogInit", "LogNet", "LogCore"][i % 3];
+            writeln!(temp_file, "{}: Display: Line {}", category, i)?;
+        }
+        for i in 0..50 {
+            writeln!(temp_file, "LogLate: Display: Line {}", i)?;
+        }
+
+        let sample = sample_categories(temp_file.path(), 50, 1000)?;
+        assert_eq!(sample.categories, vec!["LogInit", "LogNet", "LogCore"]);
+        assert_eq!(sample.lines_scanned, 50);
+        assert!(!sample.complete);
+
+        // 类别稳定后提前结束
+        let sample = sample_categories(temp_file.path(), 1000, 10)?;
+        assert_eq!(sample.lines_scanned, 13);
+        assert!(!sample.complete);
+
+        let sample = sample_categories(temp_file.path(), 1000, 1000)?;
+        assert_eq!(sample.categories.len(), 4);
+        assert!(sample.complete);
+
+        Ok(())
+    }
 }
@@ -2,29 +2,186 @@
 //!
 //! 使用 Seek 和缓存优化大文件的随机访问
 
-use parking_lot::RwLock;
-use std::collections::HashMap;
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::collections::HashSet;
 use std::fs::File;
-use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::num::NonZeroUsize;
 use std::path::Path;
 use std::sync::Arc;
 
-use crate::parser::{FileIndex, LogChunk, LogEntry, LogParser};
+use crate::parser::patterns::PATTERN_HEADER;
+use crate::parser::{
+    Encoding, FileIndex, LineFormat, LogChunk, LogEntry, LogParser, ParseStats, ParserConfig,
+    SessionBoundary, Timestamp, UnparsedSample,
+};
+use crate::streaming::buffered;
+use crate::streaming::encoding::{decode_lines_lossy, newline_spans, LossyLines};
 
-/// LRU 缓存大小
-const CACHE_SIZE: usize = 100;
+/// 默认 LRU 缓存的块数
+pub const CACHE_SIZE: usize = 100;
+
+/// 默认缓存的字节上限 (64 MB)
+pub const DEFAULT_CACHE_BYTES: usize = 64 * 1024 * 1024;
+
+/// 帧号至少回退这么多才视为新会话
+const FRAME_RESET_MIN_DROP: u64 = 100;
+
+/// UE 输出的帧号为 GFrameCounter % 1000
+const FRAME_WRAP: u64 = 1000;
+
+/// 从 [FRAME_WRAP - 窗口, FRAME_WRAP) 回到 [0, 窗口) 视为正常回绕而非重启
+const FRAME_WRAP_WINDOW: u64 = 100;
+
+/// 帧号从 previous 变为 frame 是否意味着重新开始计数
+fn is_frame_reset(previous: u64, frame: u64) -> bool {
+    if previous < frame + FRAME_RESET_MIN_DROP {
+        return false;
+    }
+    let wrapped = (FRAME_WRAP - FRAME_WRAP_WINDOW..FRAME_WRAP).contains(&previous)
+        && frame < FRAME_WRAP_WINDOW;
+    !wrapped
+}
 
 /// 块缓存项
 struct CacheItem {
     entries: Vec<LogEntry>,
-    access_time: std::time::Instant,
+    /// 条目文本的近似堆大小
+    bytes: usize,
+}
+
+/// 块缓存: 同时按块数和字节数限制，超出时淘汰最久未读取的块
+struct ChunkCache {
+    chunks: LruCache<u64, CacheItem>,
+    bytes: usize,
+    max_bytes: usize,
+}
+
+impl ChunkCache {
+    fn new(capacity: NonZeroUsize, max_bytes: usize) -> Self {
+        Self {
+            chunks: LruCache::new(capacity),
+            bytes: 0,
+            max_bytes,
+        }
+    }
+
+    /// 读取块并更新其最近读取时间
+    fn get(&mut self, chunk_index: u64) -> Option<&CacheItem> {
+        self.chunks.get(&chunk_index)
+    }
+
+    /// 放入块；单块超过字节上限时不缓存
+    fn put(&mut self, chunk_index: u64, entries: Vec<LogEntry>) {
+        self.pop(chunk_index);
+
+        let bytes = entries
+            .iter()
+            .map(|e| e.raw.len() + e.message.as_ref().map_or(0, |m| m.len()))
+            .sum();
+        if bytes > self.max_bytes {
+            return;
+        }
+
+        if let Some((_, evicted)) = self.chunks.push(chunk_index, CacheItem { entries, bytes }) {
+            self.bytes -= evicted.bytes;
+        }
+        self.bytes += bytes;
+        self.shrink();
+    }
+
+    fn pop(&mut self, chunk_index: u64) {
+        if let Some(item) = self.chunks.pop(&chunk_index) {
+            self.bytes -= item.bytes;
+        }
+    }
+
+    fn set_max_bytes(&mut self, max_bytes: usize) {
+        self.max_bytes = max_bytes;
+        self.shrink();
+    }
+
+    /// 淘汰最久未读取的块，直到总字节数不超过上限
+    fn shrink(&mut self) {
+        while self.bytes > self.max_bytes {
+            match self.chunks.pop_lru() {
+                Some((_, item)) => self.bytes -= item.bytes,
+                None => break,
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        self.chunks.clear();
+        self.bytes = 0;
+    }
+}
+
+/// 类别字符串池: 相同类别的条目共享同一份存储
+struct CategoryPool {
+    enabled: bool,
+    pool: HashSet<Arc<str>>,
+}
+
+impl CategoryPool {
+    /// 将条目的类别替换为池中的共享副本
+    fn intern(&mut self, entry: &mut LogEntry) {
+        if !self.enabled {
+            return;
+        }
+        if let Some(category) = entry.category.take() {
+            let shared = match self.pool.get(&*category) {
+                Some(existing) => existing.clone(),
+                None => {
+                    self.pool.insert(category.clone());
+                    category
+                }
+            };
+            entry.category = Some(shared);
+        }
+    }
+}
+
+/// 顺序解析整个文件的迭代器 (不经过块缓存，内存占用恒定)
+pub struct EntryIterator<'a> {
+    lines: LossyLines<BufReader<&'a File>>,
+    parser_config: &'a ParserConfig,
+    category_pool: &'a mut CategoryPool,
+    line_number: u64,
+    /// 在此之前的行只跳过不解析
+    start_line: u64,
+}
+
+impl Iterator for EntryIterator<'_> {
+    type Item = std::io::Result<LogEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (line, invalid_utf8) = loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+            self.line_number += 1;
+            if self.line_number >= self.start_line {
+                break line;
+            }
+        };
+
+        let mut entry = LogParser::parse_line_with(self.parser_config, self.line_number, &line);
+        entry.has_invalid_utf8 = invalid_utf8;
+        self.category_pool.intern(&mut entry);
+        Some(Ok(entry))
+    }
 }
 
 /// 行读取器
 pub struct LineReader {
     file: File,
     index: FileIndex,
-    cache: Arc<RwLock<HashMap<u64, CacheItem>>>,
+    cache: Arc<Mutex<ChunkCache>>,
+    parser_config: ParserConfig,
+    category_pool: CategoryPool,
 }
 
 impl LineReader {
@@ -35,10 +192,46 @@ impl LineReader {
         Ok(Self {
             file,
             index,
-            cache: Arc::new(RwLock::new(HashMap::new())),
+            cache: Arc::new(Mutex::new(ChunkCache::new(
+                Self::cache_capacity(CACHE_SIZE),
+                DEFAULT_CACHE_BYTES,
+            ))),
+            parser_config: ParserConfig::default(),
+            category_pool: CategoryPool {
+                enabled: true,
+                pool: HashSet::new(),
+            },
         })
     }
 
+    /// 设置缓存的块数 (默认 CACHE_SIZE，至少为 1)，超出的最久未读取的块被淘汰
+    pub fn with_cache_size(self, chunks: usize) -> Self {
+        self.cache
+            .lock()
+            .chunks
+            .resize(Self::cache_capacity(chunks));
+        self
+    }
+
+    /// 设置缓存的字节上限 (默认 DEFAULT_CACHE_BYTES)，按条目文本长度估算
+    ///
+    /// 立即淘汰超出的块；超过上限的单个块不会被缓存
+    pub fn set_cache_limit(&self, bytes: usize) {
+        self.cache.lock().set_max_bytes(bytes);
+    }
+
+    fn cache_capacity(chunks: usize) -> NonZeroUsize {
+        NonZeroUsize::new(chunks).unwrap_or(NonZeroUsize::MIN)
+    }
+
+    /// 启用/禁用类别字符串池 (默认启用)
+    pub fn set_category_pooling(&mut self, enabled: bool) {
+        self.category_pool.enabled = enabled;
+        if !enabled {
+            self.category_pool.pool.clear();
+        }
+    }
+
     /// 读取指定范围的行
     pub fn read_range(&mut self, start_line: u64, end_line: u64) -> std::io::Result<LogChunk> {
         // 限制范围
@@ -50,16 +243,18 @@ impl LineReader {
                 start_line,
                 end_line: start_line,
                 entries: vec![],
+                read_errors: vec![],
             });
         }
 
-        // 计算块索引
-        let chunk_index = start_line / FileIndex::INDEX_INTERVAL;
+        // 计算块索引 (与稀疏偏移的分块一致)
+        let chunk_index = self.chunk_of(start_line);
 
         // 检查缓存
         {
-            let cache = self.cache.read();
-            if let Some(item) = cache.get(&chunk_index) {
+            // 命中时更新该块的最近读取时间
+            let mut cache = self.cache.lock();
+            if let Some(item) = cache.get(chunk_index) {
                 // 从缓存中提取需要的行
                 let entries: Vec<LogEntry> = item
                     .entries
@@ -68,39 +263,73 @@ impl LineReader {
                     .cloned()
                     .collect();
 
-                if !entries.is_empty() {
+                // 缓存的块可能只读到了之前请求的结束行，或范围跨越了块边界，
+                // 请求的每一行都在缓存中才能直接返回
+                let covered = entries.len() as u64 == end_line - start_line + 1;
+                if covered {
                     return Ok(LogChunk {
                         start_line,
                         end_line,
                         entries,
+                        read_errors: vec![],
                     });
                 }
             }
         }
 
-        // 计算文件偏移
-        let offset_index = (start_line / FileIndex::INDEX_INTERVAL) as usize;
-        let file_offset = if offset_index < self.index.line_offsets.len() {
-            self.index.line_offsets[offset_index]
-        } else {
-            0
-        };
+        // 从块的第一行开始读取，写入缓存的块与按行号计算的块索引对齐
+        // (完整偏移索引也不直接定位到起始行)
+        let chunk_start = chunk_index * self.index.index_interval.max(1) + 1;
+        let (file_offset, lines_before) = self.index.seek_position(chunk_start);
 
-        // 定位到起始位置
-        self.file.seek(SeekFrom::Start(file_offset))?;
+        // 定位到起始位置 (用独立的句柄读取，解析时仍可更新分类池)
+        let mut file = self.file.try_clone()?;
+        file.seek(SeekFrom::Start(file_offset))?;
+        let lines = decode_lines_lossy(buffered(file), self.index.encoding);
+
+        Ok(self.collect_range(lines, lines_before, start_line, end_line))
+    }
 
-        // 读取行
-        let reader = BufReader::new(&self.file);
+    /// 从 lines_before 之后逐行解析到 end_line，并把读到的块写入缓存
+    ///
+    /// lines_before 须在块边界上 (稀疏索引点或块的第一行之前)，否则不缓存。
+    /// 单行读取失败时以标记条目占位并记入 read_errors，继续读取后续行；
+    /// 含失败行的块不缓存，下次读取时重试
+    fn collect_range(
+        &mut self,
+        lines: impl Iterator<Item = std::io::Result<(String, bool)>>,
+        lines_before: u64,
+        start_line: u64,
+        end_line: u64,
+    ) -> LogChunk {
+        let interval = self.index.index_interval.max(1);
+        let aligned = lines_before % interval == 0;
+        let mut chunk_index = lines_before / interval;
+        let mut current_line = lines_before;
         let mut entries: Vec<LogEntry> = Vec::new();
-        let mut current_line = (offset_index as u64) * FileIndex::INDEX_INTERVAL;
         let mut chunk_entries: Vec<LogEntry> = Vec::new();
+        let mut chunk_failed = false;
+        let mut read_errors = Vec::new();
 
-        for line_result in reader.lines() {
+        for line_result in lines {
             current_line += 1;
-            let line = line_result?;
 
-            // 解析日志行
-            let entry = LogParser::parse_line(current_line, &line);
+            let entry = match line_result {
+                Ok((line, invalid_utf8)) => {
+                    // 解析日志行
+                    let mut entry =
+                        LogParser::parse_line_with(&self.parser_config, current_line, &line);
+                    entry.has_invalid_utf8 = invalid_utf8;
+                    self.category_pool.intern(&mut entry);
+                    entry
+                }
+                Err(e) => {
+                    log::warn!("Failed to read line {}: {}", current_line, e);
+                    chunk_failed = true;
+                    read_errors.push((current_line, e.to_string()));
+                    LogEntry::raw(current_line, format!("<read error: {}>", e))
+                }
+            };
 
             // 保存到块缓存
             chunk_entries.push(entry.clone());
@@ -116,21 +345,72 @@ impl LineReader {
             }
 
             // 如果已经读满一个块，缓存它
-            if chunk_entries.len() >= FileIndex::INDEX_INTERVAL as usize {
-                self.cache_chunk(chunk_index, chunk_entries.clone());
+            if chunk_entries.len() as u64 >= interval {
+                if aligned && !chunk_failed {
+                    self.cache_chunk(chunk_index, chunk_entries.clone());
+                }
                 chunk_entries.clear();
+                chunk_failed = false;
+                chunk_index += 1;
             }
         }
 
         // 缓存最后一个不完整的块
-        if !chunk_entries.is_empty() {
+        if aligned && !chunk_entries.is_empty() && !chunk_failed {
             self.cache_chunk(chunk_index, chunk_entries);
         }
 
-        Ok(LogChunk {
+        LogChunk {
             start_line,
             end_line: current_line.min(end_line),
             entries,
+            read_errors,
+        }
+    }
+
+    /// 读取以 line_number 为中心、前后各 radius 行的范围 (越过文件边界的部分截掉)
+    pub fn peek_around(&mut self, line_number: u64, radius: u64) -> std::io::Result<LogChunk> {
+        self.read_range(
+            line_number.saturating_sub(radius),
+            line_number.saturating_add(radius),
+        )
+    }
+
+    /// 读取行范围，并将续行合并到其所属的日志行
+    ///
+    /// 续行的文本以 \n 追加到所属条目的 message，本身不再单独返回；
+    /// 范围开头没有所属日志行的续行保持原样
+    pub fn read_range_merged(
+        &mut self,
+        start_line: u64,
+        end_line: u64,
+    ) -> std::io::Result<LogChunk> {
+        let chunk = self.read_range(start_line, end_line)?;
+        Ok(LogChunk {
+            entries: merge_continuations(chunk.entries),
+            ..chunk
+        })
+    }
+
+    /// 从文件开头逐行解析全部条目
+    ///
+    /// 不经过块缓存，适合后端统计等需要遍历整个文件的场景
+    pub fn entries(&mut self) -> std::io::Result<EntryIterator<'_>> {
+        self.entries_from(1)
+    }
+
+    /// 从 start_line 开始逐行解析到文件末尾 (借助索引定位，不经过块缓存)
+    pub fn entries_from(&mut self, start_line: u64) -> std::io::Result<EntryIterator<'_>> {
+        let (file_offset, lines_before) = self.index.seek_position(start_line.max(1));
+        let file_offset = file_offset.max(self.index.encoding.bom_len() as u64);
+        self.file.seek(SeekFrom::Start(file_offset))?;
+
+        Ok(EntryIterator {
+            lines: decode_lines_lossy(buffered(&self.file), self.index.encoding),
+            parser_config: &self.parser_config,
+            category_pool: &mut self.category_pool,
+            line_number: lines_before,
+            start_line,
         })
     }
 
@@ -140,6 +420,96 @@ impl LineReader {
         Ok(chunk.entries.into_iter().next())
     }
 
+    /// 读取指定范围，相邻的完全相同的行折叠为一条 (保留第一行的行号)
+    ///
+    /// 只折叠相邻的重复，不改变顺序；每条结果的 repeat_count 为其代表的行数
+    pub fn read_range_deduped(
+        &mut self,
+        start_line: u64,
+        end_line: u64,
+    ) -> std::io::Result<LogChunk> {
+        let chunk = self.read_range(start_line, end_line)?;
+        Ok(LogChunk {
+            entries: collapse_repeats(chunk.entries),
+            ..chunk
+        })
+    }
+
+    /// 字节偏移所在的行号
+    ///
+    /// 从最近的索引点读到 offset 并统计换行；BOM 内的偏移视为第 1 行，
+    /// 超出文件末尾时返回最后一行
+    pub fn byte_offset_to_line(&mut self, offset: u64) -> std::io::Result<u64> {
+        let total_lines = self.index.total_lines;
+        let offset = offset.min(self.index.file_size);
+        let (line_start, lines_before) = self.index.seek_position_for_byte(offset);
+
+        let mut bytes = Vec::new();
+        self.file.seek(SeekFrom::Start(line_start))?;
+        (&self.file)
+            .take(offset.saturating_sub(line_start))
+            .read_to_end(&mut bytes)?;
+        let newlines = newline_spans(&bytes, 0, bytes.len(), self.index.encoding).count() as u64;
+
+        Ok((lines_before + newlines + 1).min(total_lines.max(1)))
+    }
+
+    /// 某行起始的字节偏移，行号越界时返回 None
+    ///
+    /// 从最近的索引点向后数换行，最多读取到下一个索引点
+    pub fn line_to_byte_offset(&mut self, line_number: u64) -> std::io::Result<Option<u64>> {
+        if line_number == 0 || line_number > self.index.total_lines {
+            return Ok(None);
+        }
+
+        let (anchor, lines_before) = self.index.seek_position(line_number);
+        let skip = line_number - 1 - lines_before;
+        if skip == 0 {
+            return Ok(Some(anchor));
+        }
+
+        let block = (lines_before / self.index.index_interval.max(1)) as usize;
+        let next_anchor = self
+            .index
+            .line_offsets
+            .get(block + 1)
+            .copied()
+            .unwrap_or(self.index.file_size);
+
+        let mut bytes = Vec::new();
+        self.file.seek(SeekFrom::Start(anchor))?;
+        (&self.file)
+            .take(next_anchor.saturating_sub(anchor))
+            .read_to_end(&mut bytes)?;
+
+        let line_start = newline_spans(&bytes, 0, bytes.len(), self.index.encoding)
+            .nth(skip as usize - 1)
+            .map(|(_, next)| anchor + next as u64);
+        Ok(line_start)
+    }
+
+    /// 读取覆盖字节范围 [start_byte, end_byte) 的所有行，行号与按行读取一致
+    ///
+    /// 用于对接只报告字节偏移的外部工具；范围为空时返回空块
+    pub fn read_byte_range(&mut self, start_byte: u64, end_byte: u64) -> std::io::Result<LogChunk> {
+        let start_line = self.byte_offset_to_line(start_byte)?;
+        let end_line = if end_byte > start_byte {
+            self.byte_offset_to_line(end_byte - 1)?
+        } else {
+            start_line - 1
+        };
+        self.read_range(start_line, end_line)
+    }
+
+    /// 读取末尾 N 行 (文件不足 N 行时返回全部)
+    ///
+    /// 由 read_range 按索引定位到起始行，不从文件开头读起
+    pub fn read_tail(&mut self, count: u64) -> std::io::Result<LogChunk> {
+        let total_lines = self.index.total_lines;
+        let start = total_lines.saturating_sub(count) + 1;
+        self.read_range(start, total_lines)
+    }
+
     /// 读取预览 (前 N 行)
     pub fn read_preview(&mut self, count: u64) -> std::io::Result<Vec<LogEntry>> {
         let end = count.min(self.index.total_lines);
@@ -149,33 +519,441 @@ impl LineReader {
 
     /// 缓存块
     fn cache_chunk(&self, chunk_index: u64, entries: Vec<LogEntry>) {
-        let mut cache = self.cache.write();
+        // 缓存满时淘汰最久未读取的块
+        self.cache.lock().put(chunk_index, entries);
+    }
 
-        // 简单的 LRU: 如果缓存满了，移除最旧的项
-        if cache.len() >= CACHE_SIZE {
-            let oldest_key = cache
-                .iter()
-                .min_by_key(|(_, v)| v.access_time)
-                .map(|(k, _)| *k);
+    /// 清除缓存
+    pub fn clear_cache(&self) {
+        let mut cache = self.cache.lock();
+        cache.clear();
+    }
+
+    /// 清除与指定行范围有重叠的缓存块 (按块中条目的实际行号判断)
+    pub fn invalidate_range(&self, start_line: u64, end_line: u64) {
+        let mut cache = self.cache.lock();
+        let stale: Vec<u64> = cache
+            .chunks
+            .iter()
+            .filter(|(_, item)| {
+                let first = item.entries.first().map_or(u64::MAX, |e| e.line_number);
+                let last = item.entries.last().map_or(0, |e| e.line_number);
+                first <= end_line && last >= start_line
+            })
+            .map(|(chunk_index, _)| *chunk_index)
+            .collect();
+        for chunk_index in stale {
+            cache.pop(chunk_index);
+        }
+    }
+
+    /// 设置解析器配置 (只影响之后解析的行，已缓存的块需重新解析)
+    pub fn set_parser_config(&mut self, config: ParserConfig) {
+        self.parser_config = config;
+    }
+
+    /// 获取解析器配置
+    pub fn parser_config(&self) -> &ParserConfig {
+        &self.parser_config
+    }
+
+    /// 按当前解析配置重新读取并解析指定范围
+    pub fn reparse_range(&mut self, start_line: u64, end_line: u64) -> std::io::Result<LogChunk> {
+        self.invalidate_range(start_line, end_line);
+        self.read_range(start_line, end_line)
+    }
 
-            if let Some(key) = oldest_key {
-                cache.remove(&key);
+    /// 从头扫描，收集最多 limit 个未能解析的行 (没匹配任何格式，即 parse_stats 中计为 raw 的行)
+    ///
+    /// 不经过块缓存，用于帮助用户发现解析器未覆盖的日志格式
+    pub fn scan_unparsed(&mut self, limit: usize) -> std::io::Result<Vec<UnparsedSample>> {
+        let mut samples = Vec::new();
+        if limit == 0 {
+            return Ok(samples);
+        }
+
+        self.file
+            .seek(SeekFrom::Start(self.index.encoding.bom_len() as u64))?;
+        let reader = buffered(&self.file);
+
+        for (i, line_result) in lossy_text(reader, self.index.encoding).enumerate() {
+            let line = line_result?;
+            let (format, entry) =
+                LogParser::parse_line_format(&self.parser_config, i as u64 + 1, &line);
+
+            // 与 parse_stats 一致: 只有没匹配任何格式的行算未解析
+            if format == LineFormat::Raw {
+                samples.push(UnparsedSample {
+                    line_number: entry.line_number,
+                    raw: entry.raw.to_string(),
+                });
+                if samples.len() >= limit {
+                    break;
+                }
             }
         }
 
-        cache.insert(
-            chunk_index,
-            CacheItem {
-                entries,
-                access_time: std::time::Instant::now(),
-            },
-        );
+        Ok(samples)
     }
 
-    /// 清除缓存
-    pub fn clear_cache(&self) {
-        let mut cache = self.cache.write();
-        cache.clear();
+    /// 单次流式扫描，统计各解析分支命中的行数
+    pub fn parse_stats(&mut self) -> std::io::Result<ParseStats> {
+        self.file
+            .seek(SeekFrom::Start(self.index.encoding.bom_len() as u64))?;
+        let reader = buffered(&self.file);
+
+        let mut stats = ParseStats::default();
+        for (i, line_result) in lossy_text(reader, self.index.encoding).enumerate() {
+            let line = line_result?;
+            let (format, _) =
+                LogParser::parse_line_format(&self.parser_config, i as u64 + 1, &line);
+            stats.record(format);
+        }
+
+        Ok(stats)
+    }
+
+    /// 第一个带时间戳行的时间 (Unix 毫秒)，从文件开头扫描
+    pub fn first_timestamp_millis(&mut self) -> std::io::Result<Option<i64>> {
+        Ok(self
+            .first_timestamped_entry()?
+            .and_then(|entry| entry.timestamp)
+            .and_then(|timestamp| LogParser::timestamp_millis(&timestamp)))
+    }
+
+    /// 最后一个带时间戳行的时间 (Unix 毫秒)，从文件末尾按索引块向前扫描
+    pub fn last_timestamp_millis(&mut self) -> std::io::Result<Option<i64>> {
+        Ok(self
+            .last_timestamped_entry()?
+            .and_then(|entry| entry.timestamp)
+            .and_then(|timestamp| LogParser::timestamp_millis(&timestamp)))
+    }
+
+    /// 第一个和最后一个带时间戳行的原始时间戳，文件中没有时间戳时为 None
+    ///
+    /// 开头正向扫描到第一个时间戳即停止，结尾按索引块向前扫描，不会两次读完整个文件
+    pub fn time_range(&mut self) -> std::io::Result<Option<(String, String)>> {
+        let Some(first) = self.first_timestamped_entry()?.and_then(|e| e.timestamp) else {
+            return Ok(None);
+        };
+        let last = self
+            .last_timestamped_entry()?
+            .and_then(|e| e.timestamp)
+            .unwrap_or_else(|| first.clone());
+
+        Ok(Some((first, last)))
+    }
+
+    /// 第一个时间戳不早于 target 的行号 (target 为 UE 时间戳格式)
+    ///
+    /// 假定时间戳随行号递增: 先按索引块二分定位，再从该块起顺序扫描。
+    /// target 早于第一个时间戳时返回第 1 行，晚于最后一个时间戳时返回 None
+    pub fn find_line_by_timestamp(&mut self, target: &str) -> std::io::Result<Option<u64>> {
+        let target = Timestamp::parse(target).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("Invalid timestamp: {}", target),
+            )
+        })?;
+
+        let Some(first) = self.first_timestamped_entry()? else {
+            return Ok(None);
+        };
+        if first.parsed_timestamp.is_some_and(|ts| target < ts) {
+            return Ok(Some(1));
+        }
+
+        // 二分查找最后一个首个时间戳早于 target 的索引块 (没有时间戳的块视为不早于)
+        let (mut lo, mut hi) = (0, self.index.line_offsets.len());
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            let key = self
+                .read_block(mid)?
+                .into_iter()
+                .find_map(|e| e.parsed_timestamp);
+            if key.is_some_and(|ts| ts < target) {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        self.file
+            .seek(SeekFrom::Start(self.index.line_offsets[lo]))?;
+        let reader = buffered(&self.file);
+        let lines_before = lo as u64 * self.index.index_interval;
+
+        for (i, line_result) in lossy_text(reader, self.index.encoding).enumerate() {
+            let line_number = lines_before + i as u64 + 1;
+            let entry = LogParser::parse_line_with(&self.parser_config, line_number, &line_result?);
+            if entry.parsed_timestamp.is_some_and(|ts| ts >= target) {
+                return Ok(Some(line_number));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 第一个带解析时间戳的日志行 (跳过续行、文件头等)
+    fn first_timestamped_entry(&mut self) -> std::io::Result<Option<LogEntry>> {
+        self.file
+            .seek(SeekFrom::Start(self.index.encoding.bom_len() as u64))?;
+        let reader = buffered(&self.file);
+
+        for (i, line_result) in lossy_text(reader, self.index.encoding).enumerate() {
+            let entry =
+                LogParser::parse_line_with(&self.parser_config, i as u64 + 1, &line_result?);
+            if entry.parsed_timestamp.is_some() {
+                return Ok(Some(entry));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 最后一个带解析时间戳的日志行，从最后一个索引块开始向前查找
+    fn last_timestamped_entry(&mut self) -> std::io::Result<Option<LogEntry>> {
+        for block in (0..self.index.line_offsets.len()).rev() {
+            let last = self
+                .read_block(block)?
+                .into_iter()
+                .rev()
+                .find(|e| e.parsed_timestamp.is_some());
+
+            if last.is_some() {
+                return Ok(last);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 帧号范围 (最小, 最大)，没有带帧号的行时返回 None
+    ///
+    /// 帧号在会话之间会重置，不能只看首尾，需完整扫描一遍
+    pub fn frame_range(&mut self) -> std::io::Result<Option<(u64, u64)>> {
+        let mut range: Option<(u64, u64)> = None;
+        for entry in self.entries()? {
+            if let Some(frame) = entry?.frame {
+                range = Some(match range {
+                    Some((min, max)) => (min.min(frame), max.max(frame)),
+                    None => (frame, frame),
+                });
+            }
+        }
+        Ok(range)
+    }
+
+    /// 第一个帧号不小于 frame 的行号
+    ///
+    /// 帧号在会话之间会重置、整体并不单调，无法像时间戳那样按索引块二分，
+    /// 因此从头顺序扫描，找到即停；多个会话都包含目标帧时返回最早的一处
+    pub fn find_line_by_frame(&mut self, frame: u64) -> std::io::Result<Option<u64>> {
+        for entry in self.entries()? {
+            let entry = entry?;
+            if entry.frame.is_some_and(|f| f >= frame) {
+                return Ok(Some(entry.line_number));
+            }
+        }
+        Ok(None)
+    }
+
+    /// 检测时间戳回退的行 (日志轮转/重启后追加的新会话)
+    ///
+    /// 与上一个带时间戳的行比较，时间变小即视为新会话的起点
+    pub fn detect_time_resets(&mut self) -> std::io::Result<Vec<u64>> {
+        self.file
+            .seek(SeekFrom::Start(self.index.encoding.bom_len() as u64))?;
+        let reader = buffered(&self.file);
+
+        let mut resets = Vec::new();
+        let mut previous: Option<i64> = None;
+
+        for (i, line_result) in lossy_text(reader, self.index.encoding).enumerate() {
+            let Some(millis) = self.line_timestamp_millis(&line_result?) else {
+                continue;
+            };
+
+            if previous.is_some_and(|prev| millis < prev) {
+                resets.push(i as u64 + 1);
+            }
+            previous = Some(millis);
+        }
+
+        Ok(resets)
+    }
+
+    /// 检测会话边界，用于按会话切换
+    ///
+    /// 第 1 行总是第一个会话的起点；之后遇到 `Log file open,` 文件头，
+    /// 或帧号相对上一个带帧号的行大幅回退 (不含 999 -> 0 这样的正常回绕) 时开始新会话
+    pub fn detect_sessions(&mut self) -> std::io::Result<Vec<SessionBoundary>> {
+        self.file
+            .seek(SeekFrom::Start(self.index.encoding.bom_len() as u64))?;
+        let reader = buffered(&self.file);
+
+        let mut sessions = Vec::new();
+        let mut previous_frame: Option<u64> = None;
+
+        for (i, line_result) in lossy_text(reader, self.index.encoding).enumerate() {
+            let line = line_result?;
+            let entry = LogParser::parse_line_with(&self.parser_config, i as u64 + 1, &line);
+
+            let is_header = PATTERN_HEADER.is_match(&entry.raw);
+            let is_reset = previous_frame
+                .zip(entry.frame)
+                .is_some_and(|(previous, frame)| is_frame_reset(previous, frame));
+
+            if sessions.is_empty() || is_header || is_reset {
+                sessions.push(SessionBoundary {
+                    start_line: entry.line_number,
+                    timestamp: entry.timestamp,
+                });
+            }
+
+            // 文件头之后的帧号属于新会话，不与上一会话比较
+            if is_header {
+                previous_frame = None;
+            } else if entry.frame.is_some() {
+                previous_frame = entry.frame;
+            }
+        }
+
+        Ok(sessions)
+    }
+
+    /// 下一个与指定行同类别的日志行 (续行按其所属日志行的类别计算)
+    ///
+    /// 从锚点向后流式扫描，跳过续行；锚点已是最后一行时不读取文件
+    pub fn next_same_category(&mut self, line_number: u64) -> std::io::Result<Option<u64>> {
+        if line_number >= self.index.total_lines {
+            return Ok(None);
+        }
+        let Some(category) = self.effective_category(line_number)? else {
+            return Ok(None);
+        };
+
+        let start_line = line_number.saturating_add(1);
+        let (file_offset, mut current_line) = self.index.seek_position(start_line);
+        self.file.seek(SeekFrom::Start(file_offset))?;
+        let reader = buffered(&self.file);
+
+        for line_result in lossy_text(reader, self.index.encoding) {
+            current_line += 1;
+            let line = line_result?;
+            if current_line < start_line {
+                continue;
+            }
+
+            let entry = LogParser::parse_line_with(&self.parser_config, current_line, &line);
+            if !entry.is_continuation && entry.category.as_deref() == Some(&*category) {
+                return Ok(Some(current_line));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 上一个与指定行同类别的日志行 (续行按其所属日志行的类别计算)
+    ///
+    /// 从锚点所在的索引块开始按块向前扫描，跳过续行
+    pub fn prev_same_category(&mut self, line_number: u64) -> std::io::Result<Option<u64>> {
+        let Some(category) = self.effective_category(line_number)? else {
+            return Ok(None);
+        };
+
+        let Some(last_block) = self.block_of(line_number) else {
+            return Ok(None);
+        };
+        for block in (0..=last_block).rev() {
+            let found = self.read_block(block)?.into_iter().rev().find(|e| {
+                e.line_number < line_number
+                    && !e.is_continuation
+                    && e.category.as_deref() == Some(&*category)
+            });
+
+            if let Some(entry) = found {
+                return Ok(Some(entry.line_number));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 某行实际所属的类别: 普通行取自身类别，续行向前找到所属日志行
+    fn effective_category(&mut self, line_number: u64) -> std::io::Result<Option<Arc<str>>> {
+        let Some(last_block) = self.block_of(line_number) else {
+            return Ok(None);
+        };
+        for block in (0..=last_block).rev() {
+            let parent = self
+                .read_block(block)?
+                .into_iter()
+                .rev()
+                .find(|e| e.line_number <= line_number && !e.is_continuation);
+
+            if let Some(entry) = parent {
+                return Ok(entry.category);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 行号对应的缓存块索引 (第 1 到 index_interval 行为第 0 块)
+    fn chunk_of(&self, line_number: u64) -> u64 {
+        line_number.saturating_sub(1) / self.index.index_interval.max(1)
+    }
+
+    /// 行号所在的稀疏索引块 (行号越界时为 None)
+    fn block_of(&self, line_number: u64) -> Option<usize> {
+        if line_number == 0 || line_number > self.index.total_lines {
+            return None;
+        }
+        let block = ((line_number - 1) / self.index.index_interval) as usize;
+        (block < self.index.line_offsets.len()).then_some(block)
+    }
+
+    /// 读取并解析一个稀疏索引块内的全部行 (不经过块缓存)
+    fn read_block(&mut self, block: usize) -> std::io::Result<Vec<LogEntry>> {
+        let block_start = self.index.line_offsets[block];
+        let block_end = self
+            .index
+            .line_offsets
+            .get(block + 1)
+            .copied()
+            .unwrap_or(self.index.file_size);
+
+        self.file.seek(SeekFrom::Start(block_start))?;
+        let reader = buffered((&self.file).take(block_end.saturating_sub(block_start)));
+
+        let first_line = block as u64 * self.index.index_interval;
+        decode_lines_lossy(reader, self.index.encoding)
+            .enumerate()
+            .map(|(i, line_result)| {
+                let (line, invalid_utf8) = line_result?;
+                let mut entry = LogParser::parse_line_with(
+                    &self.parser_config,
+                    first_line + i as u64 + 1,
+                    &line,
+                );
+                entry.has_invalid_utf8 = invalid_utf8;
+                Ok(entry)
+            })
+            .collect()
+    }
+
+    /// 按当前解析配置解析一行的时间戳 (Unix 毫秒)
+    fn line_timestamp_millis(&self, line: &str) -> Option<i64> {
+        LogParser::parse_line_with(&self.parser_config, 0, line)
+            .timestamp
+            .and_then(|timestamp| LogParser::timestamp_millis(&timestamp))
+    }
+
+    /// 替换文件索引 (文件增长后)，并清除缓存以免返回截止于旧末尾的块
+    pub fn replace_index(&mut self, index: FileIndex) {
+        self.index = index;
+        self.clear_cache();
     }
 
     /// 获取文件索引
@@ -184,9 +962,55 @@ impl LineReader {
     }
 }
 
+/// 宽松逐行读取，只要文本 (无效 UTF-8 不会中断扫描)
+fn lossy_text<R: BufRead>(
+    reader: R,
+    encoding: Encoding,
+) -> impl Iterator<Item = std::io::Result<String>> {
+    decode_lines_lossy(reader, encoding).map(|line| line.map(|(text, _)| text))
+}
+
+/// 将续行合并到前一个非续行条目
+fn merge_continuations(entries: Vec<LogEntry>) -> Vec<LogEntry> {
+    let mut merged: Vec<LogEntry> = Vec::with_capacity(entries.len());
+
+    for entry in entries {
+        let parent = merged.last_mut().filter(|e| !e.is_continuation);
+        match parent {
+            Some(parent) if entry.is_continuation => {
+                let message = parent.message.get_or_insert_with(|| parent.raw.to_string());
+                message.push('\n');
+                message.push_str(&entry.raw);
+                parent.continuation_count += 1;
+            }
+            _ => merged.push(entry),
+        }
+    }
+
+    merged
+}
+
+/// 将原始内容相同的相邻条目折叠为一条，并记录重复次数
+fn collapse_repeats(entries: Vec<LogEntry>) -> Vec<LogEntry> {
+    let mut collapsed: Vec<LogEntry> = Vec::with_capacity(entries.len());
+
+    for mut entry in entries {
+        match collapsed.last_mut() {
+            Some(previous) if previous.raw == entry.raw => previous.repeat_count += 1,
+            _ => {
+                entry.repeat_count = 1;
+                collapsed.push(entry);
+            }
+        }
+    }
+
+    collapsed
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::{CustomPattern, Encoding, LogLevel, ParseGroupMap};
     use crate::streaming::file_indexer::index_file;
     use std::io::Write;
     use tempfile::NamedTempFile;
@@ -213,4 +1037,1064 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_read_range_extreme_lines() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=1500 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let chunk = reader.read_range(0, 3)?;
+        assert_eq!(chunk.start_line, 1);
+        assert_eq!(chunk.entries.len(), 3);
+
+        assert_eq!(reader.read_range(1400, u64::MAX)?.entries.len(), 101);
+        assert!(reader.read_range(u64::MAX, u64::MAX)?.entries.is_empty());
+        assert!(reader.read_range(0, 0)?.entries.is_empty());
+        assert!(reader.read_line(u64::MAX)?.is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_range_custom_interval() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=3000 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+
+        // 非默认间隔，且只保留稀疏偏移
+        let mut index = index_file(temp_file.path())?;
+        assert_ne!(index.index_interval, FileIndex::INDEX_INTERVAL);
+        index.dense_offsets = false;
+        index.dense_line_offsets.clear();
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let chunk = reader.read_range(1234, 1240)?;
+        let lines: Vec<u64> = chunk.entries.iter().map(|e| e.line_number).collect();
+        assert_eq!(lines, (1234..=1240).collect::<Vec<_>>());
+        assert_eq!(&*chunk.entries[0].raw, "LogInit: Display: Line 1234");
+
+        // 跨块读取后再从缓存读取同一范围
+        assert_eq!(reader.read_range(2095, 2105)?.entries.len(), 11);
+        reader.invalidate_range(2095, 2105);
+        let chunk = reader.read_range(2095, 2105)?;
+        assert_eq!(&*chunk.entries[10].raw, "LogInit: Display: Line 2105");
+        assert_eq!(reader.read_line(2999)?.map(|e| e.line_number), Some(2999));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cached_range_across_chunks() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=1500 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        assert_eq!(1000 % index.index_interval, 0);
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        // 先缓存第 1000 行所在块的一部分，再请求跨越块边界的范围
+        assert_eq!(reader.read_range(990, 999)?.entries.len(), 10);
+        let chunk = reader.read_range(995, 1005)?;
+        let lines: Vec<u64> = chunk.entries.iter().map(|e| e.line_number).collect();
+        assert_eq!(lines, (995..=1005).collect::<Vec<_>>());
+        assert_eq!(chunk.end_line, 1005);
+
+        // 再次读取时结果不变
+        assert_eq!(reader.read_range(995, 1005)?.entries.len(), 11);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_evicts_least_recently_read() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=350 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        let interval = index.index_interval;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?.with_cache_size(2);
+
+        reader.read_range(1, 10)?;
+        reader.read_range(interval + 1, interval + 10)?;
+        // 命中第 0 块，使第 1 块成为最久未读取的块
+        reader.read_range(1, 5)?;
+        reader.read_range(2 * interval + 1, 2 * interval + 10)?;
+
+        let cache = &reader.cache.lock().chunks;
+        assert!(cache.contains(&0));
+        assert!(!cache.contains(&1));
+        assert!(cache.contains(&2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cache_byte_limit() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=1000 {
+            writeln!(temp_file, "LogInit: Display: Line {:0>200}", i)?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        let interval = index.index_interval;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?.with_cache_size(1000);
+
+        // 每块约 100 * 420 字节，上限只容得下两块
+        let limit = 100_000;
+        reader.set_cache_limit(limit);
+        for block in 0..10 {
+            let start = block * interval + 1;
+            reader.read_range(start, start + interval - 1)?;
+
+            let cache = reader.cache.lock();
+            let tracked: usize = cache.chunks.iter().map(|(_, item)| item.bytes).sum();
+            assert_eq!(cache.bytes, tracked);
+            assert!(cache.bytes <= limit);
+        }
+        assert_eq!(reader.cache.lock().chunks.len(), 2);
+
+        // 单块超过上限时不缓存
+        reader.set_cache_limit(1000);
+        assert_eq!(reader.cache.lock().bytes, 0);
+        reader.read_range(1, 50)?;
+        assert!(reader.cache.lock().chunks.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_utf8_line() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Before")?;
+        temp_file.write_all(b"LogNet: Warning: Bad \xFF\xFE bytes\n")?;
+        writeln!(temp_file, "LogInit: Display: After")?;
+        temp_file.flush()?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let chunk = reader.read_range(1, 3)?;
+        assert_eq!(chunk.entries.len(), 3);
+        let bad = &chunk.entries[1];
+        assert!(bad.has_invalid_utf8);
+        assert_eq!(&*bad.raw, "LogNet: Warning: Bad \u{FFFD}\u{FFFD} bytes");
+        assert_eq!(bad.level, LogLevel::Warning);
+        assert!(!chunk.entries[0].has_invalid_utf8);
+        assert_eq!(&*chunk.entries[2].raw, "LogInit: Display: After");
+
+        let flags: Vec<bool> = reader
+            .entries()?
+            .map(|entry| entry.map(|e| e.has_invalid_utf8))
+            .collect::<std::io::Result<_>>()?;
+        assert_eq!(flags, vec![false, true, false]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_error_mid_range() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=4 {
+            writeln!(temp_file, "LogTemp: Display: Line {}", i)?;
+        }
+        temp_file.flush()?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        // 第 2 行模拟读取失败
+        let lines = (1..=4).map(|i| {
+            if i == 2 {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "stream did not contain valid UTF-8",
+                ))
+            } else {
+                Ok((format!("LogTemp: Display: Line {}", i), false))
+            }
+        });
+        let chunk = reader.collect_range(lines, 0, 1, 4);
+
+        assert_eq!(chunk.end_line, 4);
+        assert_eq!(chunk.entries.len(), 4);
+        assert_eq!(
+            chunk.read_errors,
+            vec![(2, "stream did not contain valid UTF-8".to_string())]
+        );
+        let marker = &chunk.entries[1];
+        assert_eq!(marker.line_number, 2);
+        assert_eq!(marker.level, LogLevel::Unknown);
+        assert!(marker.raw.contains("read error"));
+        assert_eq!(chunk.entries[3].message.as_deref(), Some("Line 4"));
+
+        // 含失败行的块不缓存，重新读取时得到文件中的真实内容
+        let reread = reader.read_range(1, 4)?;
+        assert!(reread.read_errors.is_empty());
+        assert_eq!(&*reread.entries[1].raw, "LogTemp: Display: Line 2");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_crlf_line_endings() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=300 {
+            write!(temp_file, "LogInit: Warning: Line {}\r\n", i)?;
+        }
+        write!(temp_file, "\r\nLogNet: Error: Last\r\n")?;
+
+        let mut index = index_file(temp_file.path())?;
+        assert_eq!(index.total_lines, 302);
+        assert_eq!(index.level_counts.get("warning"), Some(&300));
+        assert_eq!(index.level_counts.get("unknown"), None);
+
+        // 只用稀疏偏移定位
+        index.dense_offsets = false;
+        index.dense_line_offsets.clear();
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let chunk = reader.read_range(250, 252)?;
+        assert_eq!(chunk.entries[0].line_number, 250);
+        assert_eq!(&*chunk.entries[0].raw, "LogInit: Warning: Line 250");
+        assert_eq!(chunk.entries[2].message.as_deref(), Some("Line 252"));
+
+        let last = reader.read_line(302)?.unwrap();
+        assert_eq!(&*last.raw, "LogNet: Error: Last");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reparse_with_level_alias() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Starting")?;
+        writeln!(temp_file, "LogCore: Critical: Out of memory")?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let chunk = reader.read_range(1, 2)?;
+        assert_eq!(chunk.entries[1].level, LogLevel::Unknown);
+
+        let mut config = ParserConfig::default();
+        config
+            .level_aliases
+            .insert("Critical".to_string(), LogLevel::Error);
+        reader.set_parser_config(config);
+
+        // 缓存中仍是旧的解析结果
+        let chunk = reader.read_range(1, 2)?;
+        assert_eq!(chunk.entries[1].level, LogLevel::Unknown);
+
+        let chunk = reader.reparse_range(1, 2)?;
+        assert_eq!(chunk.entries[1].level, LogLevel::Error);
+        assert_eq!(chunk.entries[0].level, LogLevel::Display);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reparse_range_unaligned_read() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=300 {
+            writeln!(temp_file, "LogCore: Critical: Line {}", i)?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        assert_eq!(index.index_interval, 100);
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+        reader.read_range(150, 249)?;
+
+        let mut config = ParserConfig::default();
+        config
+            .level_aliases
+            .insert("Critical".to_string(), LogLevel::Error);
+        reader.set_parser_config(config);
+        reader.reparse_range(201, 300)?;
+
+        // 重新解析过的 201..=249 不再返回旧结果
+        let chunk = reader.read_range(150, 249)?;
+        let stale: Vec<u64> = chunk
+            .entries
+            .iter()
+            .filter(|e| e.line_number >= 201 && e.level != LogLevel::Error)
+            .map(|e| e.line_number)
+            .collect();
+        assert!(stale.is_empty(), "stale lines: {:?}", stale);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_scan_unparsed() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "Log file open, 02/14/26 11:33:35")?;
+        writeln!(temp_file, "LogInit: Display: Starting")?;
+        writeln!(temp_file, "PID 4242 | LogNet: Warning: wrapped")?;
+        writeln!(temp_file, "  continuation line")?;
+        writeln!(temp_file, "LogCore: Error: Failed")?;
+        writeln!(temp_file, "=== custom banner ===")?;
+        writeln!(temp_file, "another odd line")?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let samples = reader.scan_unparsed(10)?;
+        let lines: Vec<u64> = samples.iter().map(|s| s.line_number).collect();
+        assert_eq!(lines, vec![3, 6, 7]);
+        assert_eq!(samples[1].raw, "=== custom banner ===");
+
+        assert_eq!(reader.scan_unparsed(2)?.len(), 2);
+
+        // 自定义格式匹配的行即使没有类别组也不算未解析
+        let groups = ParseGroupMap {
+            message: Some("msg".to_string()),
+            ..Default::default()
+        };
+        reader.set_parser_config(ParserConfig {
+            custom_pattern: Some(CustomPattern::new(r"^=== (?P<msg>.*) ===$", groups).unwrap()),
+            ..Default::default()
+        });
+        let lines: Vec<u64> = reader
+            .scan_unparsed(10)?
+            .iter()
+            .map(|s| s.line_number)
+            .collect();
+        assert_eq!(lines, vec![3, 7]);
+        assert_eq!(reader.parse_stats()?.raw, 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_stats() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "Log file open, 02/14/26 11:33:35")?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-11.33.35:000][  0]LogInit: Display: Starting"
+        )?;
+        writeln!(temp_file, "LogInit: Display: Starting")?;
+        writeln!(temp_file, "LogTemp: level omitted")?;
+        writeln!(temp_file, "  continuation line")?;
+        writeln!(temp_file, "PID 4242 | LogNet: Warning: wrapped")?;
+        writeln!(temp_file, "=== custom banner ===")?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        assert_eq!(
+            reader.parse_stats()?,
+            ParseStats {
+                custom: 0,
+                standard: 1,
+                simple: 2,
+                header: 1,
+                continuation: 1,
+                raw: 2,
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pooled_entries_share_storage() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=3000 {
+            let category = if i % 2 == 0 { "LogNet" } else { "LogInit" };
+            writeln!(temp_file, "{}: Display: Line {}", category, i)?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        // 模拟滚动: 逐块读取，然后重新读取刚缓存的区域
+        let mut first_pass = Vec::new();
+        for start in (1..=3000).step_by(100) {
+            first_pass.extend(reader.read_range(start, start + 99)?.entries);
+        }
+        let second_pass = reader.read_range(2901, 3000)?.entries;
+
+        // 内容与直接解析一致
+        for entry in &first_pass {
+            let expected = LogParser::parse_line(entry.line_number, &entry.raw);
+            assert_eq!(entry.raw, expected.raw);
+            assert_eq!(entry.category, expected.category);
+            assert_eq!(entry.message, expected.message);
+        }
+
+        // 命中缓存的条目与缓存共享原始内容，没有复制字符串
+        assert_eq!(second_pass.len(), 100);
+        for (a, b) in first_pass[2900..].iter().zip(&second_pass) {
+            assert!(Arc::ptr_eq(&a.raw, &b.raw));
+        }
+
+        // 类别字符串的分配: (分配次数, 总字节数)
+        let category_allocations = |entries: &[LogEntry]| {
+            let mut seen = HashSet::new();
+            entries
+                .iter()
+                .filter_map(|e| e.category.as_ref())
+                .filter(|category| seen.insert(category.as_ptr()))
+                .fold((0, 0), |(count, bytes), category| {
+                    (count + 1, bytes + category.len())
+                })
+        };
+
+        // 不使用类别池时每行各分配一份
+        let index = index_file(temp_file.path())?;
+        let mut unpooled_reader = LineReader::from_index(temp_file.path(), index)?;
+        unpooled_reader.set_category_pooling(false);
+        let mut unpooled = Vec::new();
+        for start in (1..=3000).step_by(100) {
+            unpooled.extend(unpooled_reader.read_range(start, start + 99)?.entries);
+        }
+        assert_eq!(category_allocations(&unpooled), (3000, 1500 * 6 + 1500 * 7));
+
+        // 使用类别池时 3000 行只有两份类别字符串
+        assert_eq!(category_allocations(&first_pass), (2, 6 + 7));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_start_and_end_time() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "Log file open, 02/14/26 11:33:35")?;
+        writeln!(temp_file, "LogInit: Display: untimed")?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.33.56:070][  0]LogInit: Display: Start"
+        )?;
+        for i in 0..2500 {
+            writeln!(
+                temp_file,
+                "[2026.02.14-03.34.00:000][{:3}]LogTemp: Display: Tick",
+                i
+            )?;
+        }
+        writeln!(
+            temp_file,
+            "[2026.02.14-04.00.00:500][2500]LogExit: Display: Exiting"
+        )?;
+        writeln!(temp_file, "  trailing continuation")?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        assert_eq!(reader.first_timestamp_millis()?, Some(1_771_040_036_070));
+        assert_eq!(reader.last_timestamp_millis()?, Some(1_771_041_600_500));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_time_range() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "Log file open, 02/14/26 11:33:35")?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.33.56:070][  0]LogInit: Display: Start"
+        )?;
+        for i in 0..1500 {
+            writeln!(temp_file, "LogTemp: Display: untimed {}", i)?;
+        }
+        writeln!(
+            temp_file,
+            "[2026.02.14-04.00.00:500][ 10]LogExit: Display: Exiting"
+        )?;
+        writeln!(temp_file, "  trailing continuation")?;
+        writeln!(temp_file, "LogTemp: Display: untimed tail")?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        assert_eq!(
+            reader.time_range()?,
+            Some((
+                "2026.02.14-03.33.56:070".to_string(),
+                "2026.02.14-04.00.00:500".to_string()
+            ))
+        );
+
+        let mut untimed = NamedTempFile::new()?;
+        writeln!(untimed, "Log file open, 02/14/26 11:33:35")?;
+        writeln!(untimed, "LogInit: Display: untimed")?;
+        writeln!(untimed, "  continuation")?;
+
+        let index = index_file(untimed.path())?;
+        let mut reader = LineReader::from_index(untimed.path(), index)?;
+        assert_eq!(reader.time_range()?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_frame_range_and_find_by_frame() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        // 两个会话，第二个会话的帧号从 5 重新开始
+        for frame in (100..=12500).step_by(100) {
+            writeln!(
+                temp_file,
+                "[2026.02.14-03.00.00:000][{}]LogTemp: Display: Tick",
+                frame
+            )?;
+        }
+        writeln!(temp_file, "Log file open, 02/14/26 11:33:35")?;
+        for frame in (5..=12005).step_by(1000) {
+            writeln!(
+                temp_file,
+                "[2026.02.14-04.00.00:000][{}]LogTemp: Display: Tick",
+                frame
+            )?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        assert_eq!(reader.frame_range()?, Some((5, 12500)));
+
+        // 两个会话都有 12000 之后的帧，返回第一个会话中的行
+        let line = reader.find_line_by_frame(12000)?.unwrap();
+        assert_eq!(line, 120);
+        assert_eq!(reader.read_line(line)?.unwrap().frame, Some(12000));
+
+        assert_eq!(reader.find_line_by_frame(0)?, Some(1));
+        assert_eq!(reader.find_line_by_frame(20000)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_find_line_by_timestamp() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "Log file open, 02/14/26 11:33:35")?;
+        // 时间从 03:00:00 起每行加一秒，穿插续行
+        for n in 0..2500 {
+            writeln!(
+                temp_file,
+                "[2026.02.14-03.{:02}.{:02}:000][{:3}]LogTemp: Display: Tick",
+                n / 60,
+                n % 60,
+                n
+            )?;
+            if n % 100 == 0 {
+                writeln!(temp_file, "  continuation")?;
+            }
+        }
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        assert_eq!(
+            reader.find_line_by_timestamp("2026.02.14-02.00.00:000")?,
+            Some(1)
+        );
+
+        let line = reader
+            .find_line_by_timestamp("2026.02.14-03.25.00:000")?
+            .expect("target inside the log");
+        let entry = reader.read_line(line)?.unwrap();
+        assert_eq!(entry.timestamp.as_deref(), Some("2026.02.14-03.25.00:000"));
+
+        // 落在两行之间时取后一行
+        let line = reader
+            .find_line_by_timestamp("2026.02.14-03.25.00:500")?
+            .unwrap();
+        let entry = reader.read_line(line)?.unwrap();
+        assert_eq!(entry.timestamp.as_deref(), Some("2026.02.14-03.25.01:000"));
+
+        assert_eq!(
+            reader.find_line_by_timestamp("2026.02.14-05.00.00:000")?,
+            None
+        );
+        assert!(reader.find_line_by_timestamp("yesterday").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_peek_around() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=20 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let lines =
+            |chunk: LogChunk| -> Vec<u64> { chunk.entries.iter().map(|e| e.line_number).collect() };
+        assert_eq!(
+            lines(reader.peek_around(1, 5)?),
+            (1..=6).collect::<Vec<_>>()
+        );
+        assert_eq!(lines(reader.peek_around(10, 2)?), vec![8, 9, 10, 11, 12]);
+        assert_eq!(
+            lines(reader.peek_around(19, 5)?),
+            (14..=20).collect::<Vec<_>>()
+        );
+        assert_eq!(lines(reader.peek_around(7, 0)?), vec![7]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_byte_offset_to_line() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        let mut line_starts = Vec::new();
+        let mut offset = 0;
+        for i in 1..=3000 {
+            let line = format!("LogInit: Display: Line {}\n", i);
+            line_starts.push(offset);
+            offset += line.len() as u64;
+            temp_file.write_all(line.as_bytes())?;
+        }
+
+        let dense = index_file(temp_file.path())?;
+        let mut sparse = dense.clone();
+        sparse.dense_offsets = false;
+        sparse.dense_line_offsets.clear();
+
+        for index in [dense, sparse] {
+            let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+            // 行首、行中和行尾的换行符都属于该行
+            let start = line_starts[2344];
+            assert_eq!(reader.byte_offset_to_line(start)?, 2345);
+            assert_eq!(reader.byte_offset_to_line(start + 5)?, 2345);
+            assert_eq!(reader.byte_offset_to_line(line_starts[2345] - 1)?, 2345);
+            assert_eq!(reader.byte_offset_to_line(0)?, 1);
+            assert_eq!(reader.byte_offset_to_line(u64::MAX)?, 3000);
+
+            let chunk = reader.read_byte_range(start + 5, line_starts[2346] + 1)?;
+            let lines: Vec<u64> = chunk.entries.iter().map(|e| e.line_number).collect();
+            assert_eq!(lines, vec![2345, 2346, 2347]);
+            assert_eq!(chunk.entries[0].message.as_deref(), Some("Line 2345"));
+            assert!(reader.read_byte_range(start, start)?.entries.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_line_to_byte_offset() -> std::io::Result<()> {
+        use std::io::BufRead;
+
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"\xEF\xBB\xBF")?;
+        for i in 1..=3000 {
+            write!(temp_file, "LogInit: Display: Line {}\r\n", i)?;
+        }
+
+        let dense = index_file(temp_file.path())?;
+        let mut sparse = dense.clone();
+        sparse.dense_offsets = false;
+        sparse.dense_line_offsets.clear();
+
+        for index in [dense, sparse] {
+            let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+            for line_number in [1, 2, 1000, 1001, 2345, 3000] {
+                let offset = reader.line_to_byte_offset(line_number)?.unwrap();
+
+                let mut file = File::open(temp_file.path())?;
+                file.seek(SeekFrom::Start(offset))?;
+                let mut text = String::new();
+                BufReader::new(file).read_line(&mut text)?;
+                assert_eq!(text, format!("LogInit: Display: Line {}\r\n", line_number));
+
+                assert_eq!(reader.byte_offset_to_line(offset)?, line_number);
+            }
+
+            assert_eq!(reader.line_to_byte_offset(0)?, None);
+            assert_eq!(reader.line_to_byte_offset(3001)?, None);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_tail() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=5000 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let chunk = reader.read_tail(500)?;
+        assert_eq!((chunk.start_line, chunk.end_line), (4501, 5000));
+        let lines: Vec<u64> = chunk.entries.iter().map(|e| e.line_number).collect();
+        assert_eq!(lines, (4501..=5000).collect::<Vec<_>>());
+        assert_eq!(chunk.entries[0].message.as_deref(), Some("Line 4501"));
+
+        assert_eq!(reader.read_tail(10000)?.entries.len(), 5000);
+        assert!(reader.read_tail(0)?.entries.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_sessions() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "Log file open, 02/14/26 11:33:35")?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.33.56:070][  0]LogInit: Display: Run 1"
+        )?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.33.57:000][998]LogTemp: Display: Tick"
+        )?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.33.57:010][  2]LogTemp: Display: Wrapped"
+        )?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.34.00:000][437]LogTemp: Display: Tick"
+        )?;
+        writeln!(temp_file, "  continuation")?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-04.00.00:000][  0]LogInit: Display: Run 2"
+        )?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-04.00.01:000][350]LogTemp: Display: Small drop next"
+        )?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-04.00.01:500][300]LogTemp: Display: Not a reset"
+        )?;
+        writeln!(temp_file, "Log file open, 02/14/26 12:30:00")?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-04.30.00:000][  0]LogInit: Display: Run 3"
+        )?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let sessions = reader.detect_sessions()?;
+        let starts: Vec<u64> = sessions.iter().map(|s| s.start_line).collect();
+        assert_eq!(starts, vec![1, 7, 10]);
+        assert_eq!(sessions[0].timestamp.as_deref(), Some("02/14/26 11:33:35"));
+        assert_eq!(
+            sessions[1].timestamp.as_deref(),
+            Some("2026.02.14-04.00.00:000")
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_time_resets() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.33.56:070][  0]LogInit: Display: Run 1"
+        )?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.40.00:000][ 10]LogTemp: Display: Tick"
+        )?;
+        writeln!(temp_file, "  continuation")?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.10.00:000][  0]LogInit: Display: Run 2"
+        )?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.10.00:000][  1]LogTemp: Display: Same time"
+        )?;
+        writeln!(
+            temp_file,
+            "[2026.02.14-03.20.00:000][  2]LogTemp: Display: Tick"
+        )?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        assert_eq!(reader.detect_time_resets()?, vec![4]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_time_resets_custom_pattern() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "<2026.02.14-03.40.00:000> LogInit: Run 1")?;
+        writeln!(temp_file, "<2026.02.14-03.10.00:000> LogInit: Run 2")?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        // 内置格式识别不了这种时间戳
+        assert!(reader.detect_time_resets()?.is_empty());
+
+        let groups = ParseGroupMap {
+            timestamp: Some("ts".to_string()),
+            category: Some("cat".to_string()),
+            message: Some("msg".to_string()),
+            ..Default::default()
+        };
+        let pattern = r"^<(?P<ts>[\d.:-]+)> (?P<cat>\w+): (?P<msg>.*)$";
+        reader.set_parser_config(ParserConfig {
+            custom_pattern: Some(CustomPattern::new(pattern, groups).unwrap()),
+            ..Default::default()
+        });
+        assert_eq!(reader.detect_time_resets()?, vec![2]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_step_same_category() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Starting")?;
+        writeln!(temp_file, "LogNet: Warning: Packet lost")?;
+        writeln!(temp_file, "  continuation of packet lost")?;
+        writeln!(temp_file, "LogCore: Display: Tick")?;
+        writeln!(temp_file, "LogInit: Display: Ready")?;
+        writeln!(temp_file, "LogNet: Error: Connection closed")?;
+        writeln!(temp_file, "LogCore: Display: Tick")?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        assert_eq!(reader.next_same_category(2)?, Some(6));
+        assert_eq!(reader.prev_same_category(6)?, Some(2));
+        assert_eq!(reader.next_same_category(6)?, None);
+        assert_eq!(reader.prev_same_category(2)?, None);
+
+        // 续行按所属日志行 (LogNet) 计算
+        assert_eq!(reader.next_same_category(3)?, Some(6));
+        assert_eq!(reader.prev_same_category(3)?, Some(2));
+
+        assert_eq!(reader.next_same_category(0)?, None);
+        assert_eq!(reader.next_same_category(7)?, None);
+        assert_eq!(reader.next_same_category(100)?, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dense_read_caches_aligned_chunks() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=300 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        assert!(index.dense_offsets);
+        assert_eq!(index.index_interval, 100);
+
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+        let chunk = reader.read_range(150, 249)?;
+        assert_eq!(chunk.entries.first().map(|e| e.line_number), Some(150));
+        assert_eq!(chunk.entries.len(), 100);
+
+        // 块从边界开始缓存: 块 1 为 101..=200 整块，块 2 从 201 开始
+        let mut cache = reader.cache.lock();
+        let lines = |item: &CacheItem| {
+            (
+                item.entries.first().map(|e| e.line_number),
+                item.entries.len(),
+            )
+        };
+        assert_eq!(cache.get(1).map(lines), Some((Some(101), 100)));
+        assert_eq!(cache.get(2).map(lines), Some((Some(201), 49)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dense_single_line_read() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=2500 {
+            writeln!(temp_file, "LogInit: Display: Line {}", i)?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        assert!(index.dense_offsets);
+
+        // 完整偏移下定位无需跳过任何行
+        let (_, lines_before) = index.seek_position(1750);
+        assert_eq!(lines_before, 1749);
+
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+        let entry = reader.read_line(1750)?.expect("line 1750 exists");
+        assert_eq!(entry.line_number, 1750);
+        assert_eq!(&*entry.raw, "LogInit: Display: Line 1750");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_utf16le_with_bom() -> std::io::Result<()> {
+        let text =
+            "LogInit: Display: 引擎启动\r\nLogNet: Error: Ċ 连接失败\r\nLogInit: Warning: Done\r\n";
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(&[0xFF, 0xFE])?;
+        for unit in text.encode_utf16() {
+            temp_file.write_all(&unit.to_le_bytes())?;
+        }
+
+        let index = index_file(temp_file.path())?;
+        assert_eq!(index.encoding, Encoding::Utf16Le);
+        assert_eq!(index.total_lines, 3);
+        assert_eq!(index.categories.get("LogInit"), Some(&2));
+        assert_eq!(index.level_counts.get("error"), Some(&1));
+
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+        let chunk = reader.read_range(1, 3)?;
+        assert_eq!(chunk.entries.len(), 3);
+        assert_eq!(&*chunk.entries[0].raw, "LogInit: Display: 引擎启动");
+        assert_eq!(chunk.entries[1].level, LogLevel::Error);
+        assert_eq!(chunk.entries[1].message.as_deref(), Some("Ċ 连接失败"));
+
+        let entry = reader.read_line(3)?.expect("line 3 exists");
+        assert_eq!(entry.category.as_deref(), Some("LogInit"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_utf8_bom_first_line() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        temp_file.write_all(b"\xEF\xBB\xBFLogInit: Display: First\nLogInit: Display: Second\n")?;
+
+        let index = index_file(temp_file.path())?;
+        assert_eq!(index.encoding, Encoding::Utf8Bom);
+        assert_eq!(index.categories.get("LogInit"), Some(&2));
+
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+        let chunk = reader.read_range(1, 2)?;
+        assert_eq!(&*chunk.entries[0].raw, "LogInit: Display: First");
+        assert_eq!(chunk.entries[0].category.as_deref(), Some("LogInit"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_range_deduped() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Starting")?;
+        for _ in 0..50 {
+            writeln!(temp_file, "LogNet: Warning: Packet dropped")?;
+        }
+        writeln!(temp_file, "LogInit: Display: Starting")?;
+        writeln!(temp_file, "LogNet: Warning: Packet dropped")?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let chunk = reader.read_range_deduped(1, 53)?;
+        let runs: Vec<(u64, u64)> = chunk
+            .entries
+            .iter()
+            .map(|e| (e.line_number, e.repeat_count))
+            .collect();
+        // 只折叠相邻的重复，之后再出现的相同行单独保留
+        assert_eq!(runs, vec![(1, 1), (2, 50), (52, 1), (53, 1)]);
+        assert_eq!(chunk.end_line, 53);
+
+        // 普通读取不计算重复次数
+        assert_eq!(reader.read_range(2, 3)?.entries[0].repeat_count, 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_range_merged() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        writeln!(temp_file, "LogInit: Display: Starting")?;
+        writeln!(temp_file, "LogWindows: Error: === Critical error: ===")?;
+        for frame in 0..5 {
+            writeln!(
+                temp_file,
+                "    UnrealEditor-Core.dll!Frame{}() [Core.cpp:{}]",
+                frame, frame
+            )?;
+        }
+        writeln!(temp_file, "LogExit: Display: Exiting")?;
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let chunk = reader.read_range_merged(1, 8)?;
+        assert_eq!(chunk.entries.len(), 3);
+        assert_eq!(chunk.end_line, 8);
+
+        let error = &chunk.entries[1];
+        assert_eq!(error.line_number, 2);
+        assert_eq!(error.continuation_count, 5);
+        let message = error.message.as_deref().unwrap();
+        assert_eq!(message.lines().count(), 6);
+        assert!(message.starts_with("=== Critical error: ===\n    UnrealEditor-Core.dll!Frame0()"));
+        assert!(message.ends_with("Frame4() [Core.cpp:4]"));
+
+        assert_eq!(chunk.entries[2].line_number, 8);
+        assert_eq!(chunk.entries[2].continuation_count, 0);
+
+        // 从续行中间开始时，没有所属日志行的续行保持原样
+        let chunk = reader.read_range_merged(4, 8)?;
+        assert_eq!(chunk.entries.len(), 5);
+        assert!(chunk.entries[0].is_continuation);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_entries_iterator() -> std::io::Result<()> {
+        let mut temp_file = NamedTempFile::new()?;
+        for i in 1..=1000 {
+            if i % 7 == 0 {
+                writeln!(temp_file, "LogNet: Error: Packet {} dropped", i)?;
+            } else {
+                writeln!(temp_file, "LogNet: Display: Packet {} ok", i)?;
+            }
+        }
+
+        let index = index_file(temp_file.path())?;
+        let mut reader = LineReader::from_index(temp_file.path(), index)?;
+
+        let mut total = 0;
+        let mut errors = 0;
+        for entry in reader.entries()? {
+            let entry = entry?;
+            total += 1;
+            assert_eq!(entry.line_number, total);
+            if entry.level == LogLevel::Error {
+                errors += 1;
+            }
+        }
+        assert_eq!(total, 1000);
+        assert_eq!(errors, 142);
+
+        let lines: Vec<u64> = reader
+            .entries_from(998)?
+            .map(|e| e.map(|e| e.line_number))
+            .collect::<std::io::Result<_>>()?;
+        assert_eq!(lines, vec![998, 999, 1000]);
+
+        // 不填充块缓存
+        assert!(reader.cache.lock().chunks.is_empty());
+
+        Ok(())
+    }
 }
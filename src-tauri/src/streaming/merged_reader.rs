@@ -0,0 +1,326 @@
+//! 多文件合并读取 - 将 UE 拆分到 Backup/ 的多个日志按时间顺序合并为一个视图
+//!
+//! 打开时扫描每个文件一次，只记录来自同一文件的连续行段，读取时按段转发给各文件的行读取器
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::parser::{FileIndex, LogChunk, LogParser, ParserConfig};
+use crate::streaming::LineReader;
+
+/// 合并视图中连续来自同一文件的一段行
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct MergeRun {
+    /// 在合并视图中的起始行号 (1-based)
+    merged_start: u64,
+    /// 来源文件序号
+    source: usize,
+    /// 在来源文件中的起始行号
+    source_start: u64,
+    /// 行数
+    len: u64,
+}
+
+/// 参与合并的一个文件
+struct MergedSource {
+    path: Arc<str>,
+    reader: LineReader,
+}
+
+/// 多文件合并读取器
+pub struct MergedReader {
+    sources: Vec<MergedSource>,
+    runs: Vec<MergeRun>,
+    total_lines: u64,
+}
+
+impl MergedReader {
+    /// 按时间戳合并多个已建好索引的文件
+    ///
+    /// 没有时间戳的行 (续行、无时间戳格式) 跟随其前一个带时间戳的行，
+    /// 文件第一个时间戳之前的行跟随该时间戳，完全没有时间戳的文件排在最前。
+    /// 时间戳相同时按传入顺序 (稳定合并)，同一文件内的行序保持不变
+    pub fn new(files: Vec<(FileIndex, PathBuf)>, config: &ParserConfig) -> std::io::Result<Self> {
+        let mut sources = Vec::with_capacity(files.len());
+        for (index, path) in files {
+            let mut reader = LineReader::from_index(&path, index)?;
+            reader.set_parser_config(config.clone());
+            sources.push(MergedSource {
+                path: path.to_string_lossy().into(),
+                reader,
+            });
+        }
+
+        let (runs, total_lines) = merge_runs(&mut sources)?;
+        Ok(Self {
+            sources,
+            runs,
+            total_lines,
+        })
+    }
+
+    /// 合并后的总行数
+    pub fn total_lines(&self) -> u64 {
+        self.total_lines
+    }
+
+    /// 参与合并的文件路径 (按传入顺序)
+    pub fn source_paths(&self) -> Vec<String> {
+        self.sources.iter().map(|s| s.path.to_string()).collect()
+    }
+
+    /// 各文件的路径和索引 (按传入顺序，索引为克隆，可在释放读取器的锁后使用)
+    pub fn files(&self) -> Vec<(PathBuf, FileIndex)> {
+        self.sources
+            .iter()
+            .map(|s| (PathBuf::from(s.path.as_ref()), s.reader.index().clone()))
+            .collect()
+    }
+
+    /// 读取合并视图中指定范围的行 (含两端，超出范围的部分被截断)
+    ///
+    /// 条目的 line_number 为合并视图中的行号，source_file 为来源文件
+    pub fn read_range(&mut self, start_line: u64, end_line: u64) -> std::io::Result<LogChunk> {
+        let start_line = start_line.max(1);
+        let end_line = end_line.min(self.total_lines);
+
+        if start_line > end_line {
+            return Ok(LogChunk {
+                start_line,
+                end_line: start_line,
+                entries: vec![],
+                read_errors: vec![],
+            });
+        }
+
+        let first_run = self
+            .runs
+            .partition_point(|run| run.merged_start + run.len <= start_line);
+        let mut entries = Vec::with_capacity((end_line - start_line + 1) as usize);
+        let mut read_errors = Vec::new();
+
+        for run in &self.runs[first_run..] {
+            if run.merged_start > end_line {
+                break;
+            }
+            let from = start_line.max(run.merged_start);
+            let to = end_line.min(run.merged_start + run.len - 1);
+            let source = &mut self.sources[run.source];
+            let skip = from - run.merged_start;
+
+            let source_from = run.source_start + skip;
+            let chunk = source
+                .reader
+                .read_range(source_from, source_from + (to - from))?;
+            read_errors.extend(
+                chunk
+                    .read_errors
+                    .into_iter()
+                    .map(|(line, error)| (from + (line - source_from), error)),
+            );
+            entries.extend(
+                chunk
+                    .entries
+                    .into_iter()
+                    .zip(from..)
+                    .map(|(mut entry, line)| {
+                        entry.line_number = line;
+                        entry.source_file = Some(source.path.clone());
+                        entry
+                    }),
+            );
+        }
+
+        Ok(LogChunk {
+            start_line,
+            end_line,
+            entries,
+            read_errors,
+        })
+    }
+}
+
+/// k 路归并各文件的行，返回合并后的行段和总行数
+fn merge_runs(sources: &mut [MergedSource]) -> std::io::Result<(Vec<MergeRun>, u64)> {
+    // 每个文件第一个时间戳之前的行使用该时间戳
+    let mut last_keys = Vec::with_capacity(sources.len());
+    for source in sources.iter_mut() {
+        last_keys.push(source.reader.first_timestamp_millis()?.unwrap_or(i64::MIN));
+    }
+
+    let mut iters = Vec::with_capacity(sources.len());
+    for source in sources.iter_mut() {
+        iters.push(source.reader.entries()?);
+    }
+
+    // 各文件下一行的排序键: (时间戳, 文件序号, 行号)，文件序号保证稳定
+    let mut heap = BinaryHeap::new();
+    let mut next_key = |source: usize, last_keys: &mut [i64]| -> std::io::Result<_> {
+        let Some(entry) = iters[source].next().transpose()? else {
+            return Ok(None);
+        };
+        if let Some(millis) = entry
+            .timestamp
+            .as_deref()
+            .and_then(LogParser::timestamp_millis)
+        {
+            last_keys[source] = millis;
+        }
+        Ok(Some(Reverse((
+            last_keys[source],
+            source,
+            entry.line_number,
+        ))))
+    };
+
+    for source in 0..last_keys.len() {
+        if let Some(key) = next_key(source, &mut last_keys)? {
+            heap.push(key);
+        }
+    }
+
+    let mut runs: Vec<MergeRun> = Vec::new();
+    let mut total_lines = 0;
+    while let Some(Reverse((_, source, line_number))) = heap.pop() {
+        total_lines += 1;
+        match runs.last_mut() {
+            Some(run) if run.source == source && run.source_start + run.len == line_number => {
+                run.len += 1;
+            }
+            _ => runs.push(MergeRun {
+                merged_start: total_lines,
+                source,
+                source_start: line_number,
+                len: 1,
+            }),
+        }
+
+        if let Some(key) = next_key(source, &mut last_keys)? {
+            heap.push(key);
+        }
+    }
+
+    Ok((runs, total_lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::streaming::index_file;
+    use std::io::Write;
+    use tempfile::NamedTempFile;
+
+    fn write_log(lines: &[&str]) -> std::io::Result<NamedTempFile> {
+        let mut temp_file = NamedTempFile::new()?;
+        for line in lines {
+            writeln!(temp_file, "{}", line)?;
+        }
+        temp_file.flush()?;
+        Ok(temp_file)
+    }
+
+    fn open(files: &[&NamedTempFile]) -> std::io::Result<MergedReader> {
+        let files = files
+            .iter()
+            .map(|file| Ok((index_file(file.path())?, file.path().to_path_buf())))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        MergedReader::new(files, &ParserConfig::default())
+    }
+
+    #[test]
+    fn test_merge_by_timestamp() -> std::io::Result<()> {
+        let backup = write_log(&[
+            "Log file open, 02/14/26 11:33:35",
+            "[2026.02.14-03.33.56:070][  0]LogInit: Display: A1",
+            "[2026.02.14-03.34.00:000][  1]LogInit: Error: A2",
+            "  continuation of A2",
+            "[2026.02.14-03.36.00:000][  2]LogExit: Display: A3",
+        ])?;
+        let current = write_log(&[
+            "[2026.02.14-03.34.00:000][  5]LogNet: Display: B1",
+            "[2026.02.14-03.35.00:000][  6]LogNet: Display: B2",
+            "LogNet: Display: B3 without timestamp",
+        ])?;
+
+        let mut reader = open(&[&backup, &current])?;
+        assert_eq!(reader.total_lines(), 8);
+
+        let chunk = reader.read_range(1, 100)?;
+        assert_eq!((chunk.start_line, chunk.end_line), (1, 8));
+        let merged: Vec<(u64, &str, bool)> = chunk
+            .entries
+            .iter()
+            .map(|e| {
+                let from_backup =
+                    e.source_file.as_deref() == Some(backup.path().to_string_lossy().as_ref());
+                (e.line_number, e.raw.as_ref(), from_backup)
+            })
+            .collect();
+        assert_eq!(
+            merged,
+            vec![
+                (1, "Log file open, 02/14/26 11:33:35", true),
+                (
+                    2,
+                    "[2026.02.14-03.33.56:070][  0]LogInit: Display: A1",
+                    true
+                ),
+                // 时间戳相同时靠前的文件先出现，续行跟随所属的行
+                (3, "[2026.02.14-03.34.00:000][  1]LogInit: Error: A2", true),
+                (4, "  continuation of A2", true),
+                (
+                    5,
+                    "[2026.02.14-03.34.00:000][  5]LogNet: Display: B1",
+                    false
+                ),
+                (
+                    6,
+                    "[2026.02.14-03.35.00:000][  6]LogNet: Display: B2",
+                    false
+                ),
+                (7, "LogNet: Display: B3 without timestamp", false),
+                (
+                    8,
+                    "[2026.02.14-03.36.00:000][  2]LogExit: Display: A3",
+                    true
+                ),
+            ]
+        );
+
+        // 跨越多个行段的子范围
+        let chunk = reader.read_range(4, 6)?;
+        let lines: Vec<u64> = chunk.entries.iter().map(|e| e.line_number).collect();
+        assert_eq!(lines, vec![4, 5, 6]);
+        assert_eq!(chunk.entries[0].raw.as_ref(), "  continuation of A2");
+        assert_eq!(reader.read_range(9, 10)?.entries.len(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_without_timestamps() -> std::io::Result<()> {
+        let plain = write_log(&["LogInit: Display: P1", "LogInit: Display: P2"])?;
+        let stamped = write_log(&["[2026.02.14-03.33.56:070][  0]LogInit: Display: S1"])?;
+
+        let mut reader = open(&[&stamped, &plain])?;
+        let raws: Vec<String> = reader
+            .read_range(1, 3)?
+            .entries
+            .iter()
+            .map(|e| e.raw.to_string())
+            .collect();
+        assert_eq!(
+            raws,
+            vec![
+                "LogInit: Display: P1",
+                "LogInit: Display: P2",
+                "[2026.02.14-03.33.56:070][  0]LogInit: Display: S1",
+            ]
+        );
+        assert_eq!(reader.source_paths().len(), 2);
+
+        Ok(())
+    }
+}
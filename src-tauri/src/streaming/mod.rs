@@ -1,7 +1,43 @@
 //! 流式加载模块
 
+pub mod encoding;
 pub mod file_indexer;
 pub mod line_reader;
+pub mod merged_reader;
 
-pub use file_indexer::{index_file, FileIndexer};
-pub use line_reader::LineReader;
+use std::io::{BufReader, Read};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+pub use encoding::{decode_lines, skip_bom};
+pub use file_indexer::{
+    index_file, load_or_index_file, load_or_index_file_with_progress, log_file_info, map_file,
+    sample_categories, FileIndexer, FILE_TRUNCATED,
+};
+pub use line_reader::{EntryIterator, LineReader};
+pub use merged_reader::MergedReader;
+
+/// 顺序读取的默认缓冲区大小 (256 KB)
+pub const DEFAULT_IO_BUFFER_SIZE: usize = 256 * 1024;
+
+/// 缓冲区大小下限
+pub const MIN_IO_BUFFER_SIZE: usize = 64;
+
+/// 当前缓冲区大小 (搜索、过滤、行读取共用)
+static IO_BUFFER_SIZE: AtomicUsize = AtomicUsize::new(DEFAULT_IO_BUFFER_SIZE);
+
+/// 获取当前缓冲区大小
+pub fn io_buffer_size() -> usize {
+    IO_BUFFER_SIZE.load(Ordering::Relaxed)
+}
+
+/// 设置缓冲区大小 (不低于 MIN_IO_BUFFER_SIZE)，返回实际生效的值
+pub fn set_io_buffer_size(size: usize) -> usize {
+    let size = size.max(MIN_IO_BUFFER_SIZE);
+    IO_BUFFER_SIZE.store(size, Ordering::Relaxed);
+    size
+}
+
+/// 使用当前缓冲区大小创建 BufReader
+pub fn buffered<R: Read>(inner: R) -> BufReader<R> {
+    BufReader::with_capacity(io_buffer_size(), inner)
+}
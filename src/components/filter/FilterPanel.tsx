@@ -5,9 +5,10 @@ import { useLogStore } from '../../stores/logStore';
 import { useFilterStore } from '../../stores/filterStore';
 import type { LogLevel } from '../../types/log';
 
-const LOG_LEVELS: LogLevel[] = ['error', 'warning', 'display', 'verbose', 'veryverbose', 'unknown'];
+const LOG_LEVELS: LogLevel[] = ['fatal', 'error', 'warning', 'display', 'verbose', 'veryverbose', 'unknown'];
 
 const LEVEL_COLORS: Record<LogLevel, string> = {
+  fatal: '#b91c1c',
   error: '#ef4444',
   warning: '#f97316',
   display: '#3b82f6',
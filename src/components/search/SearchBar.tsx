@@ -7,6 +7,7 @@ export function SearchBar() {
   const {
     searchOptions,
     searchResults,
+    searchTruncated,
     currentSearchIndex,
     showSearchOnly,
     search,
@@ -96,8 +97,11 @@ export function SearchBar() {
 
         {/* 结果计数 */}
         {resultCount > 0 && (
-          <span className="search-count">
-            {currentPos}/{resultCount}
+          <span
+            className="search-count"
+            title={searchTruncated ? `仅显示前 ${resultCount} 个结果` : undefined}
+          >
+            {currentPos}/{resultCount}{searchTruncated && '+'}
           </span>
         )}
 
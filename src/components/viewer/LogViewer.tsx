@@ -21,6 +21,7 @@ const PRESET_COLORS = [
 ];
 
 const LEVEL_COLORS: Record<string, string> = {
+  fatal: '#b91c1c',
   error: '#ef4444',
   warning: '#f97316',
   display: '#3b82f6',
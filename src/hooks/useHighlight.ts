@@ -35,8 +35,8 @@ export function useHighlight(
     // 添加搜索结果
     for (const result of lineSearchResults) {
       ranges.push({
-        start: result.start,
-        end: result.end,
+        start: result.char_start,
+        end: result.char_end,
         type: 'search',
       });
     }
@@ -307,7 +307,7 @@ export function getToolDefinitions(): ToolDefinition[] {
           levels: {
             type: 'array',
             items: { type: 'string' },
-            description: 'Log levels to filter (error, warning, display, verbose, veryverbose)',
+            description: 'Log levels to filter (fatal, error, warning, display, verbose, veryverbose)',
           },
           categories: {
             type: 'array',
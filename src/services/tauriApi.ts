@@ -5,9 +5,34 @@ import type {
   LogChunk,
   FileIndex,
   SearchResult,
+  SearchResults,
+  SearchHitWithContext,
   SearchOptions,
   OpenFileResult,
+  OpenMergedResult,
+  LogFileInfo,
+  IndexStats,
   LogLevel,
+  ParserConfig,
+  UnparsedSample,
+  EntryField,
+  ProjectedChunk,
+  CategorySample,
+  FilterOptions,
+  LogEntry,
+  HighlightSpan,
+  ExportFormat,
+  LevelHistogram,
+  SessionBoundary,
+  Bookmark,
+  DiffResult,
+  MessageGroup,
+  TimeBucket,
+  CallStack,
+  ParseStats,
+  ParseGroupMap,
+  PreviewMode,
+  NextMatch,
 } from '../types/log';
 
 /// 过滤行结果
@@ -16,9 +41,79 @@ export interface FilteredLinesResult {
   total_count: number;
 }
 
+/// 过滤后的日志块
+export interface FilteredChunk {
+  start_line: number;
+  end_line: number;
+  entries: LogEntry[];
+  total_scanned: number;
+}
+
 /// 打开日志文件
-export async function openLogFile(path: string): Promise<OpenFileResult> {
-  return invoke<OpenFileResult>('open_log_file', { path });
+export async function openLogFile(
+  path: string,
+  previewMode: PreviewMode = 'head'
+): Promise<OpenFileResult> {
+  return invoke<OpenFileResult>('open_log_file', { path, previewMode });
+}
+
+/// 在后台打开日志文件 (大文件)，进度通过 index-progress 事件发送，
+/// 完成后发送 index-done (OpenFileResult)，失败时发送 index-error
+export async function openLogFileWithProgress(
+  path: string,
+  previewMode: PreviewMode = 'head'
+): Promise<void> {
+  return invoke('open_log_file_with_progress', { path, previewMode });
+}
+
+/// 按时间顺序合并打开多个日志文件 (如 Backup/ 中拆分的日志)
+export async function openMergedFiles(paths: string[]): Promise<OpenMergedResult> {
+  return invoke<OpenMergedResult>('open_merged_files', { paths });
+}
+
+/// 加载合并视图的日志块 (条目带 source_file)
+export async function loadMergedChunk(startLine: number, endLine: number): Promise<LogChunk> {
+  return invoke<LogChunk>('load_merged_chunk', { startLine, endLine });
+}
+
+/// 在后台预先构建并持久化索引 (最近文件预热)
+export async function precomputeIndex(path: string): Promise<void> {
+  return invoke('precompute_index', { path });
+}
+
+/// 构建一次索引并返回耗时和吞吐量 (性能诊断，不影响当前文件)
+export async function indexBenchmark(path: string): Promise<IndexStats> {
+  return invoke<IndexStats>('index_benchmark', { path });
+}
+
+/// 列出目录中的日志文件 (按修改时间降序，行数为估算值)
+export async function discoverLogs(dir: string, recursive: boolean): Promise<LogFileInfo[]> {
+  return invoke<LogFileInfo[]>('discover_logs', { dir, recursive });
+}
+
+/// 增量索引当前文件新追加的行 (返回新增行数)
+export async function refreshFile(): Promise<number> {
+  return invoke<number>('refresh_file');
+}
+
+/// 开始跟踪当前文件，文件增长时发送 file-grew 事件
+export async function startTailing(pollMs: number): Promise<void> {
+  return invoke('start_tailing', { pollMs });
+}
+
+/// 停止跟踪当前文件
+export async function stopTailing(): Promise<void> {
+  return invoke('stop_tailing');
+}
+
+/// 监视目录，出现新的 .log 或已有的 .log 明显增长时发送 new-log-file 事件 (LogFileInfo)
+export async function watchDirectory(dir: string): Promise<void> {
+  return invoke('watch_directory', { dir });
+}
+
+/// 停止监视目录
+export async function stopWatching(): Promise<void> {
+  return invoke('stop_watching');
 }
 
 /// 加载日志块
@@ -26,6 +121,50 @@ export async function loadChunk(startLine: number, endLine: number): Promise<Log
   return invoke<LogChunk>('load_chunk', { startLine, endLine });
 }
 
+/// 读取末尾 count 行 (打开大文件时代替开头预览)
+export async function loadTail(count: number): Promise<LogChunk> {
+  return invoke<LogChunk>('load_tail', { count });
+}
+
+/// 读取某行前后各 radius 行 (上下文预览)
+export async function peekAround(lineNumber: number, radius: number): Promise<LogChunk> {
+  return invoke<LogChunk>('peek_around', { lineNumber, radius });
+}
+
+/// 加载日志块 (续行合并到所属日志行)
+export async function loadChunkMerged(startLine: number, endLine: number): Promise<LogChunk> {
+  return invoke<LogChunk>('load_chunk_merged', { startLine, endLine });
+}
+
+/// 加载覆盖字节范围 [startByte, endByte) 的日志行
+export async function loadByteRange(startByte: number, endByte: number): Promise<LogChunk> {
+  return invoke<LogChunk>('load_byte_range', { startByte, endByte });
+}
+
+/// 某行起始的字节偏移 (行号越界时为 null)
+export async function lineToByteOffset(lineNumber: number): Promise<number | null> {
+  return invoke<number | null>('line_to_byte_offset', { lineNumber });
+}
+
+/// 字节偏移所在的行号
+export async function byteOffsetToLine(offset: number): Promise<number> {
+  return invoke<number>('byte_offset_to_line', { offset });
+}
+
+/// 加载日志块 (相邻的相同行折叠为一条)
+export async function loadChunkDeduped(startLine: number, endLine: number): Promise<LogChunk> {
+  return invoke<LogChunk>('load_chunk_deduped', { startLine, endLine });
+}
+
+/// 加载日志块 (列式，只返回请求的字段)
+export async function loadChunkProjected(
+  startLine: number,
+  endLine: number,
+  fields: EntryField[]
+): Promise<ProjectedChunk> {
+  return invoke<ProjectedChunk>('load_chunk_projected', { startLine, endLine, fields });
+}
+
 /// 获取文件索引
 export async function getFileIndex(): Promise<FileIndex | null> {
   return invoke<FileIndex | null>('get_file_index');
@@ -36,9 +175,47 @@ export async function closeFile(): Promise<void> {
   return invoke('close_file');
 }
 
-/// 搜索日志
-export async function searchLogs(options: SearchOptions): Promise<SearchResult[]> {
-  return invoke<SearchResult[]>('search_logs', { options });
+/// 搜索日志 (结果超过 max_results 时 truncated 为 true)
+export async function searchLogs(options: SearchOptions): Promise<SearchResults> {
+  return invoke<SearchResults>('search_logs', { options });
+}
+
+/// 在合并打开的所有文件中搜索 (结果带 source_file，共享 maxResults 上限)
+export async function searchAllFiles(options: SearchOptions): Promise<SearchResults> {
+  return invoke<SearchResults>('search_all_files', { options });
+}
+
+/// 只在指定类别的行中搜索 (已构建类别行号索引时只读取这些行)
+export async function searchInCategory(
+  category: string,
+  options: SearchOptions
+): Promise<SearchResults> {
+  return invoke<SearchResults>('search_in_category', { category, options });
+}
+
+/// 分批搜索: 结果按行号顺序通过 search-batch 事件发送，结束后发送 search-complete
+export async function searchStreaming(options: SearchOptions): Promise<void> {
+  return invoke('search_streaming', { options });
+}
+
+/// 取消正在进行的搜索 (searchLogs 返回已找到的结果，cancelled 为 true)
+export async function cancelSearch(): Promise<void> {
+  return invoke('cancel_search');
+}
+
+/// 搜索日志，每个命中附带上下文行
+export async function searchWithContext(options: SearchOptions): Promise<SearchHitWithContext[]> {
+  return invoke<SearchHitWithContext[]>('search_with_context', { options });
+}
+
+/// 统计匹配总数
+export async function countMatches(options: SearchOptions): Promise<number> {
+  return invoke<number>('count_matches', { options });
+}
+
+/// 获取有匹配的行号 (升序去重，用于搜索小地图)
+export async function searchMatchingLines(options: SearchOptions): Promise<number[]> {
+  return invoke<number[]>('search_matching_lines', { options });
 }
 
 /// 搜索下一页
@@ -50,6 +227,38 @@ export async function searchNext(
   return invoke<SearchResult[]>('search_next', { fromLine, maxResults, options });
 }
 
+/// 查找 fromLine 之后的下一个匹配行 (wrap 时到达末尾后从开头继续)
+export async function findNext(
+  fromLine: number,
+  options: SearchOptions,
+  wrap: boolean
+): Promise<NextMatch> {
+  return invoke<NextMatch>('find_next', { fromLine, options, wrap });
+}
+
+/// 查找 fromLine 之前的上一个匹配行 (wrap 时到达开头后从末尾继续)
+export async function findPrev(
+  fromLine: number,
+  options: SearchOptions,
+  wrap: boolean
+): Promise<NextMatch> {
+  return invoke<NextMatch>('find_prev', { fromLine, options, wrap });
+}
+
+/// 搜索上一页 (从 fromLine 向前)
+export async function searchPrev(
+  fromLine: number,
+  maxResults: number,
+  options: SearchOptions
+): Promise<SearchResult[]> {
+  return invoke<SearchResult[]>('search_prev', { fromLine, maxResults, options });
+}
+
+/// 校验正则表达式 (无效时以 RegexErrorInfo 拒绝)
+export async function validateRegex(pattern: string): Promise<void> {
+  return invoke<void>('validate_regex', { pattern });
+}
+
 /// 测试正则表达式
 export async function testRegex(
   pattern: string,
@@ -59,13 +268,225 @@ export async function testRegex(
   return invoke<SearchResult[]>('test_regex', { pattern, text, caseInsensitive });
 }
 
-/// 获取过滤后的行号
+/// 获取过滤后的行号 (minLevel: 只保留不低于该级别的行)
 export async function getFilteredLines(
   levels: LogLevel[],
-  categories: string[]
+  categories: string[],
+  minLevel?: LogLevel
 ): Promise<FilteredLinesResult> {
   return invoke<FilteredLinesResult>('get_filtered_lines', { 
     levels, 
-    categories 
+    categories,
+    minLevel
   });
 }
+
+/// 读取行范围并按过滤选项筛选
+export async function filterLogs(
+  options: FilterOptions,
+  startLine: number,
+  endLine: number
+): Promise<FilteredChunk> {
+  return invoke<FilteredChunk>('filter_logs', { options, startLine, endLine });
+}
+
+/// 获取各区域的主导日志级别 (滚动条热力图)
+export async function regionLevels(regions: number): Promise<LogLevel[]> {
+  return invoke<LogLevel[]>('region_levels', { regions });
+}
+
+/// 设置解析器配置
+export async function setParserConfig(config: ParserConfig): Promise<void> {
+  return invoke('set_parser_config', { config });
+}
+
+/// 设置自定义行格式 (传入空字符串清除)
+export async function setCustomPattern(regex: string, groupMap: ParseGroupMap): Promise<void> {
+  return invoke('set_custom_pattern', { regex, groupMap });
+}
+
+/// 按当前解析器配置重新解析日志块
+export async function reparseChunk(startLine: number, endLine: number): Promise<LogChunk> {
+  return invoke<LogChunk>('reparse_chunk', { startLine, endLine });
+}
+
+/// 采样未能解析的行
+export async function scanUnparsed(limit: number): Promise<UnparsedSample[]> {
+  return invoke<UnparsedSample[]>('scan_unparsed', { limit });
+}
+
+/// 统计各解析分支命中的行数，用于发现尚未支持的日志格式
+export async function parseStats(): Promise<ParseStats> {
+  return invoke<ParseStats>('parse_stats');
+}
+
+/// 计算可折叠的行范围 (只显示匹配及其上下文)
+export async function searchFoldRanges(
+  options: SearchOptions,
+  context: number
+): Promise<[number, number][]> {
+  return invoke<[number, number][]>('search_fold_ranges', { options, context });
+}
+
+/// 获取第一个带时间戳行的时间 (Unix 毫秒)
+export async function getLogStartTime(): Promise<number | null> {
+  return invoke<number | null>('get_log_start_time');
+}
+
+/// 获取最后一个带时间戳行的时间 (Unix 毫秒)
+export async function getLogEndTime(): Promise<number | null> {
+  return invoke<number | null>('get_log_end_time');
+}
+
+/// 获取日志的时间跨度 (第一个与最后一个时间戳)
+export async function getTimeRange(): Promise<[string, string] | null> {
+  return invoke<[string, string] | null>('get_time_range');
+}
+
+/// 跳转到指定时间 (返回第一个不早于该时间的行号)
+export async function gotoTimestamp(timestamp: string): Promise<number | null> {
+  return invoke<number | null>('goto_timestamp', { timestamp });
+}
+
+/// 获取帧号范围 (最小, 最大)
+export async function getFrameRange(): Promise<[number, number] | null> {
+  return invoke<[number, number] | null>('get_frame_range');
+}
+
+/// 跳转到指定帧，返回第一个帧号不小于 frame 的行号
+export async function gotoFrame(frame: number): Promise<number | null> {
+  return invoke<number | null>('goto_frame', { frame });
+}
+
+/// 检测时间戳回退的会话边界
+export async function detectSessionResets(): Promise<number[]> {
+  return invoke<number[]>('detect_session_resets');
+}
+
+/// 检测会话边界 (文件头或帧号重置)
+export async function getSessions(): Promise<SessionBoundary[]> {
+  return invoke<SessionBoundary[]>('get_sessions');
+}
+
+/// 在多行样本中批量测试正则表达式
+export async function testRegexBatch(
+  pattern: string,
+  texts: string[],
+  caseInsensitive: boolean
+): Promise<SearchResult[][]> {
+  return invoke<SearchResult[][]>('test_regex_batch', { pattern, texts, caseInsensitive });
+}
+
+/// 设置顺序读取的缓冲区大小 (字节)
+export async function setIoBufferSize(bytes: number): Promise<number> {
+  return invoke<number>('set_io_buffer_size', { bytes });
+}
+
+/// 采样发现日志类别 (不做全量扫描)
+export async function discoverCategoriesSampled(maxLines: number): Promise<CategorySample> {
+  return invoke<CategorySample>('discover_categories_sampled', { maxLines });
+}
+
+/// 下一个与指定行同类别的日志行
+export async function nextSameCategory(lineNumber: number): Promise<number | null> {
+  return invoke<number | null>('next_same_category', { lineNumber });
+}
+
+/// 上一个与指定行同类别的日志行
+export async function prevSameCategory(lineNumber: number): Promise<number | null> {
+  return invoke<number | null>('prev_same_category', { lineNumber });
+}
+
+/// 获取某个类别出现的行号 (用于小地图)
+export async function getCategoryLines(category: string): Promise<number[]> {
+  return invoke<number[]>('get_category_lines', { category });
+}
+
+/// 按出现次数降序获取日志类别 (未打开文件时为空)
+export async function getCategoriesSorted(): Promise<[string, number][]> {
+  return invoke<[string, number][]>('get_categories_sorted');
+}
+
+/// 获取各级别日志数量
+export async function getLevelHistogram(): Promise<LevelHistogram> {
+  return invoke<LevelHistogram>('get_level_histogram');
+}
+
+/// 计算一行的高亮片段
+export async function highlightLine(text: string): Promise<HighlightSpan[]> {
+  return invoke<HighlightSpan[]>('highlight_line', { text });
+}
+
+/// 设置高亮模式 ([类型, 正则] 列表，空列表恢复默认)
+export async function setHighlightPatterns(patterns: [string, string][]): Promise<void> {
+  return invoke<void>('set_highlight_patterns', { patterns });
+}
+
+/// 导出行范围内的原始行 (可选过滤)，返回写入的行数
+export async function exportRange(
+  startLine: number,
+  endLine: number,
+  filter: FilterOptions | null,
+  destPath: string
+): Promise<number> {
+  return invoke<number>('export_range', { startLine, endLine, filter, destPath });
+}
+
+/// 将行范围内解析后的条目导出为 CSV 或 NDJSON，返回写入的条目数
+export async function exportStructured(
+  startLine: number,
+  endLine: number,
+  format: ExportFormat,
+  destPath: string
+): Promise<number> {
+  return invoke<number>('export_structured', { startLine, endLine, format, destPath });
+}
+
+/// 为当前文件添加书签 (已有书签时更新备注)
+export async function addBookmark(lineNumber: number, note: string): Promise<void> {
+  return invoke<void>('add_bookmark', { lineNumber, note });
+}
+
+/// 删除当前文件某行的书签 (返回是否存在)
+export async function removeBookmark(lineNumber: number): Promise<boolean> {
+  return invoke<boolean>('remove_bookmark', { lineNumber });
+}
+
+/// 列出当前文件的书签
+export async function listBookmarks(): Promise<Bookmark[]> {
+  return invoke<Bookmark[]>('list_bookmarks');
+}
+
+/// 逐行比较两个日志文件 (ignoreTimestamps 时忽略行首的时间戳和帧号)
+export async function diffFiles(
+  pathA: string,
+  pathB: string,
+  ignoreTimestamps: boolean
+): Promise<DiffResult> {
+  return invoke<DiffResult>('diff_files', { pathA, pathB, ignoreTimestamps });
+}
+
+/// 按归一化消息聚合日志 (数字、路径、UUID 视为相同)，返回次数最多的 topN 组
+export async function groupMessages(levelFilter: LogLevel[], topN: number): Promise<MessageGroup[]> {
+  return invoke<MessageGroup[]>('group_messages', { levelFilter, topN });
+}
+
+/// 按固定时间窗口统计各类别的条目数 (时间线图表)
+export async function categoryTimeline(bucketSeconds: number): Promise<TimeBucket[]> {
+  return invoke<TimeBucket[]>('category_timeline', { bucketSeconds });
+}
+
+/// 找出文件中的崩溃调用栈
+export async function getCallstacks(): Promise<CallStack[]> {
+  return invoke<CallStack[]>('get_callstacks');
+}
+
+/// 列出文件中的 ensure 失败行 (非致命)
+export async function listEnsures(): Promise<LogEntry[]> {
+  return invoke<LogEntry[]>('list_ensures');
+}
+
+/// 提取文件中出现的路径及其出现的行数 (按行数降序)
+export async function extractPaths(dedupe: boolean, assetOnly: boolean): Promise<[string, number][]> {
+  return invoke<[string, number][]>('extract_paths', { dedupe, assetOnly });
+}
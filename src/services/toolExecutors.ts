@@ -53,7 +53,7 @@ export async function executeSearchLogs(
   caseInsensitive: boolean = true
 ): Promise<ToolResult> {
   try {
-    const results = await tauriApi.searchLogs({
+    const { results, truncated } = await tauriApi.searchLogs({
       pattern,
       use_regex: useRegex,
       case_insensitive: caseInsensitive,
@@ -68,8 +68,9 @@ export async function executeSearchLogs(
           line: r.line_number,
           matchedText: r.matched_text.slice(0, 200),
         })),
-        hasMore: results.length > 50,
+        hasMore: truncated || results.length > 50,
         totalMatches: results.length,
+        truncated,
       },
     };
   } catch (e) {
@@ -73,7 +73,7 @@ export const useFilterStore = create<FilterState>((set) => ({
 
   selectAllLevels: () => {
     set({
-      selectedLevels: new Set(['error', 'warning', 'display', 'verbose', 'veryverbose', 'unknown']),
+      selectedLevels: new Set(['fatal', 'error', 'warning', 'display', 'verbose', 'veryverbose', 'unknown']),
     });
   },
 
@@ -25,6 +25,7 @@ interface LogState {
   // 搜索
   searchOptions: SearchOptions;
   searchResults: SearchResult[];
+  searchTruncated: boolean;
   currentSearchIndex: number;
 
   // 过滤
@@ -89,6 +90,7 @@ export const useLogStore = create<LogState>((set, get) => ({
     case_insensitive: true,
   },
   searchResults: [],
+  searchTruncated: false,
   currentSearchIndex: -1,
   filterOptions: {
     categories: [],
@@ -120,6 +122,7 @@ export const useLogStore = create<LogState>((set, get) => ({
         entriesMap: newMap,
         loadedRanges: [{ start: 1, end: result.preview.length }],
         searchResults: [],
+        searchTruncated: false,
         currentSearchIndex: -1,
         filteredLines: [],
         showFilteredOnly: false,
@@ -140,6 +143,7 @@ export const useLogStore = create<LogState>((set, get) => ({
         entriesMap: {},
         loadedRanges: [],
         searchResults: [],
+        searchTruncated: false,
         currentSearchIndex: -1,
         filteredLines: [],
         showFilteredOnly: false,
@@ -252,13 +256,14 @@ export const useLogStore = create<LogState>((set, get) => ({
       currentSearchIndex: -1,
     });
     try {
-      const results = await api.searchLogs(options);
+      const { results, truncated } = await api.searchLogs(options);
       set({
         searchResults: results,
+        searchTruncated: truncated,
         currentSearchIndex: results.length > 0 ? 0 : -1,
       });
     } catch (e) {
-      set({ error: String(e), searchResults: [] });
+      set({ error: String(e), searchResults: [], searchTruncated: false });
     } finally {
       set({ isLoading: false });
     }
@@ -1,27 +1,74 @@
 //! 日志类型定义 (前端)
 
 /// 日志级别
-export type LogLevel = 'error' | 'warning' | 'display' | 'verbose' | 'veryverbose' | 'unknown';
+export type LogLevel = 'fatal' | 'error' | 'warning' | 'display' | 'verbose' | 'veryverbose' | 'unknown';
+
+/// 解析器配置
+export interface ParserConfig {
+  level_aliases: Record<string, LogLevel>;
+  continuation?: ContinuationRule;
+}
+
+/// 续行判定规则 (默认: 以空格或 > 开头的行和空行是续行)
+export interface ContinuationRule {
+  tab_indent: boolean;
+  blank_line_separate: boolean;
+}
+
+/// 自定义格式中各字段对应的命名捕获组
+export interface ParseGroupMap {
+  timestamp?: string;
+  frame?: string;
+  category?: string;
+  level?: string;
+  message?: string;
+}
+
+/// 解析后的 UE 时间戳
+export interface Timestamp {
+  year: number;
+  month: number;
+  day: number;
+  hour: number;
+  minute: number;
+  second: number;
+  millis: number;
+}
 
 /// 日志条目
 export interface LogEntry {
   line_number: number;
   raw: string;
   timestamp?: string;
+  parsed_timestamp?: Timestamp;
   frame?: number;
   category?: string;
   level: LogLevel;
   message?: string;
+  sub_tag?: string;
   is_continuation: boolean;
+  is_ensure: boolean;
+  has_invalid_utf8: boolean;
+  continuation_count: number;
+  repeat_count: number;
+  source_file?: string;
 }
 
+/// 文件编码 (根据 BOM 检测)
+export type Encoding = 'utf8' | 'utf8_bom' | 'utf16_le' | 'utf16_be';
+
 /// 文件索引
 export interface FileIndex {
   file_path: string;
   total_lines: number;
   file_size: number;
+  encoding: Encoding;
+  modified_time: number;
+  content_hash?: number;
+  last_indexed_offset: number;
   line_offsets: number[];
   index_interval: number;
+  dense_offsets: boolean;
   categories: Record<string, number>;
   level_counts: Record<string, number>;
 }
@@ -31,6 +78,45 @@ export interface LogChunk {
   start_line: number;
   end_line: number;
   entries: LogEntry[];
+  read_errors: [number, string][];
+}
+
+/// 文件增长事件 (file-grew)
+export interface FileGrewEvent {
+  total_lines: number;
+  chunk: LogChunk;
+}
+
+/// 索引进度事件 (index-progress)
+export interface IndexProgressEvent {
+  bytes_done: number;
+  bytes_total: number;
+}
+
+/// 可投影的日志条目字段
+export type EntryField =
+  | 'line_number'
+  | 'raw'
+  | 'timestamp'
+  | 'frame'
+  | 'category'
+  | 'level'
+  | 'message'
+  | 'is_continuation';
+
+/// 列式日志块 (只包含请求的字段)
+export interface ProjectedChunk {
+  start_line: number;
+  end_line: number;
+  len: number;
+  line_numbers?: number[];
+  raw?: string[];
+  timestamps?: (string | null)[];
+  frames?: (number | null)[];
+  categories?: (string | null)[];
+  levels?: LogLevel[];
+  messages?: (string | null)[];
+  is_continuation?: boolean[];
 }
 
 /// 搜索结果
@@ -39,27 +125,192 @@ export interface SearchResult {
   matched_text: string;
   start: number;
   end: number;
+  char_start: number;
+  char_end: number;
+  level: LogLevel;
+  category?: string;
+  captures?: Record<string, string>;
+  source_file?: string;
 }
 
+/// 文件搜索结果 (超过结果数上限时截断)
+export interface SearchResults {
+  results: SearchResult[];
+  truncated: boolean;
+  cancelled: boolean;
+}
+
+/// 分批搜索完成事件 (search-complete)
+export interface SearchComplete {
+  total: number;
+  truncated: boolean;
+  cancelled: boolean;
+}
+
+/// 带上下文的搜索命中
+export interface SearchHitWithContext {
+  result: SearchResult;
+  before: LogEntry[];
+  after: LogEntry[];
+}
+
+/// 正则表达式校验错误
+export interface RegexErrorInfo {
+  message: string;
+  offset?: number;
+}
+
+/// 搜索结果排序方式
+export type ResultSort = 'line_asc' | 'line_desc' | 'level_then_line';
+
 /// 搜索选项
 export interface SearchOptions {
   pattern: string;
   use_regex: boolean;
   case_insensitive: boolean;
+  whole_word?: boolean;
+  ascii_case_insensitive?: boolean;
   start_line?: number;
   end_line?: number;
+  sort?: ResultSort;
+  level_filter?: LogLevel[];
+  category_filter?: string[];
+  context_lines?: number;
+  max_results?: number;
+  search_merged?: boolean;
 }
 
 /// 过滤选项
 export interface FilterOptions {
   categories: string[];
   levels: LogLevel[];
+  min_level?: LogLevel;
   exclude_categories: string[];
+  sub_tags?: string[];
+}
+
+/// 采样得到的日志类别
+export interface CategorySample {
+  categories: string[];
+  complete: boolean;
+  lines_scanned: number;
+}
+
+/// 未能解析的行样本
+export interface UnparsedSample {
+  line_number: number;
+  raw: string;
+}
+
+/// 会话边界
+export interface SessionBoundary {
+  start_line: number;
+  timestamp?: string;
+}
+
+/// 书签
+export interface Bookmark {
+  line_number: number;
+  note: string;
+}
+
+/// 差异块类型
+export type DiffKind = 'added' | 'removed' | 'equal';
+
+/// 差异块 (Removed 的 lines 取自 A，Added 取自 B，Equal 为空)
+export interface DiffBlock {
+  kind: DiffKind;
+  a_start: number;
+  a_count: number;
+  b_start: number;
+  b_count: number;
+  lines: string[];
+}
+
+/// 两个文件的行级差异
+export interface DiffResult {
+  blocks: DiffBlock[];
+  a_total_lines: number;
+  b_total_lines: number;
+  approximate: boolean;
+}
+
+/// 按归一化消息聚合的日志组
+export interface MessageGroup {
+  normalized_message: string;
+  count: number;
+  first_line: number;
+  sample: string;
+}
+
+/// 时间窗口内各类别的条目数
+export interface TimeBucket {
+  start_timestamp: string;
+  counts: Record<string, number>;
+}
+
+/// 调用栈中的一帧 (无法识别格式时只有 raw)
+export interface StackFrame {
+  line_number: number;
+  raw: string;
+  module?: string;
+  function?: string;
+  offset?: string;
+}
+
+/// 崩溃调用栈
+export interface CallStack {
+  start_line: number;
+  frames: StackFrame[];
+  message: string;
+}
+
+/// 各解析分支命中的行数
+export interface ParseStats {
+  custom: number;
+  standard: number;
+  simple: number;
+  header: number;
+  continuation: number;
+  raw: number;
+}
+
+/// 打开文件时预览的位置
+export type PreviewMode = 'head' | 'tail';
+
+/// 跳转到下一个/上一个匹配的结果 (wrapped 表示越过文件末尾或开头后找到)
+export type NextMatch =
+  | { kind: 'found'; line: number }
+  | { kind: 'wrapped'; line: number }
+  | { kind: 'none' };
+
+/// 目录扫描发现的日志文件
+export interface LogFileInfo {
+  path: string;
+  size: number;
+  modified: number;
+  line_count_estimate: number;
+}
+
+/// 索引构建耗时与吞吐量
+export interface IndexStats {
+  bytes: number;
+  lines: number;
+  elapsed_ms: number;
+  mb_per_sec: number;
 }
 
 /// 文件打开结果
 export interface OpenFileResult {
   index: FileIndex;
+  encoding: Encoding;
+  preview: LogEntry[];
+}
+
+/// 多文件合并打开结果
+export interface OpenMergedResult {
+  total_lines: number;
+  sources: string[];
   preview: LogEntry[];
 }
 
@@ -68,3 +319,25 @@ export interface LogParserError {
   message: string;
   code: string;
 }
+
+/// 高亮片段 (字节偏移，默认类型为 path/uuid/number)
+export interface HighlightSpan {
+  start: number;
+  end: number;
+  kind: string;
+  normalized?: string;
+}
+
+/// 结构化导出格式
+export type ExportFormat = 'csv' | 'ndjson';
+
+/// 各级别日志数量
+export interface LevelHistogram {
+  fatal: number;
+  error: number;
+  warning: number;
+  display: number;
+  verbose: number;
+  very_verbose: number;
+  unknown: number;
+}